@@ -0,0 +1,23 @@
+-- Full text ranked search (see Database::search)
+CREATE EXTENSION IF NOT EXISTS pg_trgm WITH SCHEMA public;
+
+ALTER TABLE bots ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(username_cached, '')), 'A')
+    || setweight(to_tsvector('english', coalesce(description, '')), 'B')
+    || setweight(to_tsvector('english', coalesce(long_description, '')), 'C')
+) STORED;
+
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', coalesce(name_cached, '')), 'A')
+    || setweight(to_tsvector('english', coalesce(description, '')), 'B')
+    || setweight(to_tsvector('english', coalesce(long_description, '')), 'C')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS bots_search_vector_idx ON bots USING gin (search_vector);
+CREATE INDEX IF NOT EXISTS servers_search_vector_idx ON servers USING gin (search_vector);
+
+-- Trigram indexes for typo tolerance
+CREATE INDEX IF NOT EXISTS bots_username_cached_trgm_idx ON bots USING gin (username_cached gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS servers_name_cached_trgm_idx ON servers USING gin (name_cached gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS users_username_trgm_idx ON users USING gin (username gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS bot_packs_name_trgm_idx ON bot_packs USING gin (name gin_trgm_ops);
@@ -0,0 +1,5 @@
+-- Faceted search filters (see Database::search)
+CREATE INDEX IF NOT EXISTS bots_features_idx ON bots USING gin (features);
+CREATE INDEX IF NOT EXISTS bots_flags_idx ON bots USING gin (flags);
+CREATE INDEX IF NOT EXISTS servers_tags_idx ON servers USING gin (tags);
+CREATE INDEX IF NOT EXISTS reviews_target_idx ON reviews (target_id, target_type) WHERE parent_id IS NULL;
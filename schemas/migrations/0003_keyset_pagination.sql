@@ -0,0 +1,4 @@
+-- Indexes backing cursor (keyset) pagination
+CREATE INDEX IF NOT EXISTS bots_votes_keyset_idx ON bots (state, (coalesce(votes, 0)) DESC, bot_id DESC);
+CREATE INDEX IF NOT EXISTS servers_votes_keyset_idx ON servers (state, (coalesce(votes, 0)) DESC, guild_id DESC);
+CREATE INDEX IF NOT EXISTS reviews_keyset_idx ON reviews (target_id, target_type, (coalesce(epoch[1], 0)) DESC, id DESC) WHERE parent_id IS NULL;
@@ -0,0 +1,20 @@
+-- Durable outbound webhook queue and delivery log
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    target_id bigint NOT NULL,
+    target_type integer NOT NULL,
+    eid text NOT NULL,
+    url text NOT NULL,
+    payload jsonb NOT NULL,
+    status integer NOT NULL DEFAULT 0,
+    attempts integer NOT NULL DEFAULT 0,
+    last_status_code integer,
+    last_error text,
+    next_attempt_at timestamptz NOT NULL DEFAULT NOW(),
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    delivered_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt_at) WHERE status = 0;
+CREATE INDEX IF NOT EXISTS webhook_deliveries_target_idx ON webhook_deliveries (target_id, target_type, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS webhook_deliveries_eid_idx ON webhook_deliveries (target_id, target_type, eid);
@@ -0,0 +1,4 @@
+-- Webhook event subscription masks, see EventName::webhook_bit for the bit of each event
+-- Defaults to BotVote (bit 0) and ServerVote (bit 28) only, which was the behaviour before event webhooks
+ALTER TABLE bots ADD COLUMN IF NOT EXISTS webhook_events bigint NOT NULL DEFAULT 268435457;
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS webhook_events bigint NOT NULL DEFAULT 268435457;
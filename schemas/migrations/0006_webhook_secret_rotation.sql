@@ -0,0 +1,3 @@
+-- Secondary webhook secret, set while a webhook secret is being rotated
+ALTER TABLE bots ADD COLUMN IF NOT EXISTS webhook_secret_secondary text;
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS webhook_secret_secondary text;
@@ -0,0 +1,11 @@
+-- Per bot/server websocket event sequence numbers used by RESUME
+CREATE TABLE IF NOT EXISTS ws_event_seqs (
+    id bigint NOT NULL,
+    type text NOT NULL,
+    seq bigint NOT NULL DEFAULT 0,
+    PRIMARY KEY (id, type)
+);
+
+ALTER TABLE ws_events ADD COLUMN IF NOT EXISTS seq bigint;
+
+CREATE INDEX IF NOT EXISTS ws_events_seq_idx ON ws_events (id, type, seq);
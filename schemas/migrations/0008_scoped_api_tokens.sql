@@ -0,0 +1,17 @@
+-- Named API tokens with scopes, expiry and last used tracking
+--
+-- Only the SHA-256 of the token is stored. Tokens in the api_token columns of
+-- users/bots keep working as full scope tokens
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    target_id bigint NOT NULL,
+    token_type integer NOT NULL,
+    name text NOT NULL,
+    token_hash text NOT NULL UNIQUE,
+    scopes text[] NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    expires_at timestamptz,
+    last_used_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS api_tokens_target_idx ON api_tokens (target_id, token_type, created_at DESC);
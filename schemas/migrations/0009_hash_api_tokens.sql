@@ -0,0 +1,58 @@
+-- Store user, bot and server tokens as salted hashes instead of plaintext
+--
+-- api_token_prefix is the first 8 characters of the token and is used to find the row. The
+-- token is then checked against api_token_hash (salt$hash, hex encoded SHA-256 of the salt
+-- followed by the token) in constant time. Tokens still in api_token (such as ones set by other
+-- services) are hashed the first time they are used
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS api_token_prefix text;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS api_token_hash text;
+ALTER TABLE users ALTER COLUMN api_token DROP NOT NULL;
+
+ALTER TABLE bots ADD COLUMN IF NOT EXISTS api_token_prefix text;
+ALTER TABLE bots ADD COLUMN IF NOT EXISTS api_token_hash text;
+ALTER TABLE bots ALTER COLUMN api_token DROP NOT NULL;
+
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS api_token_prefix text;
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS api_token_hash text;
+ALTER TABLE servers ALTER COLUMN api_token DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS users_api_token_prefix_idx ON users (api_token_prefix);
+CREATE INDEX IF NOT EXISTS bots_api_token_prefix_idx ON bots (api_token_prefix);
+CREATE INDEX IF NOT EXISTS servers_api_token_prefix_idx ON servers (api_token_prefix);
+
+-- Webhooks used to be signed using the API token when no webhook secret was set. The token is never
+-- copied over, bots and servers without a secret get a new one on their next webhook (see get_webhook_secret)
+
+-- Hash existing tokens
+WITH salted AS (
+    SELECT user_id, gen_random_bytes(16) AS salt FROM users WHERE api_token IS NOT NULL
+)
+UPDATE users SET
+    api_token_prefix = left(users.api_token, 8),
+    api_token_hash = encode(salted.salt, 'hex') || '$' || encode(digest(salted.salt || convert_to(users.api_token, 'UTF8'), 'sha256'), 'hex'),
+    api_token = NULL
+FROM salted WHERE users.user_id = salted.user_id;
+
+WITH salted AS (
+    SELECT bot_id, gen_random_bytes(16) AS salt FROM bots WHERE api_token IS NOT NULL
+)
+UPDATE bots SET
+    api_token_prefix = left(bots.api_token, 8),
+    api_token_hash = encode(salted.salt, 'hex') || '$' || encode(digest(salted.salt || convert_to(bots.api_token, 'UTF8'), 'sha256'), 'hex'),
+    api_token = NULL
+FROM salted WHERE bots.bot_id = salted.bot_id;
+
+WITH salted AS (
+    SELECT guild_id, gen_random_bytes(16) AS salt FROM servers WHERE api_token IS NOT NULL
+)
+UPDATE servers SET
+    api_token_prefix = left(servers.api_token, 8),
+    api_token_hash = encode(salted.salt, 'hex') || '$' || encode(digest(salted.salt || convert_to(servers.api_token, 'UTF8'), 'sha256'), 'hex'),
+    api_token = NULL
+FROM salted WHERE servers.guild_id = salted.guild_id;
+
+-- Logging in now creates a full access token in api_tokens (one per login) as the user token
+-- can no longer be sent back. These have no scopes
+ALTER TABLE api_tokens ALTER COLUMN scopes DROP NOT NULL;
@@ -0,0 +1,30 @@
+-- OAuth2 authorization code (with PKCE) flow for Frostpaw clients
+--
+-- Codes, access tokens and refresh tokens are stored as their SHA-256. Codes are single use and
+-- deleted when exchanged. Refresh tokens are rotated on every use. Access tokens with NULL scopes
+-- come from the older claw login and have full access
+CREATE TABLE IF NOT EXISTS oauth2_codes (
+    code_hash text PRIMARY KEY,
+    client_id text NOT NULL,
+    user_id bigint NOT NULL,
+    redirect_uri text NOT NULL,
+    scopes text[] NOT NULL,
+    code_challenge text NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    expires_at timestamptz NOT NULL DEFAULT NOW() + interval '10 minutes'
+);
+
+CREATE TABLE IF NOT EXISTS oauth2_tokens (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    client_id text NOT NULL,
+    user_id bigint NOT NULL,
+    access_token_hash text NOT NULL UNIQUE,
+    refresh_token_hash text UNIQUE,
+    scopes text[],
+    access_expires_at timestamptz NOT NULL,
+    refresh_expires_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    last_used_at timestamptz
+);
+
+CREATE INDEX IF NOT EXISTS oauth2_tokens_user_idx ON oauth2_tokens (user_id, client_id);
@@ -0,0 +1,13 @@
+-- Frostpaw clients managed by their owners through the dev portal
+--
+-- redirect_domains replaces the allowed_oauth2 list in the config. verification_state replaces
+-- verified (which is kept in sync for older clients) so clients can be submitted to staff
+ALTER TABLE frostpaw_clients ADD COLUMN IF NOT EXISTS redirect_domains text[] NOT NULL DEFAULT '{}';
+ALTER TABLE frostpaw_clients ADD COLUMN IF NOT EXISTS verification_state integer NOT NULL DEFAULT 0;
+ALTER TABLE frostpaw_clients ADD COLUMN IF NOT EXISTS created_at timestamptz NOT NULL DEFAULT NOW();
+
+UPDATE frostpaw_clients SET redirect_domains = ARRAY[rtrim(regexp_replace(domain, '^https?://', ''), '/')] WHERE redirect_domains = '{}';
+UPDATE frostpaw_clients SET verification_state = 2 WHERE verified;
+
+CREATE UNIQUE INDEX IF NOT EXISTS frostpaw_clients_id_key ON frostpaw_clients (id);
+CREATE INDEX IF NOT EXISTS frostpaw_clients_owner_idx ON frostpaw_clients (owner_id);
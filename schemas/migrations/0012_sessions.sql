@@ -0,0 +1,8 @@
+-- Where logins and Frostpaw client tokens were created from, shown when listing sessions
+--
+-- origin is only the network of the IP address (/24 for IPv4, /48 for IPv6)
+ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS origin text;
+ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS user_agent text;
+
+ALTER TABLE oauth2_tokens ADD COLUMN IF NOT EXISTS origin text;
+ALTER TABLE oauth2_tokens ADD COLUMN IF NOT EXISTS user_agent text;
@@ -0,0 +1,24 @@
+-- Metadata and risk scores of votes, suspicious votes are held here for staff review instead of being counted
+--
+-- origin is only the network of the IP address (/24 for IPv4, /48 for IPv6) and fingerprint is the SHA-256
+-- of request headers that stay the same for a browser. state is a VoteRiskState (0 = counted, 1 = held,
+-- 2 = confirmed by staff, 3 = voided by staff)
+CREATE TABLE IF NOT EXISTS vote_risk (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id bigint NOT NULL,
+    target_id bigint NOT NULL,
+    target_type integer NOT NULL,
+    origin text,
+    fingerprint text,
+    account_created_at timestamptz NOT NULL,
+    score integer NOT NULL DEFAULT 0,
+    reasons text[] NOT NULL DEFAULT '{}',
+    state integer NOT NULL DEFAULT 0,
+    reviewed_by bigint,
+    reviewed_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS vote_risk_origin_idx ON vote_risk (origin, created_at DESC);
+CREATE INDEX IF NOT EXISTS vote_risk_fingerprint_idx ON vote_risk (fingerprint, created_at DESC);
+CREATE INDEX IF NOT EXISTS vote_risk_state_idx ON vote_risk (state, created_at DESC);
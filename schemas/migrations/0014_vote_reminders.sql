@@ -0,0 +1,17 @@
+-- Vote reminders users have opted in to, one per bot/server
+--
+-- remind_at is set to when the vote cooldown (user_vote_table/user_server_vote_table) expires whenever the
+-- user votes and is cleared once the reminder is sent. method is a ReminderMethod (0 = push, 1 = DM, 2 = both)
+CREATE TABLE IF NOT EXISTS vote_reminders (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id bigint NOT NULL,
+    target_id bigint NOT NULL,
+    target_type integer NOT NULL,
+    method integer NOT NULL DEFAULT 0,
+    remind_at timestamptz,
+    last_sent_at timestamptz,
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, target_id, target_type)
+);
+
+CREATE INDEX IF NOT EXISTS vote_reminders_remind_at_idx ON vote_reminders (remind_at) WHERE remind_at IS NOT NULL;
@@ -0,0 +1,2 @@
+-- How many votes a vote counted as (see VoteRules), so voiding a vote removes the right amount
+ALTER TABLE vote_risk ADD COLUMN IF NOT EXISTS weight bigint NOT NULL DEFAULT 1;
@@ -0,0 +1,6 @@
+-- Staff review queue, servers can now be claimed by a verifier just like bots
+ALTER TABLE servers ADD COLUMN IF NOT EXISTS verifier bigint;
+
+-- The queue is walked oldest first
+CREATE INDEX IF NOT EXISTS bots_queue_idx ON bots (state, created_at, bot_id);
+CREATE INDEX IF NOT EXISTS servers_queue_idx ON servers (state, created_at, guild_id);
@@ -0,0 +1,24 @@
+-- Who did what to a bot, server or user. target_type is a AuditTargetType (0 = bot, 1 = server, 2 = user)
+-- and action is a AuditAction (0-12 being the same as UserBotAction)
+CREATE TABLE IF NOT EXISTS audit_logs (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    target_id bigint NOT NULL,
+    target_type integer NOT NULL,
+    actor_id bigint NOT NULL,
+    action integer NOT NULL,
+    context text,
+    created_at timestamptz NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_logs_target_idx ON audit_logs (target_id, target_type, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS audit_logs_actor_idx ON audit_logs (actor_id, created_at DESC);
+
+-- Copy over the old logs. Rows written by the API use the ID of their audit log so this can be run again safely
+INSERT INTO audit_logs (id, target_id, target_type, actor_id, action, context, created_at)
+SELECT _lynxtag, bot_id, 0, user_id, action, NULLIF(context, ''), action_time FROM user_bot_logs
+ON CONFLICT (id) DO NOTHING;
+
+-- Server edits (13 = EditServer)
+INSERT INTO audit_logs (id, target_id, target_type, actor_id, action, context, created_at)
+SELECT action_id, guild_id, 1, user_id, 13, field || ': ' || value, action_time FROM server_audit_logs
+ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1,20 @@
+-- Hidden reviews are only shown to their author and staff. filter_reason is why the spam filter flagged a review
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS hidden boolean NOT NULL DEFAULT false;
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS filter_reason text;
+
+CREATE INDEX IF NOT EXISTS reviews_flagged_idx ON reviews (flagged) WHERE flagged;
+
+-- reason is a ReviewReportReason. Reports are resolved when staff hide, restore or delete the review
+CREATE TABLE IF NOT EXISTS review_reports (
+    id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    review_id uuid NOT NULL REFERENCES reviews (id) ON DELETE CASCADE,
+    user_id bigint NOT NULL,
+    reason integer NOT NULL,
+    context text,
+    created_at timestamptz NOT NULL DEFAULT NOW(),
+    resolved_by bigint,
+    resolved_at timestamptz,
+    UNIQUE (review_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS review_reports_open_idx ON review_reports (review_id) WHERE resolved_at IS NULL;
@@ -0,0 +1,7 @@
+-- The official response of the owner to a (top level) review, there can only be one per review
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS owner_response boolean NOT NULL DEFAULT false;
+
+CREATE UNIQUE INDEX IF NOT EXISTS reviews_owner_response_idx ON reviews (parent_id) WHERE owner_response;
+
+-- Replies are loaded a page at a time
+CREATE INDEX IF NOT EXISTS reviews_replies_idx ON reviews (parent_id, (coalesce(epoch[1], 0)), id) WHERE parent_id IS NOT NULL;
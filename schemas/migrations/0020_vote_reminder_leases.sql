@@ -0,0 +1,4 @@
+-- Claimed reminders are leased (instead of being cleared) until they are sent so a crashed worker doesn't lose them.
+-- remind_at is only cleared once the reminder is sent and is pushed back (with attempts counting up) when sending fails
+ALTER TABLE vote_reminders ADD COLUMN IF NOT EXISTS leased_until timestamptz;
+ALTER TABLE vote_reminders ADD COLUMN IF NOT EXISTS attempts integer NOT NULL DEFAULT 0;
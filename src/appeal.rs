@@ -1,7 +1,7 @@
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
 /// Handles bot appeals
-use actix_web::{http, post, web, HttpRequest, HttpResponse};
+use actix_web::{http, post, web, HttpRequest, HttpResponse, ResponseError};
 use log::error;
 use serenity::model::prelude::*;
 
@@ -10,22 +10,16 @@ async fn appeal_bot(
     req: HttpRequest,
     info: web::Path<models::GetUserBotPath>,
     request: web::Json<models::Appeal>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
     let bot_id = info.bot_id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Appeal Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     let rl = data.database.get_ratelimit(models::Ratelimit::Appeal, user_id).await;
@@ -131,22 +125,16 @@ async fn appeal_server(
     req: HttpRequest,
     info: web::Path<models::GetUserServerPath>,
     request: web::Json<models::Appeal>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
     let server_id = info.server_id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Appeal Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     let server = data.database.get_server(server_id).await;
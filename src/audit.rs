@@ -0,0 +1,123 @@
+// Endpoints to read and export the audit logs of bots, servers and users
+use crate::auth;
+use crate::converters;
+use crate::models;
+use actix_web::{get, http, web, HttpRequest, HttpResponse, ResponseError};
+use log::error;
+
+/// The most entries returned by a export
+const EXPORT_LIMIT: i64 = 10000;
+
+/// Returns a page of a audit log, or all of it as a download if ``format`` is set
+async fn audit_log_response(
+    data: &models::AppState,
+    target_id: i64,
+    target_type: models::AuditTargetType,
+    query: &models::AuditLogQuery,
+) -> HttpResponse {
+    if let Some(format) = query.format {
+        let logs = match data.database.get_audit_logs(target_id, target_type, query, None, EXPORT_LIMIT).await {
+            Ok(page) => page.items,
+            Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+        };
+
+        let (body, content_type, extension) = match format {
+            models::ExportFormat::Json => (serde_json::to_string(&logs).unwrap_or_default(), "application/json", "json"),
+            models::ExportFormat::Csv => (converters::audit_logs_csv(&logs), "text/csv", "csv"),
+        };
+
+        return HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header((
+                http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"audit-logs-{}.{}\"", target_id, extension),
+            ))
+            .body(body);
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_audit_logs(target_id, target_type, query, cursor.as_ref(), models::Cursor::limit(query.limit, 50))
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::Ok().json(models::AuditLogList {
+            logs: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Get Bot Audit Logs
+#[get("/users/{user_id}/bots/{bot_id}/audit-logs")]
+async fn get_bot_audit_logs(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    query: web::Query<models::AuditLogQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Bot Audit Logs Auth error");
+        return err.error_response();
+    }
+
+    // Staff can see the audit log of any bot
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await
+        && !data.database.has_capability(info.user_id, models::Capability::AuditView).await
+    {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    audit_log_response(data, info.bot_id, models::AuditTargetType::Bot, &query).await
+}
+
+/// Get Server Audit Logs
+#[get("/users/{user_id}/servers/{server_id}/audit-logs")]
+async fn get_server_audit_logs(
+    req: HttpRequest,
+    info: web::Path<models::GetUserServerPath>,
+    query: web::Query<models::AuditLogQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Server Audit Logs Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_server_owner(info.server_id, info.user_id).await
+        && !data.database.has_capability(info.user_id, models::Capability::AuditView).await
+    {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    audit_log_response(data, info.server_id, models::AuditTargetType::Server, &query).await
+}
+
+/// Get User Audit Logs
+#[get("/users/{id}/audit-logs")]
+async fn get_user_audit_logs(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    query: web::Query<models::AuditLogQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(id.id) {
+        error!("User Audit Logs Auth error");
+        return err.error_response();
+    }
+
+    audit_log_response(data, id.id, models::AuditTargetType::User, &query).await
+}
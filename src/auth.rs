@@ -0,0 +1,264 @@
+// Request authentication (the ``Authorization`` header) as actix extractors
+//
+// Tokens are resolved to a ``models::ResolvedToken`` which handlers then check against
+// the user/bot/server in the path (and the scopes of scoped API tokens) using ``ensure``/``ensure_scope``
+use crate::models;
+use actix_web::dev::Payload;
+use actix_web::{http, web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use futures::future::LocalBoxFuture;
+use log::error;
+
+impl ResponseError for models::AuthError {
+    fn status_code(&self) -> http::StatusCode {
+        http::StatusCode::FORBIDDEN
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(models::APIResponse::err_small(self))
+    }
+}
+
+/// The kind of token to assume when a token has no ``Bot ``/``User ``/``Server `` prefix
+#[derive(Clone, Copy)]
+pub enum TokenKind {
+    User,
+    Bot,
+    Server,
+}
+
+/// Resolves the ``Authorization`` header of a request. The resolved token is stored in
+/// the request extensions so using multiple extractors only looks the token up once
+pub async fn authenticate(req: &HttpRequest, default: TokenKind) -> Result<models::ResolvedToken, models::AuthError> {
+    if let Some(resolved) = req.extensions().get::<models::ResolvedToken>() {
+        return Ok(resolved.clone());
+    }
+
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or_default()
+        .trim();
+
+    // OAuth2 clients send their access tokens as bearer tokens
+    let token = token.strip_prefix("Bearer ").unwrap_or(token).to_string();
+
+    if token.is_empty() {
+        return Err(models::AuthError::MissingToken);
+    }
+
+    let kind = if token.starts_with("Frostpaw.") || token.starts_with("User ") {
+        TokenKind::User
+    } else if token.starts_with("Bot ") {
+        TokenKind::Bot
+    } else if token.starts_with("Server ") {
+        TokenKind::Server
+    } else {
+        default
+    };
+
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let resolved = match kind {
+        TokenKind::User => data.database.resolve_user_token(&token).await,
+        TokenKind::Bot => data.database.resolve_bot_token(&token).await,
+        TokenKind::Server => data.database.resolve_server_token(&token).await,
+    }
+    .ok_or(models::AuthError::InvalidToken)?;
+
+    req.extensions_mut().insert(resolved.clone());
+
+    Ok(resolved)
+}
+
+/// Checks the token is for ``expected`` and has ``scope`` (or is a full access token if no scope is given)
+fn check(
+    kind: &str,
+    expected: i64,
+    got: i64,
+    scopes: &Option<Vec<models::TokenScope>>,
+    scope: Option<models::TokenScope>,
+) -> Result<(), models::AuthError> {
+    if expected != got {
+        error!("{} auth error: expected {}, got {}", kind, expected, got);
+        return Err(models::AuthError::WrongTarget);
+    }
+
+    match (scopes, scope) {
+        (None, _) => Ok(()),
+        (Some(scopes), Some(scope)) if scopes.contains(&scope) => Ok(()),
+        _ => {
+            error!("{} auth error: token for {} is missing scope {:?}", kind, got, scope);
+            Err(models::AuthError::MissingScope)
+        }
+    }
+}
+
+/// A request authenticated using a user token (or a ``Frostpaw.`` access token)
+pub struct UserAuth {
+    pub user_id: i64,
+    /// The custom client acting for the user, if any
+    pub client_id: Option<String>,
+    /// ``None`` unless a scoped API token is used
+    pub scopes: Option<Vec<models::TokenScope>>,
+}
+
+impl UserAuth {
+    /// Checks that the request is authenticated as this user using a full access token
+    pub fn ensure(&self, user_id: i64) -> Result<(), models::AuthError> {
+        check("User", user_id, self.user_id, &self.scopes, None)
+    }
+
+    /// Checks that the request is authenticated as this user using a token with ``scope``
+    pub fn ensure_scope(&self, user_id: i64, scope: models::TokenScope) -> Result<(), models::AuthError> {
+        check("User", user_id, self.user_id, &self.scopes, Some(scope))
+    }
+
+    /// Like ``ensure`` but also rejects custom clients and scoped tokens, even ones without scopes set.
+    /// Used for account management (API tokens etc.) which only the user themselves should be able to do
+    pub fn ensure_full(&self, user_id: i64) -> Result<(), models::AuthError> {
+        if self.client_id.is_some() || self.scopes.is_some() {
+            error!("User auth error: {} is not using a full access token", self.user_id);
+            return Err(models::AuthError::WrongTokenType);
+        }
+
+        self.ensure(user_id)
+    }
+}
+
+impl FromRequest for UserAuth {
+    type Error = models::AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let resolved = authenticate(&req, TokenKind::User).await?;
+            match resolved.principal {
+                models::Principal::User(user_id) => Ok(UserAuth {
+                    user_id,
+                    client_id: None,
+                    scopes: resolved.scopes,
+                }),
+                models::Principal::FrostpawClient { user_id, client_id } => Ok(UserAuth {
+                    user_id,
+                    client_id: Some(client_id),
+                    scopes: resolved.scopes,
+                }),
+                _ => Err(models::AuthError::WrongTokenType),
+            }
+        })
+    }
+}
+
+/// A request authenticated using a bot token
+pub struct BotAuth {
+    pub bot_id: i64,
+    /// ``None`` unless a scoped API token is used
+    pub scopes: Option<Vec<models::TokenScope>>,
+}
+
+impl BotAuth {
+    /// Checks that the request is authenticated as this bot using a full access token
+    pub fn ensure(&self, bot_id: i64) -> Result<(), models::AuthError> {
+        check("Bot", bot_id, self.bot_id, &self.scopes, None)
+    }
+
+    /// Checks that the request is authenticated as this bot using a token with ``scope``
+    pub fn ensure_scope(&self, bot_id: i64, scope: models::TokenScope) -> Result<(), models::AuthError> {
+        check("Bot", bot_id, self.bot_id, &self.scopes, Some(scope))
+    }
+}
+
+impl FromRequest for BotAuth {
+    type Error = models::AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let resolved = authenticate(&req, TokenKind::Bot).await?;
+            match resolved.principal {
+                models::Principal::Bot(bot_id) => Ok(BotAuth {
+                    bot_id,
+                    scopes: resolved.scopes,
+                }),
+                _ => Err(models::AuthError::WrongTokenType),
+            }
+        })
+    }
+}
+
+/// A request authenticated using a server token
+pub struct ServerAuth {
+    pub server_id: i64,
+}
+
+impl ServerAuth {
+    /// Checks that the request is authenticated as this server
+    pub fn ensure(&self, server_id: i64) -> Result<(), models::AuthError> {
+        check("Server", server_id, self.server_id, &None, None)
+    }
+}
+
+impl FromRequest for ServerAuth {
+    type Error = models::AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            match authenticate(&req, TokenKind::Server).await?.principal {
+                models::Principal::Server(server_id) => Ok(ServerAuth { server_id }),
+                _ => Err(models::AuthError::WrongTokenType),
+            }
+        })
+    }
+}
+
+/// A request authenticated using any kind of token, for endpoints supporting multiple
+/// auth types. Tokens without a prefix are treated as bot tokens for backwards compatibility
+pub struct Auth(pub models::ResolvedToken);
+
+impl FromRequest for Auth {
+    type Error = models::AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move { authenticate(&req, TokenKind::Bot).await.map(Auth) })
+    }
+}
+
+/// The user viewing a page, from the ``Frostpaw-Auth`` (``user_id|token``) header.
+/// Unlike the other extractors, this never fails and is ``None`` if the header is missing or invalid
+pub struct FrostpawAuth(pub Option<i64>);
+
+impl FromRequest for FrostpawAuth {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let auth = req
+                .headers()
+                .get("Frostpaw-Auth")
+                .and_then(|header| header.to_str().ok())
+                .unwrap_or_default();
+
+            let (user_id, token) = match auth.split_once('|') {
+                Some((user_id, token)) => (user_id.parse::<i64>().unwrap_or(0), token),
+                None => return Ok(FrostpawAuth(None)),
+            };
+
+            let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+            if data.database.authorize_user(user_id, token).await {
+                Ok(FrostpawAuth(Some(user_id)))
+            } else {
+                Ok(FrostpawAuth(None))
+            }
+        })
+    }
+}
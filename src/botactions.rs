@@ -1,9 +1,10 @@
 /// Handles bot actions (view, add, edit, delete, transfer)
 
+use crate::auth;
 use crate::models;
 use crate::converters;
 use actix_web::http::header::HeaderValue;
-use actix_web::{get, delete, patch, post, web, http, web::Json, HttpRequest, HttpResponse};
+use actix_web::{get, delete, patch, post, web, http, web::Json, HttpRequest, HttpResponse, ResponseError};
 use log::{error, debug};
 use serenity::model::prelude::*;
 use std::time::Duration;
@@ -315,119 +316,114 @@ async fn add_bot(
     req: HttpRequest,
     id: web::Path<models::FetchBotPath>,
     bot: web::Json<models::Bot>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    let mut bot = bot.into_inner();
-    if data.database.authorize_user(id.id, auth).await {
-        bot.owners.insert(0, models::BotOwner {
-            user: models::User {
-                id: id.id.to_string(),
-                username: "".to_string(),
-                avatar: "".to_string(),
-                disc: "0000".to_string(),
-                bot: false,
-                status: models::Status::Unknown,
-            },
-            main: true,
-        });
-        
-        let check_err = check_bot(data, models::BotActionMode::Add, &mut bot).await;
-        if check_err.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&check_err.unwrap_err()));
-        }
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
 
-        let res = data.database.add_bot(&bot).await;
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
-        }
+    let mut bot = bot.into_inner();
 
-        // Metro Code
-        let mut map = json!(
-            {
-                "bot_id": bot.user.id,
-                "username": &bot.user.username,
-                "banner": &bot.banner_card,
-                "owner": id.id,
-                "extra_owners": bot.owners.clone().into_iter().map(|x| x.user.id).collect::<Vec<String>>(),
-                "description": &bot.description,
-                "long_description": &bot.long_description,
-                "tags": &bot.tags.clone().into_iter().map(|x| x.id).collect::<Vec<String>>(),
-                "library": &bot.library,
-                "nsfw": bot.flags.contains(&(models::Flags::NSFW as i32)),
-                "prefix": &bot.prefix,
-            }
-        );
+    bot.owners.insert(0, models::BotOwner {
+        user: models::User {
+            id: id.id.to_string(),
+            username: "".to_string(),
+            avatar: "".to_string(),
+            disc: "0000".to_string(),
+            bot: false,
+            status: models::Status::Unknown,
+        },
+        main: true,
+    });
+    
+    let check_err = check_bot(data, models::BotActionMode::Add, &mut bot).await;
+    if check_err.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&check_err.unwrap_err()));
+    }
 
-        let obj: &mut serde_json::Map<std::string::String, serde_json::Value> = map.as_object_mut().unwrap();
+    let res = data.database.add_bot(&bot).await;
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
+    }
 
-        if bot.extra_links.contains_key("Website") {
-            obj.insert("website".to_string(), json!(bot.extra_links["Website"]));
-        } else if bot.extra_links.contains_key("website") {
-            obj.insert("website".to_string(), json!(bot.extra_links["website"]));
+    // Metro Code
+    let mut map = json!(
+        {
+            "bot_id": bot.user.id,
+            "username": &bot.user.username,
+            "banner": &bot.banner_card,
+            "owner": id.id,
+            "extra_owners": bot.owners.clone().into_iter().map(|x| x.user.id).collect::<Vec<String>>(),
+            "description": &bot.description,
+            "long_description": &bot.long_description,
+            "tags": &bot.tags.clone().into_iter().map(|x| x.id).collect::<Vec<String>>(),
+            "library": &bot.library,
+            "nsfw": bot.flags.contains(&(models::Flags::NSFW as i32)),
+            "prefix": &bot.prefix,
         }
+    );
 
-        if bot.extra_links.contains_key("Donate") {
-            obj.insert("donate".to_string(), json!(bot.extra_links["Donate"]));
-        } else if bot.extra_links.contains_key("donate") {
-            obj.insert("donate".to_string(), json!(bot.extra_links["donate"]));
-        }
+    let obj: &mut serde_json::Map<std::string::String, serde_json::Value> = map.as_object_mut().unwrap();
 
-        if bot.extra_links.contains_key("Github") {
-            obj.insert("github".to_string(), json!(bot.extra_links["Github"]));
-        } else if bot.extra_links.contains_key("github") {
-            obj.insert("github".to_string(), json!(bot.extra_links["github"]));
-        }
+    if bot.extra_links.contains_key("Website") {
+        obj.insert("website".to_string(), json!(bot.extra_links["Website"]));
+    } else if bot.extra_links.contains_key("website") {
+        obj.insert("website".to_string(), json!(bot.extra_links["website"]));
+    }
 
-        let metro = data.database.requests.post("https://catnip.metrobots.xyz/bots?list_id=5800d395-beb3-4d79-90b9-93e1ca674b40")
-        .header("Authorization", &data.config.secrets.metro_key)
-        .json(&map)
-        .send()
-        .await;    
+    if bot.extra_links.contains_key("Donate") {
+        obj.insert("donate".to_string(), json!(bot.extra_links["Donate"]));
+    } else if bot.extra_links.contains_key("donate") {
+        obj.insert("donate".to_string(), json!(bot.extra_links["donate"]));
+    }
 
-        if let Ok(m) = metro {
-            debug!("Metro code: {}", m.text().await.unwrap());
-        } else {
-            error!("Metro code: error {}", metro.unwrap_err());
-        }
-        // End of metro code
-
-        let _ = data
-            .config
-            .discord
-            .channels
-            .bot_logs
-            .send_message(&data.config.discord_http, |m| {
-                m.content(data.config.discord.roles.staff_ping_add_role.mention());
-                m.embed(|e| {
-                    e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
-                    e.title("New Bot!");
-                    e.color(0x00ff00);
-                    e.description(format!(
-                        "{user} has added {bot} ({bot_name}) to the queue!",
-                        user = UserId(id.id as u64).mention(),
-                        bot_name = bot.user.username,
-                        bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention()
-                    ));
-
-                    e.field("Guild Count (approx)", bot.guild_count.to_string(), true);
-
-                    e
-                });
-                m
-            })
-            .await;
+    if bot.extra_links.contains_key("Github") {
+        obj.insert("github".to_string(), json!(bot.extra_links["Github"]));
+    } else if bot.extra_links.contains_key("github") {
+        obj.insert("github".to_string(), json!(bot.extra_links["github"]));
+    }
 
-        return HttpResponse::Ok().json(models::APIResponse::ok());
+    let metro = data.database.requests.post("https://catnip.metrobots.xyz/bots?list_id=5800d395-beb3-4d79-90b9-93e1ca674b40")
+    .header("Authorization", &data.config.secrets.metro_key)
+    .json(&map)
+    .send()
+    .await;    
+
+    if let Ok(m) = metro {
+        debug!("Metro code: {}", m.text().await.unwrap());
+    } else {
+        error!("Metro code: error {}", metro.unwrap_err());
     }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+    // End of metro code
+
+    let _ = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.content(data.config.discord.roles.staff_ping_add_role.mention());
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
+                e.title("New Bot!");
+                e.color(0x00ff00);
+                e.description(format!(
+                    "{user} has added {bot} ({bot_name}) to the queue!",
+                    user = UserId(id.id as u64).mention(),
+                    bot_name = bot.user.username,
+                    bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention()
+                ));
+
+                e.field("Guild Count (approx)", bot.guild_count.to_string(), true);
+
+                e
+            });
+            m
+        })
+        .await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 /// Edit bot
@@ -436,89 +432,86 @@ async fn edit_bot(
     req: HttpRequest,
     id: web::Path<models::FetchBotPath>,
     bot: web::Json<models::Bot>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    let mut bot = bot.into_inner();
-    if data.database.authorize_user(id.id, auth).await {
-        // Before doing anything else, get the bot from db and check if user is owner
-        let owners = data
-            .database
-            .get_bot_owners(bot.user.id.parse::<i64>().unwrap_or(0))
-            .await;
-
-        let mut got_owner = false;
-        for owner in owners {
-            if owner.user.id == id.id.to_string() {
-                got_owner = true;
-                break;
-            } else if owner.main {
-                bot.owners.insert(0, models::BotOwner {
-                    user: models::User {
-                        id: owner.user.id,
-                        username: "".to_string(),
-                        avatar: "".to_string(),
-                        disc: "0000".to_string(),
-                        bot: false,
-                        status: models::Status::Unknown,
-                    },
-                    main: true,
-                });        
-            }
-        }
-
-        if !got_owner {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::Forbidden));
-        }
+    if let Err(err) = auth.ensure_scope(id.id, models::TokenScope::BotEdit) {
+        error!("Edit bot auth error");
+        return err.error_response();
+    }
 
-        let res = check_bot(data, models::BotActionMode::Edit, &mut bot).await;
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&res.unwrap_err()));
-        }
-        let res = data.database.edit_bot(id.id, &bot).await;
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
-        }
-        let result = data
-            .config
-            .discord
-            .channels
-            .bot_logs
-            .send_message(&data.config.discord_http, |m| {
-                m.embed(|e| {
-                    e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
-                    e.title("Bot Edit!");
-                    e.color(0x00ff00);
-                    e.description(format!(
-                        "{user} has edited {bot} ({bot_name})!",
-                        user = UserId(id.id as u64).mention(),
-                        bot_name = bot.user.username,
-                        bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention()
-                    ));
-
-                    e
-                });
-                m
-            })
-            .await;
+    let mut bot = bot.into_inner();
 
-        if result.is_err() {
-            error!("Error sending message: {}", result.unwrap_err());
-            return HttpResponse::Ok().json(models::APIResponse::ok());
+    // Before doing anything else, get the bot from db and check if user is owner
+    let owners = data
+        .database
+        .get_bot_owners(bot.user.id.parse::<i64>().unwrap_or(0))
+        .await;
+
+    let mut got_owner = false;
+    for owner in owners {
+        if owner.user.id == id.id.to_string() {
+            got_owner = true;
+            break;
+        } else if owner.main {
+            bot.owners.insert(0, models::BotOwner {
+                user: models::User {
+                    id: owner.user.id,
+                    username: "".to_string(),
+                    avatar: "".to_string(),
+                    disc: "0000".to_string(),
+                    bot: false,
+                    status: models::Status::Unknown,
+                },
+                main: true,
+            });        
         }
+    }
 
-        // Invalidate the cache
-        data.database.bot_cache.invalidate(&id.id).await;
+    if !got_owner {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
 
+    let res = check_bot(data, models::BotActionMode::Edit, &mut bot).await;
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&res.unwrap_err()));
+    }
+    let res = data.database.edit_bot(id.id, &bot).await;
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
+    }
+    let result = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
+                e.title("Bot Edit!");
+                e.color(0x00ff00);
+                e.description(format!(
+                    "{user} has edited {bot} ({bot_name})!",
+                    user = UserId(id.id as u64).mention(),
+                    bot_name = bot.user.username,
+                    bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention()
+                ));
+
+                e
+            });
+            m
+        })
+        .await;
+
+    if result.is_err() {
+        error!("Error sending message: {}", result.unwrap_err());
         return HttpResponse::Ok().json(models::APIResponse::ok());
     }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    // Invalidate the cache
+    data.database.bot_cache.invalidate(&id.id).await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 /// Transfer ownership
@@ -527,154 +520,142 @@ async fn transfer_ownership(
     req: HttpRequest,
     id: web::Path<models::GetUserBotPath>,
     owner: web::Json<models::BotOwner>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(id.user_id, auth).await {
-        // Before doing anything else, get the bot from db and check if user is owner
-        let owners = data
-            .database
-            .get_bot_owners(id.bot_id)
-            .await;
-
-        let mut got_owner = false;
-        for bot_owner in owners {
-            if bot_owner.main && bot_owner.user.id == id.user_id.to_string() {
-                got_owner = true;
-                break;
-            }
-        }
+    if let Err(err) = auth.ensure(id.user_id) {
+        return err.error_response();
+    }
 
-        if !got_owner {
-            return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::CheckBotError::NotMainOwner));
+    // Before doing anything else, get the bot from db and check if user is owner
+    let owners = data
+        .database
+        .get_bot_owners(id.bot_id)
+        .await;
+
+    let mut got_owner = false;
+    for bot_owner in owners {
+        if bot_owner.main && bot_owner.user.id == id.user_id.to_string() {
+            got_owner = true;
+            break;
         }
+    }
 
-        // Owner validation
-        let owner_copy = owner.clone();
-
-        if !owner_copy.main {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
-        }
+    if !got_owner {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::CheckBotError::NotMainOwner));
+    }
 
-        if owner_copy.user.id == id.user_id.to_string() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
-        }
+    // Owner validation
+    let owner_copy = owner.clone();
 
-        if owner_copy.user.id.parse::<i64>().is_err() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
-        }
+    if !owner_copy.main {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
+    }
 
-        // Does the user actually even exist?
-        let owner_user = data
-            .database
-            .get_user(owner_copy.user.id.parse::<i64>().unwrap())
-            .await;
-        if owner_user.id.is_empty() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
+    if owner_copy.user.id == id.user_id.to_string() {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
+    }
 
-        data.database
-            .transfer_ownership(id.user_id, id.bot_id, owner.clone())
-            .await;
-        let _ = data
-            .config
-            .discord
-            .channels
-            .bot_logs
-            .send_message(&data.config.discord_http, |m| {
-                m.embed(|e| {
-                    e.url(data.config.discord.site_url.to_string() + "/bot/" + &id.bot_id.to_string());
-                    e.title("Bot Ownership Transfer!");
-                    e.color(0x00ff00);
-                    e.description(format!(
-                        "{user} has transferred ownership of {bot} to {new_owner}!",
-                        user = UserId(id.user_id as u64).mention(),
-                        bot = UserId(id.bot_id as u64).mention(),
-                        new_owner = UserId(owner.user.id.parse::<u64>().unwrap_or(0)).mention()
-                    ));
-
-                    e
-                });
-                m
-            })
-            .await;
+    if owner_copy.user.id.parse::<i64>().is_err() {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
+    }
 
-        return HttpResponse::Ok().json(models::APIResponse::ok());
+    // Does the user actually even exist?
+    let owner_user = data
+        .database
+        .get_user(owner_copy.user.id.parse::<i64>().unwrap())
+        .await;
+    if owner_user.id.is_empty() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
     }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    data.database
+        .transfer_ownership(id.user_id, id.bot_id, owner.clone())
+        .await;
+    let _ = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + "/bot/" + &id.bot_id.to_string());
+                e.title("Bot Ownership Transfer!");
+                e.color(0x00ff00);
+                e.description(format!(
+                    "{user} has transferred ownership of {bot} to {new_owner}!",
+                    user = UserId(id.user_id as u64).mention(),
+                    bot = UserId(id.bot_id as u64).mention(),
+                    new_owner = UserId(owner.user.id.parse::<u64>().unwrap_or(0)).mention()
+                ));
+
+                e
+            });
+            m
+        })
+        .await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 /// Delete bot
 #[delete("/users/{user_id}/bots/{bot_id}")]
-async fn delete_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>) -> HttpResponse {
+async fn delete_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>, auth: auth::UserAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-
-    if data.database.authorize_user(id.user_id, auth).await {
-        // Before doing anything else, get the bot from db and check if user is owner
-        let bot_user = data.database.get_bot(id.bot_id).await;
-        if bot_user.is_none() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
+    if let Err(err) = auth.ensure(id.user_id) {
+        return err.error_response();
+    }
 
-        let mut got_owner = false;
-        for owner in bot_user.clone().unwrap().owners {
-            if owner.main && owner.user.id == id.user_id.to_string() {
-                got_owner = true;
-                break;
-            }
-        }
+    // Before doing anything else, get the bot from db and check if user is owner
+    let bot_user = data.database.get_bot(id.bot_id).await;
+    if bot_user.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
 
-        if !got_owner {
-            return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::CheckBotError::NotMainOwner));
+    let mut got_owner = false;
+    for owner in bot_user.clone().unwrap().owners {
+        if owner.main && owner.user.id == id.user_id.to_string() {
+            got_owner = true;
+            break;
         }
+    }
 
-        // Delete the bot
-        let res = data.database.delete_bot(id.user_id, id.bot_id).await;
-
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
-        }
+    if !got_owner {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::CheckBotError::NotMainOwner));
+    }
 
-        let _ = data
-            .config
-            .discord
-            .channels
-            .bot_logs
-            .send_message(&data.config.discord_http, |m| {
-                m.embed(|e| {
-                    e.url(data.config.discord.site_url.to_string() + "/bot/" + &id.bot_id.to_string());
-                    e.title("Bot Deleted :(");
-                    e.color(0x00ff00);
-                    e.description(format!(
-                        "{user} has deleted {bot} ({bot_name})",
-                        user = UserId(id.user_id as u64).mention(),
-                        bot_name = bot_user.unwrap().user.username,
-                        bot = UserId(id.bot_id as u64).mention(),
-                    ));
-
-                    e
-                });
-                m
-            })
-            .await;
+    // Delete the bot
+    let res = data.database.delete_bot(id.user_id, id.bot_id).await;
 
-        return HttpResponse::Ok().json(models::APIResponse::ok());
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
     }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    let _ = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + "/bot/" + &id.bot_id.to_string());
+                e.title("Bot Deleted :(");
+                e.color(0x00ff00);
+                e.description(format!(
+                    "{user} has deleted {bot} ({bot_name})",
+                    user = UserId(id.user_id as u64).mention(),
+                    bot_name = bot_user.unwrap().user.username,
+                    bot = UserId(id.bot_id as u64).mention(),
+                ));
+
+                e
+            });
+            m
+        })
+        .await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 // Get Import Sources
@@ -695,59 +676,132 @@ async fn get_import_sources(_req: HttpRequest) -> HttpResponse {
 }
 
 #[post("/users/{user_id}/bots/{bot_id}/import")]
-async fn import_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>, src: web::Query<models::ImportQuery>, body: web::Json<models::ImportBody>) -> HttpResponse {
+async fn import_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>, src: web::Query<models::ImportQuery>, body: web::Json<models::ImportBody>, auth: auth::UserAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = id.user_id;
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        // Fetch bot from RDL
-        let bot_id = id.bot_id;
-
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert("Lightleap-Dest", HeaderValue::from_str("Fates List").unwrap());
-        headers.insert("Lightleap-Site", HeaderValue::from_str(&data.config.discord.site_url).unwrap());
-
-        let mut bot = match src.src {
-            models::ImportSource::Rdl => {
-                let mut bot_data: HashMap<String, serde_json::Value> = data.requests.get("https://discord.rovelstars.com/api/bots/".to_owned()+&bot_id.to_string())
-                .timeout(Duration::from_secs(10))
-                .headers(headers)
-                .send()
-                .await
-                .unwrap()
-                .json::<HashMap<String, serde_json::Value>>()
-                .await
-                .unwrap();
-
-                if bot_data.get("err").is_some() {
-                    return HttpResponse::NotFound().json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    if let Err(err) = auth.ensure(user_id) {
+        error!("Import auth error");
+        return err.error_response();
+    }
+
+    // Fetch bot from RDL
+    let bot_id = id.bot_id;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("Lightleap-Dest", HeaderValue::from_str("Fates List").unwrap());
+    headers.insert("Lightleap-Site", HeaderValue::from_str(&data.config.discord.site_url).unwrap());
+
+    let mut bot = match src.src {
+        models::ImportSource::Rdl => {
+            let mut bot_data: HashMap<String, serde_json::Value> = data.requests.get("https://discord.rovelstars.com/api/bots/".to_owned()+&bot_id.to_string())
+            .timeout(Duration::from_secs(10))
+            .headers(headers)
+            .send()
+            .await
+            .unwrap()
+            .json::<HashMap<String, serde_json::Value>>()
+            .await
+            .unwrap();
+
+            if bot_data.get("err").is_some() {
+                return HttpResponse::NotFound().json(models::APIResponse::err_small(&models::GenericError::NotFound));
+            }
+
+            debug!("{:?}", bot_data);
+
+            let owners: Vec<String> = bot_data.remove("owners").unwrap().as_array().unwrap().iter().map(|x| x.as_str().unwrap().to_string()).collect();
+
+            let mut extra_owners = Vec::new();
+
+            let mut got_owner = false;
+            for owner in owners {
+                if owner == user_id.to_string() {
+                    got_owner = true;
+                } else {
+                    extra_owners.push(models::BotOwner {
+                        user: models::User {
+                            id: owner,
+                            ..models::User::default()
+                        },
+                        main: false
+                    });
                 }
+            }
 
-                debug!("{:?}", bot_data);
+            if !got_owner {
+                return HttpResponse::BadRequest().json(models::APIResponse {
+                    done: false,
+                    reason: Some(
+                        "You are not allowed to import bots you are not owner of!".to_string(),
+                    ),
+                    context: None,
+                });
+            }
 
-                let owners: Vec<String> = bot_data.remove("owners").unwrap().as_array().unwrap().iter().map(|x| x.as_str().unwrap().to_string()).collect();
+            let mut extra_links = indexmap::IndexMap::new();
 
-                let mut extra_owners = Vec::new();
+            let website = bot_data.remove("website").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string();
+            if website != *"null" && !website.is_empty() {
+                extra_links.insert("Website".to_string(), website);
+            };
 
-                let mut got_owner = false;
-                for owner in owners {
-                    if owner == user_id.to_string() {
-                        got_owner = true;
-                    } else {
-                        extra_owners.push(models::BotOwner {
-                            user: models::User {
-                                id: owner,
-                                ..models::User::default()
-                            },
-                            main: false
-                        });
+            let github = bot_data.remove("github").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string();
+            if github != *"null" && !github.is_empty() {
+                extra_links.insert("Github".to_string(), github);
+            };    
+
+            models::Bot {
+                user: models::User {
+                    id: bot_id.to_string(),
+                    ..models::User::default()
+                },
+                description: bot_data.remove("short").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
+                long_description: bot_data.remove("desc").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
+                prefix: Some(bot_data.remove("prefix").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),
+                library: bot_data.remove("lib").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
+                extra_links,
+                invite: Some(bot_data.remove("invite").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),
+                vanity: "_".to_string() + bot_data.remove("username").unwrap_or_else(|| json!("")).as_str().unwrap_or("") + "-" + &converters::create_token(32),
+                shard_count: 0,
+                owners: extra_owners,
+                tags: vec![
+                    // Rovel does not provide us with tags, assert utility
+                    models::Tag {
+                        id: "utility".to_string(),
+                        ..models::Tag::default()
                     }
+                ],
+                ..models::Bot::default()
+            }
+        }
+        models::ImportSource::Custom => {
+            let mut body = body.into_inner();
+            let ext_data = &mut body.ext_data;
+            if let Some(ref mut bot_data) = ext_data {
+                debug!("{:?}", bot_data);
+
+                let owners: Vec<String> = bot_data.remove("owners").unwrap_or_else(|| json!([])).as_array().unwrap_or(&Vec::new()).iter().map(|x| x.as_str().unwrap_or_default().to_string()).collect();
+                
+                let mut extra_owners = Vec::new();
+                
+                let mut got_owner = false;
+
+                if owners.is_empty() {
+                    got_owner = true
+                } else {
+                    for owner in owners {
+                        if owner == user_id.to_string() {
+                            got_owner = true;
+                        } else {
+                            extra_owners.push(models::BotOwner {
+                                user: models::User {
+                                    id: owner,
+                                    ..models::User::default()
+                                },
+                                main: false
+                            });
+                        }
+                    }    
                 }
 
                 if !got_owner {
@@ -758,7 +812,8 @@ async fn import_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>, src
                         ),
                         context: None,
                     });
-                }
+                }    
+
 
                 let mut extra_links = indexmap::IndexMap::new();
 
@@ -771,168 +826,89 @@ async fn import_bot(req: HttpRequest, id: web::Path<models::GetUserBotPath>, src
                 if github != *"null" && !github.is_empty() {
                     extra_links.insert("Github".to_string(), github);
                 };    
-
+                
                 models::Bot {
                     user: models::User {
                         id: bot_id.to_string(),
                         ..models::User::default()
-                    },
-                    description: bot_data.remove("short").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
-                    long_description: bot_data.remove("desc").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
-                    prefix: Some(bot_data.remove("prefix").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),
-                    library: bot_data.remove("lib").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
-                    extra_links,
-                    invite: Some(bot_data.remove("invite").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),
+                    },                    
                     vanity: "_".to_string() + bot_data.remove("username").unwrap_or_else(|| json!("")).as_str().unwrap_or("") + "-" + &converters::create_token(32),
+                    description: bot_data.remove("description").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
+                    long_description: bot_data.remove("long_description").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
+                    prefix: Some(bot_data.remove("prefix").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),   
+                    invite: Some(bot_data.remove("invite").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()), 
                     shard_count: 0,
-                    owners: extra_owners,
+                    owners: extra_owners,    
+                    extra_links,
                     tags: vec![
-                        // Rovel does not provide us with tags, assert utility
                         models::Tag {
                             id: "utility".to_string(),
                             ..models::Tag::default()
                         }
-                    ],
+                    ],        
                     ..models::Bot::default()
                 }
+            } else {
+                return HttpResponse::BadRequest().json(models::APIResponse {
+                    done: false,
+                    reason: Some("Invalid bot data".to_string()),
+                    context: None,
+                });
             }
-            models::ImportSource::Custom => {
-                let mut body = body.into_inner();
-                let ext_data = &mut body.ext_data;
-                if let Some(ref mut bot_data) = ext_data {
-                    debug!("{:?}", bot_data);
-
-                    let owners: Vec<String> = bot_data.remove("owners").unwrap_or_else(|| json!([])).as_array().unwrap_or(&Vec::new()).iter().map(|x| x.as_str().unwrap_or_default().to_string()).collect();
-                    
-                    let mut extra_owners = Vec::new();
-                    
-                    let mut got_owner = false;
-
-                    if owners.is_empty() {
-                        got_owner = true
-                    } else {
-                        for owner in owners {
-                            if owner == user_id.to_string() {
-                                got_owner = true;
-                            } else {
-                                extra_owners.push(models::BotOwner {
-                                    user: models::User {
-                                        id: owner,
-                                        ..models::User::default()
-                                    },
-                                    main: false
-                                });
-                            }
-                        }    
-                    }
-
-                    if !got_owner {
-                        return HttpResponse::BadRequest().json(models::APIResponse {
-                            done: false,
-                            reason: Some(
-                                "You are not allowed to import bots you are not owner of!".to_string(),
-                            ),
-                            context: None,
-                        });
-                    }    
-
-
-                    let mut extra_links = indexmap::IndexMap::new();
+        },
+    };
 
-                    let website = bot_data.remove("website").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string();
-                    if website != *"null" && !website.is_empty() {
-                        extra_links.insert("Website".to_string(), website);
-                    };
-    
-                    let github = bot_data.remove("github").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string();
-                    if github != *"null" && !github.is_empty() {
-                        extra_links.insert("Github".to_string(), github);
-                    };    
-                    
-                    models::Bot {
-                        user: models::User {
-                            id: bot_id.to_string(),
-                            ..models::User::default()
-                        },                    
-                        vanity: "_".to_string() + bot_data.remove("username").unwrap_or_else(|| json!("")).as_str().unwrap_or("") + "-" + &converters::create_token(32),
-                        description: bot_data.remove("description").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
-                        long_description: bot_data.remove("long_description").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string(),
-                        prefix: Some(bot_data.remove("prefix").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()),   
-                        invite: Some(bot_data.remove("invite").unwrap_or_else(|| json!("")).as_str().unwrap_or("").to_string()), 
-                        shard_count: 0,
-                        owners: extra_owners,    
-                        extra_links,
-                        tags: vec![
-                            models::Tag {
-                                id: "utility".to_string(),
-                                ..models::Tag::default()
-                            }
-                        ],        
-                        ..models::Bot::default()
+    let res = check_bot(data, models::BotActionMode::Add, &mut bot).await;
+    if res.is_err() {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
+    }
+    bot.owners.insert(0, models::BotOwner {
+        user: models::User {
+            id: user_id.clone().to_string(),
+            username: "".to_string(),
+            avatar: "".to_string(),
+            disc: "0000".to_string(),
+            bot: false,
+            status: models::Status::Unknown,
+        },
+        main: true,
+    });
+    let res = data.database.add_bot(&bot).await;
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
+    }
+    let _ = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.content(data.config.discord.roles.staff_ping_add_role.mention());
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
+                e.title("New Bot!");
+                e.color(0x00ff00);
+                e.description(format!(
+                    "{user} has added {bot} ({bot_name}) to the queue through {source}!",
+                    user = UserId(user_id as u64).mention(),
+                    bot_name = bot.user.username,
+                    bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention(),
+                    source = if src.src == models::ImportSource::Custom {
+                        src.src.source_name() + "(" + &src.custom_source.clone().unwrap_or_else(|| "Unknown".to_string()) + ")"
+                    } else {
+                        src.src.source_name()
                     }
-                } else {
-                    return HttpResponse::BadRequest().json(models::APIResponse {
-                        done: false,
-                        reason: Some("Invalid bot data".to_string()),
-                        context: None,
-                    });
-                }
-            },
-        };
-
-        let res = check_bot(data, models::BotActionMode::Add, &mut bot).await;
-        if res.is_err() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
-        }
-        bot.owners.insert(0, models::BotOwner {
-            user: models::User {
-                id: user_id.clone().to_string(),
-                username: "".to_string(),
-                avatar: "".to_string(),
-                disc: "0000".to_string(),
-                bot: false,
-                status: models::Status::Unknown,
-            },
-            main: true,
-        });
-        let res = data.database.add_bot(&bot).await;
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
-        }
-        let _ = data
-            .config
-            .discord
-            .channels
-            .bot_logs
-            .send_message(&data.config.discord_http, |m| {
-                m.content(data.config.discord.roles.staff_ping_add_role.mention());
-                m.embed(|e| {
-                    e.url(data.config.discord.site_url.to_string() + "/bot/" + &bot.user.id);
-                    e.title("New Bot!");
-                    e.color(0x00ff00);
-                    e.description(format!(
-                        "{user} has added {bot} ({bot_name}) to the queue through {source}!",
-                        user = UserId(user_id as u64).mention(),
-                        bot_name = bot.user.username,
-                        bot = UserId(bot.user.id.parse::<u64>().unwrap()).mention(),
-                        source = if src.src == models::ImportSource::Custom {
-                            src.src.source_name() + "(" + &src.custom_source.clone().unwrap_or_else(|| "Unknown".to_string()) + ")"
-                        } else {
-                            src.src.source_name()
-                        }
-                    ));
+                ));
 
-                    e.field("Guild Count (approx)", bot.guild_count.to_string(), true);
+                e.field("Guild Count (approx)", bot.guild_count.to_string(), true);
 
-                    e
-                });
-                m
-            })
-            .await;
+                e
+            });
+            m
+        })
+        .await;
 
-        return HttpResponse::Ok().json(models::APIResponse::ok());
-    }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 /// Post Stats
@@ -941,86 +917,52 @@ async fn post_stats(
     req: HttpRequest,
     id: web::Path<models::FetchBotPath>,
     stats: web::Json<models::BotStats>,
+    auth: auth::BotAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let bot_id = id.id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_bot(bot_id, auth).await {
-        // Firstly make sure user does not have the StatsLocked flag
-        let bot = data.database.get_bot(bot_id).await.unwrap();
+    if let Err(err) = auth.ensure_scope(bot_id, models::TokenScope::StatsWrite) {
+        error!("Stats post auth error");
+        return err.error_response();
+    }
 
-        if converters::flags_check(&bot.flags, vec![models::Flags::StatsLocked as i32]) {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("StatsLocked".to_string())));
-        }
+    // Firstly make sure user does not have the StatsLocked flag
+    let bot = data.database.get_bot(bot_id).await.unwrap();
 
-        let resp = data.database.post_stats(bot_id, bot.client_id.parse().unwrap_or(0), stats.into_inner(), &data.config.secrets.japi_key).await;
-        match resp {
-            Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
-            Err(err) => {
-                HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err))
-            }
+    if converters::flags_check(&bot.flags, vec![models::Flags::StatsLocked as i32]) {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("StatsLocked".to_string())));
+    }
+
+    let resp = data.database.post_stats(bot_id, bot.client_id.parse().unwrap_or(0), stats.into_inner(), &data.config.secrets.japi_key).await;
+    match resp {
+        Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
+        Err(err) => {
+            HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err))
         }
-    } else {
-        HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
     }
 }
 
 // Get Bot
 #[get("/bots/{id}")]
-async fn get_bot(req: HttpRequest, id: web::Path<models::FetchBotPath>) -> HttpResponse {
+async fn get_bot(req: HttpRequest, id: web::Path<models::FetchBotPath>, viewer: auth::FrostpawAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
     let id = id.into_inner();
 
     if req.headers().contains_key("Frostpaw") {
-        let auth_default = &HeaderValue::from_str("").unwrap();
-        let auth = req.headers().get("Frostpaw-Auth").unwrap_or(auth_default);
-        let mut event_user: Option<String> = None;
-        if !auth.clone().is_empty() {
-            let auth_bytes = auth.to_str();
-            match auth_bytes {
-                Ok(auth_str) => {
-                    let auth_split = auth_str.split('|');
-                    let auth_vec = auth_split.collect::<Vec<&str>>();
-
-                    let user_id = auth_vec.get(0).unwrap_or(&"");
-                    let token = auth_vec.get(1).unwrap_or(&"");
-
-                    let user_id_str = (*user_id).to_string();
-
-                    let user_id_i64 = user_id_str.parse::<i64>().unwrap_or(0);
-
-                    if data
-                        .database
-                        .authorize_user(user_id_i64, token.as_ref())
-                        .await
-                    {
-                        event_user = Some(user_id_str);
-                    }
-                }
-                Err(err) => {
-                    error!("{}", err);
-                }
-            }
-        }
 
         let event = models::Event {
             m: models::EventMeta {
                 e: models::EventName::BotView,
                 eid: Uuid::new_v4().to_hyphenated().to_string(),
+                seq: 0,
             },
             ctx: models::EventContext {
                 target: id.id.to_string(),
                 target_type: models::TargetType::Bot,
-                user: event_user,
+                user: viewer.0.map(|user_id| user_id.to_string()),
                 ts: chrono::Utc::now().timestamp(),
             },
             props: models::BotViewProp {
@@ -1072,45 +1014,38 @@ async fn random_bot(req: HttpRequest) -> Json<models::IndexBot> {
 async fn get_bot_settings(
     req: HttpRequest,
     info: web::Path<models::GetUserBotPath>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        let resp = data.database.get_bot_settings(info.bot_id).await;
-        match resp {
-            Ok(bot) => {
-                // Check if in owners before returning
-                for owner in &bot.owners {
-                    let id = owner.user.id.parse::<i64>().unwrap_or(0);
-                    if id == user_id {
-                        return HttpResponse::build(http::StatusCode::OK).json(
-                            models::BotSettings {
-                                bot,
-                                context: models::BotSettingsContext {
-                                    tags: data.database.bot_list_tags().await,
-                                    features: data.database.bot_features().await,
-                                },
+    if let Err(err) = auth.ensure(user_id) {
+        error!("Bot Settings Auth error");
+        return err.error_response();
+    }
+
+    let resp = data.database.get_bot_settings(info.bot_id).await;
+    match resp {
+        Ok(bot) => {
+            // Check if in owners before returning
+            for owner in &bot.owners {
+                let id = owner.user.id.parse::<i64>().unwrap_or(0);
+                if id == user_id {
+                    return HttpResponse::build(http::StatusCode::OK).json(
+                        models::BotSettings {
+                            bot,
+                            context: models::BotSettingsContext {
+                                tags: data.database.bot_list_tags().await,
+                                features: data.database.bot_features().await,
                             },
-                        );
-                    }
+                        },
+                    );
                 }
-                HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
-            }
-            Err(err) => {
-                HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err))
             }
+            HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+        }
+        Err(err) => {
+            HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err))
         }
-    } else {
-        error!("Bot Settings Auth error");
-        HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
     }
 }
@@ -1,7 +1,7 @@
 // Add, remove and delete commands from bots
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
-use actix_web::{delete, post, http, web, HttpRequest, HttpResponse};
+use actix_web::{delete, post, web, HttpRequest, HttpResponse, ResponseError};
 use log::{error, debug};
 
 #[post("/bots/{id}/commands")]
@@ -9,36 +9,42 @@ async fn add_command(
     req: HttpRequest,
     id: web::Path<models::FetchBotPath>,
     res: web::Json<models::BotCommandVec>,
+    auth: auth::BotAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let id = id.id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_bot(id, auth).await {
-        for command in &res.commands {
-            debug!("Adding command: {}", command.name);
-            if command.name.is_empty() {
-                return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::CommandError::CommandLengthError(command.name.clone()))); 
-            }
-            if command.description.is_empty() {
-                return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::CommandError::CommandLengthError(command.name.clone()))); 
-            }
-            let command_ret = data.database.add_command(id, command).await;
-            if command_ret.is_err() {
-                return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(command_ret.unwrap_err()))); 
-            }
+    if let Err(err) = auth.ensure_scope(id, models::TokenScope::CommandsWrite) {
+        error!("Command post auth error");
+        return err.error_response();
+    }
+
+    for command in &res.commands {
+        debug!("Adding command: {}", command.name);
+        if command.name.is_empty() {
+            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::CommandError::CommandLengthError(command.name.clone()))); 
+        }
+        if command.description.is_empty() {
+            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::CommandError::CommandLengthError(command.name.clone()))); 
+        }
+        let command_ret = data.database.add_command(id, command).await;
+        if command_ret.is_err() {
+            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(command_ret.unwrap_err()))); 
         }
-        return HttpResponse::Ok().json(models::APIResponse::ok());
     }
-    error!("Command post auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: id,
+            target_type: models::AuditTargetType::Bot,
+            actor_id: id,
+            action: models::AuditAction::AddCommands,
+            context: Some(res.commands.iter().map(|command| command.name.as_str()).collect::<Vec<&str>>().join("|")),
+        })
+        .await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 #[delete("/bots/{id}/commands")]
@@ -46,47 +52,64 @@ async fn delete_commands(
     req: HttpRequest,
     id: web::Path<models::FetchBotPath>,
     query: web::Query<models::CommandDeleteQuery>,
+    auth: auth::BotAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let id = id.id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_bot(id, auth).await {
-        // If nuke, delete all commands
-        if query.nuke.unwrap_or(false) {
-            data.database.delete_all_commands(id).await;
-        }
+    if let Err(err) = auth.ensure_scope(id, models::TokenScope::CommandsWrite) {
+        error!("Command delete auth error");
+        return err.error_response();
+    }
 
-        // If names, delete each command by name,
-        if query.names.is_some() {
-            let names = query.names.as_ref().unwrap();
-            for cmd in names.split('|').collect::<Vec<&str>>() {
-                data.database.delete_commands_by_name(id, cmd).await;
-            }
+    // If nuke, delete all commands
+    if query.nuke.unwrap_or(false) {
+        data.database.delete_all_commands(id).await;
+    }
+
+    // If names, delete each command by name,
+    if query.names.is_some() {
+        let names = query.names.as_ref().unwrap();
+        for cmd in names.split('|').collect::<Vec<&str>>() {
+            data.database.delete_commands_by_name(id, cmd).await;
         }
+    }
 
-        // If ids, delete each command by id
-        if query.ids.is_some() {
-            let ids = query.ids.as_ref().unwrap();
-            for cmd in ids.split('|').collect::<Vec<&str>>() {
-                let id_parse = uuid::Uuid::parse_str(cmd);
-                if id_parse.is_err() {
-                    continue;
-                }
-                let cmd_id = id_parse.unwrap();
-                data.database.delete_commands_by_id(id, cmd_id).await;
+    // If ids, delete each command by id
+    if query.ids.is_some() {
+        let ids = query.ids.as_ref().unwrap();
+        for cmd in ids.split('|').collect::<Vec<&str>>() {
+            let id_parse = uuid::Uuid::parse_str(cmd);
+            if id_parse.is_err() {
+                continue;
             }
+            let cmd_id = id_parse.unwrap();
+            data.database.delete_commands_by_id(id, cmd_id).await;
         }
-
-        return HttpResponse::Ok().json(models::APIResponse::ok());
     }
-    error!("Command delete auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    // Record what was asked to be deleted
+    let context = if query.nuke.unwrap_or(false) {
+        "all".to_string()
+    } else {
+        [query.names.as_deref(), query.ids.as_deref()]
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<&str>>()
+            .join("|")
+    };
+
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: id,
+            target_type: models::AuditTargetType::Bot,
+            actor_id: id,
+            action: models::AuditAction::DeleteCommands,
+            context: Some(context),
+        })
+        .await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
@@ -1,13 +1,15 @@
 // Handle simple data conversions and webhook sending
 use crate::models;
-use actix_web::http::StatusCode;
+use actix_web::HttpRequest;
 use log::{debug, error};
 use pulldown_cmark::{html::push_html, Options, Parser};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use ring::hmac;
+use chrono::TimeZone;
+use ring::{constant_time, digest, hmac};
 
 pub fn invite_link(client_id: &str, invite: &str) -> String {
     if invite.starts_with("P:") && invite.len() > 2 {
@@ -88,6 +90,207 @@ pub fn create_token(length: usize) -> String {
         .collect()
 }
 
+/// Scoped API tokens start with this so they can be told apart from ``api_token`` tokens
+pub const SCOPED_TOKEN_PREFIX: &str = "fls_";
+
+pub fn create_scoped_token() -> String {
+    SCOPED_TOKEN_PREFIX.to_string() + &create_token(128)
+}
+
+/// Hashes a scoped API token for storage. Scoped tokens are random so a plain SHA-256 is enough
+pub fn hash_scoped_token(token: &str) -> String {
+    hex::encode(digest::digest(&digest::SHA256, token.as_bytes()).as_ref())
+}
+
+/// Frostpaw access tokens start with this so they can be told apart from user tokens
+pub const ACCESS_TOKEN_PREFIX: &str = "Frostpaw.";
+
+pub fn create_access_token() -> String {
+    ACCESS_TOKEN_PREFIX.to_string() + &create_token(64)
+}
+
+/// Checks a PKCE ``code_verifier`` against the ``S256`` ``code_challenge`` sent when authorizing
+pub fn verify_pkce(verifier: &str, challenge: &str) -> bool {
+    // RFC 7636 section 4.1
+    if !(43..=128).contains(&verifier.len()) {
+        return false;
+    }
+
+    let expected = base64::encode_config(
+        digest::digest(&digest::SHA256, verifier.as_bytes()).as_ref(),
+        base64::URL_SAFE_NO_PAD,
+    );
+
+    constant_time::verify_slices_are_equal(expected.as_bytes(), challenge.as_bytes()).is_ok()
+}
+
+/// Gets where a session is being created from. Only the network of the IP address (the /24 for IPv4
+/// and /48 for IPv6) is kept
+pub fn session_origin(req: &HttpRequest) -> models::SessionOrigin {
+    let ip = req.connection_info().realip_remote_addr().and_then(|addr| {
+        addr.parse::<IpAddr>()
+            .ok()
+            .or_else(|| addr.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+    });
+
+    let origin = ip.map(|ip| match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            format!("{}.{}.{}.0/24", octets[0], octets[1], octets[2])
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            format!("{:x}:{:x}:{:x}::/48", segments[0], segments[1], segments[2])
+        }
+    });
+
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|header| header.to_str().ok())
+        .map(|user_agent| user_agent.chars().take(256).collect());
+
+    models::SessionOrigin { origin, user_agent }
+}
+
+/// Gets the metadata of a vote. The fingerprint is the SHA-256 of headers that stay the same for a browser
+/// so votes from one browser can be found even if the user changes
+pub fn vote_meta(req: &HttpRequest) -> models::VoteMeta {
+    let headers: Vec<&str> = ["User-Agent", "Accept-Language", "Accept-Encoding", "Sec-CH-UA-Platform"]
+        .iter()
+        .map(|name| {
+            req.headers()
+                .get(*name)
+                .and_then(|header| header.to_str().ok())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let fingerprint = if headers.iter().all(|header| header.is_empty()) {
+        None
+    } else {
+        Some(hash_scoped_token(&headers.join("\n")))
+    };
+
+    models::VoteMeta {
+        origin: session_origin(req).origin,
+        fingerprint,
+    }
+}
+
+/// Gets when a Discord account (or anything else with a snowflake) was created
+pub fn snowflake_timestamp(id: i64) -> chrono::DateTime<chrono::Utc> {
+    // Discord epoch (2015-01-01) in milliseconds
+    let ms = (id >> 22) + 1_420_070_400_000;
+
+    chrono::Utc.timestamp_millis(ms)
+}
+
+/// How many characters of a API token are stored in plaintext (as ``api_token_prefix``) to find its row
+pub const TOKEN_LOOKUP_PREFIX: usize = 8;
+
+pub fn api_token_prefix(token: &str) -> String {
+    token.chars().take(TOKEN_LOOKUP_PREFIX).collect()
+}
+
+fn salted_token_digest(salt: &[u8], token: &str) -> digest::Digest {
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    ctx.update(salt);
+    ctx.update(token.as_bytes());
+    ctx.finish()
+}
+
+/// Hashes a API token for storage as ``api_token_hash``. This is ``salt$hash`` with both hex encoded,
+/// the hash being the SHA-256 of the salt followed by the token
+pub fn hash_api_token(token: &str) -> String {
+    let salt: [u8; 16] = thread_rng().gen();
+
+    format!(
+        "{}${}",
+        hex::encode(salt),
+        hex::encode(salted_token_digest(&salt, token).as_ref())
+    )
+}
+
+/// Checks a API token against a ``api_token_hash`` in constant time
+pub fn verify_api_token(token: &str, hash: &str) -> bool {
+    let (salt, expected) = match hash.split_once('$') {
+        Some((salt, expected)) => (hex::decode(salt), hex::decode(expected)),
+        None => return false,
+    };
+
+    match (salt, expected) {
+        (Ok(salt), Ok(expected)) => {
+            constant_time::verify_slices_are_equal(salted_token_digest(&salt, token).as_ref(), &expected).is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// Converts a free-form search query into a prefix matching tsquery (``mew:* & bot:*``)
+///
+/// Anything that isn't alphanumeric is stripped so user input can never produce
+/// invalid tsquery syntax
+pub fn search_tsquery(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase() + ":*")
+        .collect::<Vec<String>>()
+        .join(" & ")
+}
+
+/// Paginates a list of vote timestamps (newest first) using ``Cursor``
+pub fn paginate_timestamps(
+    mut timestamps: Vec<chrono::DateTime<chrono::Utc>>,
+    cursor: Option<&models::Cursor>,
+    limit: i64,
+) -> models::CursorPage<chrono::DateTime<chrono::Utc>> {
+    timestamps.retain(|ts| match cursor {
+        Some(cursor) if cursor.rev => ts.timestamp_nanos() > cursor.key,
+        Some(cursor) => ts.timestamp_nanos() < cursor.key,
+        None => true,
+    });
+
+    if cursor.map(|c| c.rev).unwrap_or(false) {
+        timestamps.sort();
+    } else {
+        timestamps.sort_by(|a, b| b.cmp(a));
+    }
+
+    timestamps.truncate((limit + 1) as usize);
+
+    models::Cursor::paginate(timestamps, limit, cursor, |ts| (ts.timestamp_nanos(), String::new()))
+}
+
+/// Exports audit logs as CSV (RFC 4180), actions are written using their names
+pub fn audit_logs_csv(logs: &[models::AuditLog]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut csv = "id,target_id,target_type,actor_id,action,context,created_at\r\n".to_string();
+
+    for log in logs {
+        csv += &format!(
+            "{},{},{:?},{},{:?},{},{}\r\n",
+            log.id,
+            log.target_id,
+            log.target_type,
+            log.actor_id,
+            log.action,
+            field(log.context.as_deref().unwrap_or_default()),
+            log.created_at.to_rfc3339()
+        );
+    }
+
+    csv
+}
+
 pub fn flags_check(flag_list: &[i32], flag_vec: Vec<i32>) -> bool {
     for flag in flag_vec {
         if flag_list.contains(&flag) {
@@ -134,55 +337,265 @@ pub async fn send_discord_integration(
     }
 }
 
-// Moved here due to 'static requirement
-pub async fn send_vote_webhook(
-    requests: reqwest::Client,
-    webhook: String,
-    webhook_token: String,
-    webhook_hmac_only: bool,
-    vote_event: models::VoteWebhookEvent,
-) {
-    let mut tries = 0;
-    while tries < 5 {
-        let mut req = requests.post(webhook.as_str());
+/// How old (in seconds) a webhook timestamp may be before ``verify_webhook`` rejects it
+pub const WEBHOOK_TOLERANCE: i64 = 5 * 60;
+
+/// Signs a webhook body, the signature is sent as ``X-Webhook-Signature``
+///
+/// This is kept for older receivers, new receivers should use ``X-Webhook-Signature-V2``
+pub fn sign_webhook(secret: &str, body: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA512, secret.as_bytes());
 
-        if !webhook_hmac_only {
-            req = req.header("Authorization", &webhook_token);
+    let tag = hmac::sign(&key, body.as_bytes());
+
+    hex::encode(tag.as_ref())
+}
+
+/// The message signed for ``X-Webhook-Signature-V2``: ``{timestamp}.{eid}.{body}``
+fn webhook_message(timestamp: i64, eid: &str, body: &str) -> String {
+    format!("{}.{}.{}", timestamp, eid, body)
+}
+
+/// Creates the ``X-Webhook-Signature-V2`` header. This is a comma seperated list of ``v2=<hex signature>``,
+/// one per secret so both the old and new secret verify while a secret is being rotated
+pub fn sign_webhook_v2(secrets: &[&str], timestamp: i64, eid: &str, body: &str) -> String {
+    let message = webhook_message(timestamp, eid, body);
+
+    secrets
+        .iter()
+        .map(|secret| "v2=".to_string() + &sign_webhook(secret, &message))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Verifies a webhook as a receiver would. ``timestamp`` is the ``X-Webhook-Timestamp`` header,
+/// ``eid`` is the ``X-Webhook-Event-Id`` header and ``signature`` is the ``X-Webhook-Signature-V2`` header
+///
+/// Receivers should also remember recently seen ``eid``s to reject replays within the tolerance window
+pub fn verify_webhook(
+    secret: &str,
+    timestamp: &str,
+    eid: &str,
+    body: &str,
+    signature: &str,
+    now: i64,
+) -> Result<(), models::WebhookSignatureError> {
+    let ts = timestamp
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| models::WebhookSignatureError::MalformedTimestamp)?;
+
+    if now.abs_diff(ts) > WEBHOOK_TOLERANCE as u64 {
+        return Err(models::WebhookSignatureError::Expired);
+    }
+
+    let key = hmac::Key::new(hmac::HMAC_SHA512, secret.as_bytes());
+    let message = webhook_message(ts, eid, body);
+
+    for sig in signature.split(',') {
+        let sig = match sig.trim().strip_prefix("v2=").map(hex::decode) {
+            Some(Ok(sig)) => sig,
+            _ => continue,
+        };
+
+        // hmac::verify is constant time
+        if hmac::verify(&key, message.as_bytes(), &sig).is_ok() {
+            return Ok(());
+        }
+    }
+
+    Err(models::WebhookSignatureError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_token_hash_verifies() {
+        let hash = hash_api_token("mytoken");
+
+        assert!(verify_api_token("mytoken", &hash));
+        assert!(!verify_api_token("mytoken2", &hash));
+        assert!(!verify_api_token("", &hash));
+    }
+
+    #[test]
+    fn api_token_hash_is_salted() {
+        let (first, second) = (hash_api_token("mytoken"), hash_api_token("mytoken"));
+
+        assert_ne!(first, second);
+        assert!(verify_api_token("mytoken", &first));
+        assert!(verify_api_token("mytoken", &second));
+    }
+
+    #[test]
+    fn api_token_hash_format() {
+        // salt$sha256(salt + token), this is also what the migrations compute in SQL
+        let hash = "000102030405060708090a0b0c0d0e0f$da988d1b097b573b084bbd70f3d9c9185917572c68211516b6d6d593ed1d3b41";
+
+        assert!(verify_api_token("mytoken", hash));
+        assert!(!verify_api_token("mytoken", &hash.replace("0f$", "0e$")));
+    }
+
+    #[test]
+    fn api_token_hash_rejects_malformed() {
+        for hash in ["", "mytoken", "$", "zz$zz", "0011$", "$0011", "0011$0011$0011"] {
+            assert!(!verify_api_token("mytoken", hash), "{} verified", hash);
         }
+    }
+
+    #[test]
+    fn api_token_prefix_is_bounded() {
+        assert_eq!(api_token_prefix("abcdefghijklmnop"), "abcdefgh");
+        assert_eq!(api_token_prefix("abc"), "abc");
+        assert_eq!(api_token_prefix(""), "");
+    }
+
+    #[test]
+    fn session_origin_keeps_only_the_network() {
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("203.0.113.57:41234".parse().unwrap())
+            .to_http_request();
+        assert_eq!(session_origin(&req).origin.as_deref(), Some("203.0.113.0/24"));
 
-        let hmac_data = serde_json::to_string(&vote_event);
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("[2001:db8:1234:5678::1]:443".parse().unwrap())
+            .to_http_request();
+        assert_eq!(session_origin(&req).origin.as_deref(), Some("2001:db8:1234::/48"));
+    }
+
+    #[test]
+    fn session_origin_without_address() {
+        let origin = session_origin(&actix_web::test::TestRequest::default().to_http_request());
+
+        assert_eq!(origin.origin, None);
+        assert_eq!(origin.user_agent, None);
+    }
+
+    #[test]
+    fn session_origin_truncates_user_agent() {
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("User-Agent", "a".repeat(1000)))
+            .to_http_request();
+
+        assert_eq!(session_origin(&req).user_agent, Some("a".repeat(256)));
+    }
+
+    // RFC 7636 appendix B
+    const PKCE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const PKCE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
 
-        if hmac_data.is_err() {
-            error!("Failed to serialize vote webhook data");
-            return;
+    #[test]
+    fn verify_pkce_rfc_vector() {
+        assert!(verify_pkce(PKCE_VERIFIER, PKCE_CHALLENGE));
+    }
+
+    #[test]
+    fn verify_pkce_rejects_mismatch() {
+        assert!(!verify_pkce(&PKCE_VERIFIER.replace('d', "e"), PKCE_CHALLENGE));
+        // Only S256 is supported, so the plain method (challenge == verifier) must fail
+        assert!(!verify_pkce(PKCE_VERIFIER, PKCE_VERIFIER));
+        assert!(!verify_pkce(PKCE_VERIFIER, ""));
+    }
+
+    #[test]
+    fn verify_pkce_checks_verifier_length() {
+        let s256 = |verifier: &str| {
+            base64::encode_config(
+                digest::digest(&digest::SHA256, verifier.as_bytes()).as_ref(),
+                base64::URL_SAFE_NO_PAD,
+            )
+        };
+
+        for len in [0, 42, 129] {
+            let verifier = "a".repeat(len);
+            assert!(!verify_pkce(&verifier, &s256(&verifier)), "verifier of length {} accepted", len);
+        }
+
+        for len in [43, 128] {
+            let verifier = "a".repeat(len);
+            assert!(verify_pkce(&verifier, &s256(&verifier)), "verifier of length {} rejected", len);
         }
+    }
 
-        let hmac_data = hmac_data.unwrap();
+    const EID: &str = "b6b4c8e2-8f0a-4a59-9d1c-3f0c8a1e2d7b";
+    const BODY: &str = r#"{"votes":10}"#;
+    const NOW: i64 = 1660000000;
 
-        // Add HMAC
-	let key = hmac::Key::new(hmac::HMAC_SHA512, webhook_token.as_bytes());
+    #[test]
+    fn sign_webhook_v2_signs_with_every_secret() {
+        let signature = sign_webhook_v2(&["new", "old"], NOW, EID, BODY);
+        let parts: Vec<&str> = signature.split(',').collect();
 
-	let tag = hmac::sign(&key, hmac_data.as_bytes());
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], "v2=".to_string() + &sign_webhook("new", &webhook_message(NOW, EID, BODY)));
+        assert_eq!(parts[1], "v2=".to_string() + &sign_webhook("old", &webhook_message(NOW, EID, BODY)));
+    }
+
+    #[test]
+    fn verify_webhook_accepts_any_secret_during_rotation() {
+        let signature = sign_webhook_v2(&["new", "old"], NOW, EID, BODY);
+
+        assert_eq!(verify_webhook("new", &NOW.to_string(), EID, BODY, &signature, NOW), Ok(()));
+        assert_eq!(verify_webhook("old", &NOW.to_string(), EID, BODY, &signature, NOW), Ok(()));
+        assert_eq!(
+            verify_webhook("other", &NOW.to_string(), EID, BODY, &signature, NOW),
+            Err(models::WebhookSignatureError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn verify_webhook_rejects_tampering() {
+        let signature = sign_webhook_v2(&["secret"], NOW, EID, BODY);
 
-        let hmac = hex::encode(tag.as_ref());
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), EID, r#"{"votes":11}"#, &signature, NOW),
+            Err(models::WebhookSignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), "another-event", BODY, &signature, NOW),
+            Err(models::WebhookSignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_webhook("secret", &(NOW + 1).to_string(), EID, BODY, &signature, NOW),
+            Err(models::WebhookSignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), EID, BODY, "v2=zz,v1=abcd", NOW),
+            Err(models::WebhookSignatureError::InvalidSignature)
+        );
+    }
 
-        req = req.header("X-Webhook-Signature", hmac);
+    #[test]
+    fn verify_webhook_checks_timestamp() {
+        let signature = sign_webhook_v2(&["secret"], NOW, EID, BODY);
 
-        let res = req.json(&vote_event).send().await;
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), EID, BODY, &signature, NOW + WEBHOOK_TOLERANCE),
+            Ok(())
+        );
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), EID, BODY, &signature, NOW + WEBHOOK_TOLERANCE + 1),
+            Err(models::WebhookSignatureError::Expired)
+        );
+        assert_eq!(
+            verify_webhook("secret", &NOW.to_string(), EID, BODY, &signature, NOW - WEBHOOK_TOLERANCE - 1),
+            Err(models::WebhookSignatureError::Expired)
+        );
+        assert_eq!(
+            verify_webhook("secret", "soon", EID, BODY, &signature, NOW),
+            Err(models::WebhookSignatureError::MalformedTimestamp)
+        );
+    }
 
-        if res.is_err() {
-            error!("Failed to send webhook: {}", res.unwrap_err());
-            tries += 1;
-            continue;
+    #[test]
+    fn verify_webhook_does_not_overflow() {
+        for (ts, now) in [(i64::MIN, NOW), (i64::MAX, NOW), (i64::MIN, i64::MAX), (i64::MAX, i64::MIN)] {
+            assert_eq!(
+                verify_webhook("secret", &ts.to_string(), EID, BODY, "v2=00", now),
+                Err(models::WebhookSignatureError::Expired)
+            );
         }
-        let res = res.unwrap();
-        let status = res.status();
-        if !(status.is_success() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::BAD_REQUEST || status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN) {
-            error!("Failed to send webhook: {}", res.text().await.unwrap());
-            tries += 1;
-            continue;
-        } 
-        debug!("Sent webhook with status code: {}", status);
-        break;
     }
 }
@@ -16,16 +16,33 @@ async fn index(req: HttpRequest, info: web::Query<models::IndexQuery>) -> HttpRe
 
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
-    let cache = data.database.index_cache.get(&info.target_type);
+    let cursor = match models::Cursor::from_query(&info.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    // Only the first page is cached
+    if cursor.is_none() {
+        let cache = data.database.index_cache.get(&info.target_type);
         
-    if cache.is_some() {
-        return HttpResponse::Ok().json(cache.unwrap());
+        if cache.is_some() {
+            return HttpResponse::Ok().json(cache.unwrap());
+        }
     }
 
+    let top_voted = if info.target_type == models::TargetType::Bot {
+        data.database.index_bots(models::State::Approved, cursor.as_ref(), 12).await
+    } else {
+        data.database.index_servers(models::State::Approved, cursor.as_ref(), 12).await
+    };
+
+    index.top_voted = top_voted.items;
+    index.next_cursor = top_voted.next_cursor;
+    index.prev_cursor = top_voted.prev_cursor;
+
     let index = Arc::new(if info.target_type == models::TargetType::Bot {
 	index.random = data.database.random_bot().await;
-        index.top_voted = data.database.index_bots(models::State::Approved).await;
-        index.certified = data.database.index_bots(models::State::Certified).await;
+        index.certified = data.database.index_bots(models::State::Certified, None, 12).await.items;
         index.tags = data.database.bot_list_tags().await;
         index.new = data.database.index_new_bots().await;
         index.features = data.database.bot_features().await;
@@ -33,14 +50,16 @@ async fn index(req: HttpRequest, info: web::Query<models::IndexQuery>) -> HttpRe
         index
     } else {
 	index.random = data.database.random_server().await;
-        index.top_voted = data.database.index_servers(models::State::Approved).await;
-        index.certified = data.database.index_servers(models::State::Certified).await;
+        index.certified = data.database.index_servers(models::State::Certified, None, 12).await.items;
         index.new = data.database.index_new_servers().await;
         index.tags = data.database.server_list_tags().await;
 
         index 
    });
-    data.database.index_cache.insert(info.target_type, index.clone()).await;
+
+    if cursor.is_none() {
+        data.database.index_cache.insert(info.target_type, index.clone()).await;
+    }
     HttpResponse::Ok().json(index)
 }
 
@@ -87,13 +106,19 @@ async fn search_list(req: HttpRequest, info: web::Query<models::SearchQuery>) ->
 
     let search = info.into_inner();
 
-    let search_key = format!("{query}-{gc_from}-{gc_to}", query = search.q, gc_from = search.gc_from, gc_to = search.gc_to);
+    let (limit, offset) = match search.limits() {
+        Ok(limits) => limits,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    // Every filter changes the response so the whole query is used as the cache key
+    let search_key = serde_json::to_string(&search).unwrap_or_default();
 
     let cached_resp = data.database.search_cache.get(&search_key);
     match cached_resp {
         Some(resp) => HttpResponse::Ok().json(resp),
         None => {
-            let search_resp = Arc::new(data.database.search(search).await);
+            let search_resp = Arc::new(data.database.search(search, limit, offset).await);
             data.database.search_cache.insert(search_key, search_resp.clone()).await;
             HttpResponse::Ok().json(search_resp)
         }
@@ -105,10 +130,16 @@ async fn search_list(req: HttpRequest, info: web::Query<models::SearchQuery>) ->
 async fn search_tags(
     req: HttpRequest,
     info: web::Query<models::SearchTagQuery>,
-) -> Json<models::Search> {
+) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let search_resp = data.database.search_tags(&info.q).await;
-    Json(search_resp)
+
+    let cursor = match models::Cursor::from_query(&info.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let search_resp = data.database.search_tags(&info.q, cursor.as_ref(), models::Cursor::limit(info.limit, 15)).await;
+    HttpResponse::Ok().json(search_resp)
 }
 
 /// Mini Index: Get Tags And Features
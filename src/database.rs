@@ -7,6 +7,7 @@ use chrono::TimeZone;
 use chrono::Utc;
 use deadpool_redis::redis::AsyncCommands;
 use deadpool_redis::{Config, Runtime};
+use futures::StreamExt;
 use indexmap::IndexMap;
 use indexmap::indexmap;
 use log::{debug, error};
@@ -18,6 +19,7 @@ use sqlx::postgres::PgPoolOptions;
 use tokio::task;
 use bigdecimal::BigDecimal;
 use bigdecimal::ToPrimitive;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use moka::future::Cache;
@@ -30,6 +32,7 @@ pub struct Database {
     discord_server: Arc<serenity::http::client::Http>,
     default_map: serde_json::Map<String, serde_json::Value>,
     discord_config: models::DiscordData,
+    staff_perms: models::StaffPermConfig,
     // Requests
     pub requests: reqwest::Client,
     // Our moka caches
@@ -37,11 +40,16 @@ pub struct Database {
     pub server_cache: Cache<i64, Arc<models::Server>>,
     pub index_cache: Cache<models::TargetType, Arc<models::Index>>,
     pub search_cache: Cache<String, Arc<models::Search>>,
-    pub client_data: Cache<String, Arc<models::FrostpawLogin>>,
+    /// Webhook of a bot/server used when fanning out events, see ``webhook_event``
+    pub webhook_target_cache: Cache<(models::TargetType, i64), Arc<models::WebhookTarget>>,
+    pub ratelimit_tier_cache: Cache<String, models::RatelimitTier>,
+    /// Hashed token to the rate limit key and tier of whoever it belongs to, see ``ratelimit``
+    pub ratelimit_token_cache: Cache<String, (String, models::RatelimitTier)>,
+    pub perm_cache: Cache<i64, bristlefrost::models::StaffRole>,
 }
 
 impl Database {
-    pub async fn new(max_connections: u32, url: &str, redis_url: &str, discord_main: Arc<serenity::http::client::Http>, discord_server: Arc<serenity::http::client::Http>, discord_config: models::DiscordData) -> Self {
+    pub async fn new(max_connections: u32, url: &str, redis_url: &str, discord_main: Arc<serenity::http::client::Http>, discord_server: Arc<serenity::http::client::Http>, discord_config: models::DiscordData, staff_perms: models::StaffPermConfig) -> Self {
         let cfg = Config::from_url(redis_url);
         Database {
             pool: PgPoolOptions::new()
@@ -52,6 +60,7 @@ impl Database {
             redis: cfg.create_pool(Some(Runtime::Tokio1)).unwrap(),
             default_map: serde_json::Map::new(),
             discord_config,
+            staff_perms,
             requests: reqwest::Client::builder()
                 .user_agent("Lightleap/0.1.0")
                 .build()
@@ -83,11 +92,24 @@ impl Database {
                 .time_to_idle(Duration::from_secs(45))
                 // Create the cache.
                 .build(),
-            client_data: Cache::builder()
-                // Time to live (TTL): 15 minutes
-                .time_to_live(Duration::from_secs(15 * 60))
-                // Time to idle (TTI):  45 seconds
-                .time_to_idle(Duration::from_secs(15 * 60))
+            webhook_target_cache: Cache::builder()
+                // Time to live (TTL): 1 minute
+                .time_to_live(Duration::from_secs(60))
+                // Create the cache.
+                .build(),
+            ratelimit_tier_cache: Cache::builder()
+                // Time to live (TTL): 5 minutes
+                .time_to_live(Duration::from_secs(5 * 60))
+                // Create the cache.
+                .build(),
+            ratelimit_token_cache: Cache::builder()
+                // Time to live (TTL): 5 minutes
+                .time_to_live(Duration::from_secs(5 * 60))
+                // Create the cache.
+                .build(),
+            perm_cache: Cache::builder()
+                // Time to live (TTL): 5 minutes
+                .time_to_live(Duration::from_secs(5 * 60))
                 // Create the cache.
                 .build(),
             discord_main,
@@ -110,6 +132,133 @@ impl Database {
         conn.set_ex(&key, 0, identifier as usize).await.unwrap_or(0);
     }
 
+    /// Counts a request against a quota using a sliding window. The window is approximated from the
+    /// count of the current and previous fixed windows (weighted by how much of it still overlaps)
+    pub async fn ratelimit_hit(&self, bucket: &str, quota: models::RatelimitQuota) -> Option<models::RatelimitStatus> {
+        let mut conn = self.redis.get().await.ok()?;
+
+        let per = quota.per.max(1);
+        let now = Utc::now().timestamp_millis() as f64 / 1000.0;
+        let window = (now / per as f64) as u64;
+        let elapsed = now - (window * per) as f64;
+
+        let current_key = format!("rl:{}:{}", bucket, window);
+        let previous_key = format!("rl:{}:{}", bucket, window.saturating_sub(1));
+
+        let (current, _, previous): (u64, i64, Option<u64>) = deadpool_redis::redis::pipe()
+            .atomic()
+            .incr(&current_key, 1)
+            .expire(&current_key, (per * 2) as usize)
+            .get(&previous_key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| error!("Ratelimit error: {}", err))
+            .ok()?;
+
+        let weight = 1.0 - elapsed / per as f64;
+        let count = (previous.unwrap_or(0) as f64 * weight).ceil() as u64 + current;
+
+        let reset = (window + 1) * per;
+
+        Some(models::RatelimitStatus {
+            limit: quota.requests,
+            remaining: (quota.requests as u64).saturating_sub(count) as u32,
+            reset,
+            retry_after: if count > quota.requests as u64 {
+                (reset as f64 - now).ceil() as u64
+            } else {
+                0
+            },
+        })
+    }
+
+    /// Gets the ratelimit tier of a principal, staff are looked up using baypaw
+    pub async fn get_ratelimit_tier(&self, principal: &models::Principal) -> models::RatelimitTier {
+        let key = format!("{:?}", principal);
+
+        if let Some(tier) = self.ratelimit_tier_cache.get(&key) {
+            return tier;
+        }
+
+        let tier = match principal {
+            models::Principal::User(user_id) | models::Principal::FrostpawClient { user_id, .. } => {
+                if self.get_staff_perm(*user_id).await >= models::STAFF_PERM {
+                    models::RatelimitTier::Staff
+                } else {
+                    models::RatelimitTier::User
+                }
+            }
+            models::Principal::Bot(bot_id) => {
+                let row = sqlx::query!(
+                    "SELECT state FROM bots WHERE bot_id = $1",
+                    bot_id
+                )
+                .fetch_optional(&self.pool)
+                .await;
+
+                match row {
+                    Ok(Some(row)) if row.state == models::State::Certified as i32 => models::RatelimitTier::CertifiedBot,
+                    _ => models::RatelimitTier::Bot,
+                }
+            }
+            models::Principal::Server(_) => models::RatelimitTier::Bot,
+        };
+
+        self.ratelimit_tier_cache.insert(key, tier).await;
+
+        tier
+    }
+
+    /// Gets the staff role of a user from baypaw (or ``fake_baypaw`` in tests)
+    pub async fn get_staff_role(&self, user_id: i64) -> Result<bristlefrost::models::StaffRole, reqwest::Error> {
+        #[cfg(test)]
+        if let Some(ref fake_baypaw) = self.staff_perms.fake_baypaw {
+            return Ok(fake_baypaw.get(&user_id.to_string()).cloned().unwrap_or_default());
+        }
+
+        if let Some(role) = self.perm_cache.get(&user_id) {
+            return Ok(role);
+        }
+
+        let role = self
+            .requests
+            .get(format!("http://127.0.0.1:1234/perms/{}", user_id))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await?
+            .json::<bristlefrost::models::StaffRole>()
+            .await?;
+
+        self.perm_cache.insert(user_id, role.clone()).await;
+
+        Ok(role)
+    }
+
+    /// Gets the staff perm of a user, ``0`` if they are not staff or baypaw is down
+    pub async fn get_staff_perm(&self, user_id: i64) -> f32 {
+        match self.get_staff_role(user_id).await {
+            Ok(role) => role.perm,
+            Err(err) => {
+                error!("Could not get perms of {} from baypaw: {}", user_id, err);
+                0.0
+            }
+        }
+    }
+
+    /// Gets the staff role of a user along with the capabilities it grants
+    pub async fn get_staff_perms(&self, user_id: i64) -> Result<models::StaffPerms, reqwest::Error> {
+        let role = self.get_staff_role(user_id).await?;
+
+        Ok(models::StaffPerms {
+            capabilities: self.staff_perms.granted(role.perm),
+            role,
+        })
+    }
+
+    pub async fn has_capability(&self, user_id: i64, capability: models::Capability) -> bool {
+        self.get_staff_perm(user_id).await >= self.staff_perms.min_perm(capability)
+    }
+
     pub async fn add_refresh_token(&self, client_id: &str, id: i64) -> String {
         let refresh_token = converters::create_token(128);
         sqlx::query!(
@@ -124,21 +273,6 @@ impl Database {
         refresh_token    
     }
 
-    pub async fn get_user_token(&self, user_id: i64) -> String {
-        let row = sqlx::query!(
-            "SELECT api_token FROM users WHERE user_id = $1",
-            user_id
-        )
-        .fetch_one(&self.pool)
-        .await;
-
-        if row.is_err() {
-            return "".to_string()
-        }
-
-        row.unwrap().api_token
-    }
-
     pub async fn get_frostpaw_refresh_token(&self, refresh_token: String) -> models::FrostpawUserConnection {
         sqlx::query!(
             "delete from user_connections where expires_on < NOW()"
@@ -278,6 +412,64 @@ impl Database {
         user
     }
 
+    /// Gets many users at once. Cached users are fetched with one ``MGET`` and the rest are fetched 
+    /// concurrently, each user only being fetched once
+    pub async fn get_users(&self, user_ids: &[i64]) -> HashMap<i64, models::User> {
+        let mut user_ids = user_ids.to_vec();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let mut users = HashMap::new();
+
+        if user_ids.is_empty() {
+            return users;
+        }
+
+        let keys: Vec<String> = user_ids
+            .iter()
+            .map(|user_id| "user-cache:".to_string() + &user_id.to_string())
+            .collect();
+
+        let cached: Vec<Option<String>> = match self.redis.get().await {
+            Ok(mut conn) => deadpool_redis::redis::cmd("MGET")
+                .arg(&keys)
+                .query_async(&mut conn)
+                .await
+                .unwrap_or_default(),
+            Err(err) => {
+                error!("Failed to get cached users: {}", err);
+                Vec::new()
+            }
+        };
+
+        let mut missing = Vec::new();
+
+        for (i, user_id) in user_ids.into_iter().enumerate() {
+            let user: Option<models::User> = cached
+                .get(i)
+                .cloned()
+                .flatten()
+                .and_then(|data| serde_json::from_str(&data).ok());
+
+            match user {
+                Some(user) => {
+                    users.insert(user_id, user);
+                }
+                None => missing.push(user_id),
+            }
+        }
+
+        let fetched: Vec<(i64, models::User)> = futures::stream::iter(missing)
+            .map(|user_id| async move { (user_id, self.get_user(user_id).await) })
+            .buffer_unordered(8)
+            .collect()
+            .await;
+
+        users.extend(fetched);
+
+        users
+    }
+
     /// The webset command (server listing)
     pub async fn slwebset(&self, token: &str, value: &str) -> Result<(), models::GenericError> {
         const DISABLED: bool = true; // May be vulnerable to XSS, to be updated later
@@ -312,17 +504,30 @@ impl Database {
         Ok(())
     }
 
-    pub async fn index_bots(&self, state: models::State) -> Vec<models::IndexBot> {
+    pub async fn index_bots(&self, state: models::State, cursor: Option<&models::Cursor>, limit: i64) -> models::CursorPage<models::IndexBot> {
         let mut bots: Vec<models::IndexBot> = Vec::new();
         let rows = sqlx::query!(
             "SELECT bot_id, created_at, flags, description, banner_card, state, votes, guild_count 
-            FROM bots WHERE state = $1 ORDER BY votes DESC LIMIT 12",
-            state as i32
+            FROM bots WHERE state = $1 AND (
+                $2::bigint IS NULL
+                OR (NOT $4 AND (coalesce(votes, 0), bot_id) < ($2, $3))
+                OR ($4 AND (coalesce(votes, 0), bot_id) > ($2, $3))
+            ) ORDER BY 
+            CASE WHEN $4 THEN coalesce(votes, 0) END ASC, CASE WHEN $4 THEN bot_id END ASC, 
+            coalesce(votes, 0) DESC, bot_id DESC LIMIT $5",
+            state as i32,
+            cursor.map(|c| c.key),
+            cursor.map(|c| c.id_i64()),
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
         )
             .fetch_all(&self.pool)
             .await
             .unwrap();
-        for row in rows {
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| (row.votes.unwrap_or(0), row.bot_id.to_string()));
+
+        for row in page.items {
             let bot = models::IndexBot {
                 guild_count: row.guild_count.unwrap_or(0),
                 description: row.description,
@@ -337,7 +542,12 @@ impl Database {
             };
             bots.push(bot);
         }
-        bots
+
+        models::CursorPage {
+            items: bots,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
     }
 
     pub async fn bot_features(&self) -> Vec<models::Feature> {
@@ -401,17 +611,30 @@ impl Database {
         }
     }
 
-    pub async fn index_servers(&self, state: models::State) -> Vec<models::IndexBot> {
+    pub async fn index_servers(&self, state: models::State, cursor: Option<&models::Cursor>, limit: i64) -> models::CursorPage<models::IndexBot> {
         let mut servers: Vec<models::IndexBot> = Vec::new();
         let rows = sqlx::query!(
             "SELECT guild_id, flags, description, created_at, banner_card, state, votes, guild_count 
-            FROM servers WHERE state = $1 ORDER BY votes DESC LIMIT 12",
-            state as i32
+            FROM servers WHERE state = $1 AND (
+                $2::bigint IS NULL
+                OR (NOT $4 AND (coalesce(votes, 0), guild_id) < ($2, $3))
+                OR ($4 AND (coalesce(votes, 0), guild_id) > ($2, $3))
+            ) ORDER BY 
+            CASE WHEN $4 THEN coalesce(votes, 0) END ASC, CASE WHEN $4 THEN guild_id END ASC, 
+            coalesce(votes, 0) DESC, guild_id DESC LIMIT $5",
+            state as i32,
+            cursor.map(|c| c.key),
+            cursor.map(|c| c.id_i64()),
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
         )
             .fetch_all(&self.pool)
             .await
             .unwrap();
-        for row in rows {
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| (row.votes.unwrap_or(0), row.guild_id.to_string()));
+
+        for row in page.items {
             let server = models::IndexBot {
                 guild_count: row.guild_count.unwrap_or(0),
                 description: row.description,
@@ -426,7 +649,12 @@ impl Database {
             };
             servers.push(server);
         }
-        servers
+
+        models::CursorPage {
+            items: servers,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
     }
 
     pub async fn index_new_servers(&self) -> Vec<models::IndexBot> {
@@ -531,152 +759,519 @@ impl Database {
     }
 
     // Auth functions
+    //
+    // The ``authorize_*`` functions only accept full access tokens, scoped API tokens must go through
+    // ``auth`` so their scopes are checked
     pub async fn authorize_user(&self, user_id: i64, token: &str) -> bool {
+        match self.resolve_user_token(token).await {
+            Some(models::ResolvedToken {
+                principal: models::Principal::User(id) | models::Principal::FrostpawClient { user_id: id, .. },
+                scopes: None,
+            }) => id == user_id,
+            _ => false,
+        }
+    }
+
+    /// Resolves a user token, a scoped API token (or login) or a ``Frostpaw.`` access token 
+    /// (in which case the principal is the client acting for the user)
+    pub async fn resolve_user_token(&self, token: &str) -> Option<models::ResolvedToken> {
         if token.is_empty() {
-            return false;
+            return None;
         }
 
-        // Frostpaw = access token, 15 minutes time only
-        if token.starts_with("Frostpaw.") {
+        let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+        // Frostpaw = OAuth2 access token
+        if token.starts_with(converters::ACCESS_TOKEN_PREFIX) {
             debug!("Frostpaw token detected");
-            if let Some(data) = self.client_data.get(&token.to_string()) {
-                if data.user_id != user_id {
-                    return false;
+            let row = sqlx::query!(
+                "UPDATE oauth2_tokens SET last_used_at = NOW() WHERE access_token_hash = $1 AND access_expires_at > NOW() 
+                AND NOT EXISTS (
+                    SELECT 1 FROM users WHERE users.user_id = oauth2_tokens.user_id AND users.state = $2
+                ) RETURNING user_id, client_id, scopes",
+                converters::hash_scoped_token(token),
+                models::UserState::GlobalBan as i32
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .ok()?;
+
+            return match row {
+                Some(row) => Some(models::ResolvedToken {
+                    principal: models::Principal::FrostpawClient {
+                        user_id: row.user_id,
+                        client_id: row.client_id,
+                    },
+                    scopes: row.scopes.map(|scopes| {
+                        scopes
+                            .iter()
+                            .filter_map(|scope| models::TokenScope::from_name(scope))
+                            .collect()
+                    }),
+                }),
+                None => {
+                    error!("Frostpaw token not found");
+                    None
                 }
+            };
+        }
 
-                let row = sqlx::query!(
-                    "SELECT COUNT(*) FROM users WHERE user_id = $1 AND api_token = $2 AND state != $3",
-                    user_id,
-                    data.token,
-                    models::UserState::GlobalBan as i32
-                )
-                .fetch_one(&self.pool)
-                .await;
-                return match row {
-                    Ok(count) => count.count.unwrap_or(0) > 0,
-                    Err(_) => false,
-                };
+        let token = token.replace("User ", "");
+
+        if token.starts_with(converters::SCOPED_TOKEN_PREFIX) {
+            let (user_id, scopes) = self.resolve_scoped_token(models::TokenType::User, &token).await?;
+            return Some(models::ResolvedToken {
+                principal: models::Principal::User(user_id),
+                scopes,
+            });
+        }
+
+        let prefix = converters::api_token_prefix(&token);
+
+        let rows = sqlx::query!(
+            "SELECT user_id, api_token_hash FROM users WHERE api_token_prefix = $1 AND state != $2",
+            prefix,
+            models::UserState::GlobalBan as i32
+        )
+        .fetch_all(&self.pool)
+        .await
+        .ok()?;
+
+        for row in rows {
+            if converters::verify_api_token(&token, &row.api_token_hash.unwrap_or_default()) {
+                return Some(models::ResolvedToken::full(models::Principal::User(row.user_id)));
             }
-            error!("Frostpaw token not found");
-            return false;
         }
 
+        // Tokens which are not hashed yet
         let row = sqlx::query!(
-            "SELECT COUNT(*) FROM users WHERE user_id = $1 AND api_token = $2 AND state != $3",
-            user_id,
-            token.replace("User ", ""),
+            "UPDATE users SET api_token_prefix = $2, api_token_hash = $3, api_token = NULL 
+            WHERE api_token = $1 AND state != $4 RETURNING user_id",
+            token,
+            prefix,
+            converters::hash_api_token(&token),
             models::UserState::GlobalBan as i32
         )
-        .fetch_one(&self.pool)
-        .await;
-        match row {
-            Ok(count) => count.count.unwrap_or(0) > 0,
-            Err(_) => false,
-        }
+        .fetch_optional(&self.pool)
+        .await
+        .ok()??;
+
+        Some(models::ResolvedToken::full(models::Principal::User(row.user_id)))
     }
 
-    pub async fn authorize_bot(&self, bot_id: i64, token: &str) -> bool {
+    /// Resolves a bot token or scoped API token
+    pub async fn resolve_bot_token(&self, token: &str) -> Option<models::ResolvedToken> {
         if token.is_empty() {
-            return false;
+            return None;
         }
-        let row = sqlx::query!(
-            "SELECT COUNT(*) FROM bots WHERE bot_id = $1 AND api_token = $2",
-            bot_id,
-            token.replace("Bot ", ""),
-        )
-        .fetch_one(&self.pool)
-        .await;
-        match row {
-            Ok(count) => count.count.unwrap_or(0) > 0,
-            Err(_) => false,
+
+        let token = token.replace("Bot ", "");
+
+        if token.starts_with(converters::SCOPED_TOKEN_PREFIX) {
+            let (bot_id, scopes) = self.resolve_scoped_token(models::TokenType::Bot, &token).await?;
+            return Some(models::ResolvedToken {
+                principal: models::Principal::Bot(bot_id),
+                scopes,
+            });
         }
-    }
 
-    pub async fn authorize_server(&self, server_id: i64, token: &str) -> bool {
-        if token.is_empty() {
-            return false;
+        let prefix = converters::api_token_prefix(&token);
+
+        let rows = sqlx::query!(
+            "SELECT bot_id, api_token_hash FROM bots WHERE api_token_prefix = $1",
+            prefix,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .ok()?;
+
+        for row in rows {
+            if converters::verify_api_token(&token, &row.api_token_hash.unwrap_or_default()) {
+                return Some(models::ResolvedToken::full(models::Principal::Bot(row.bot_id)));
+            }
         }
+
+        // Tokens which are not hashed yet
         let row = sqlx::query!(
-            "SELECT COUNT(*) FROM servers WHERE guild_id = $1 AND api_token = $2",
-            server_id,
-            token.replace("Server ", ""),
+            "UPDATE bots SET api_token_prefix = $2, api_token_hash = $3, api_token = NULL 
+            WHERE api_token = $1 RETURNING bot_id",
+            token,
+            prefix,
+            converters::hash_api_token(&token),
         )
-        .fetch_one(&self.pool)
-        .await;
-        match row {
-            Ok(count) => count.count.unwrap_or(0) > 0,
-            Err(_) => false,
-        }
+        .fetch_optional(&self.pool)
+        .await
+        .ok()??;
+
+        Some(models::ResolvedToken::full(models::Principal::Bot(row.bot_id)))
     }
 
-    // Get bot and its helpers
-    pub async fn get_votes_per_month(&self, bot_id: i64) -> Vec<models::VotesPerMonth> {
-        let mut vpm = Vec::new();
-        let vpm_row = sqlx::query!(
-            "SELECT votes, epoch FROM bot_stats_votes_pm WHERE bot_id = $1",
-            bot_id
+    /// Resolves a server token
+    pub async fn resolve_server_token(&self, token: &str) -> Option<models::ResolvedToken> {
+        if token.is_empty() {
+            return None;
+        }
+
+        let token = token.replace("Server ", "");
+
+        let prefix = converters::api_token_prefix(&token);
+
+        let rows = sqlx::query!(
+            "SELECT guild_id, api_token_hash FROM servers WHERE api_token_prefix = $1",
+            prefix,
         )
         .fetch_all(&self.pool)
         .await
-        .unwrap();
+        .ok()?;
 
-        for row in vpm_row {
-            vpm.push(models::VotesPerMonth {
-                votes: row.votes.unwrap_or(0),
-                ts: Utc
-                    .timestamp_opt(row.epoch.unwrap_or(0), 0)
-                    .latest()
-                    .unwrap_or_else(|| {
-                        chrono::DateTime::<chrono::Utc>::from_utc(
-                            chrono::NaiveDateTime::from_timestamp(0, 0),
-                            chrono::Utc,
-                        )
-                    }),
-            });
+        for row in rows {
+            if converters::verify_api_token(&token, &row.api_token_hash.unwrap_or_default()) {
+                return Some(models::ResolvedToken::full(models::Principal::Server(row.guild_id)));
+            }
         }
 
-        vpm
+        // Tokens which are not hashed yet (server tokens are also created by the server bot)
+        let row = sqlx::query!(
+            "UPDATE servers SET api_token_prefix = $2, api_token_hash = $3, api_token = NULL 
+            WHERE api_token = $1 RETURNING guild_id",
+            token,
+            prefix,
+            converters::hash_api_token(&token),
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .ok()??;
+
+        Some(models::ResolvedToken::full(models::Principal::Server(row.guild_id)))
     }
 
-    pub async fn get_bot_tags(&self, bot_id: i64) -> Vec<models::Tag> {
-        let tag_rows = sqlx::query!("SELECT tag FROM bot_tags WHERE bot_id = $1", bot_id)
-        .fetch_all(&self.pool)
+    /// Looks up a (unexpired) token in ``api_tokens``, updating when it was last used. 
+    /// The scopes are ``None`` for logins
+    async fn resolve_scoped_token(&self, token_type: models::TokenType, token: &str) -> Option<(i64, Option<Vec<models::TokenScope>>)> {
+        let row = sqlx::query!(
+            "UPDATE api_tokens SET last_used_at = NOW() WHERE token_hash = $1 AND token_type = $2 
+            AND (expires_at IS NULL OR expires_at > NOW()) 
+            AND NOT EXISTS (
+                SELECT 1 FROM users WHERE users.user_id = api_tokens.target_id 
+                AND api_tokens.token_type = $3 AND users.state = $4
+            ) RETURNING target_id, scopes",
+            converters::hash_scoped_token(token),
+            token_type as i32,
+            models::TokenType::User as i32,
+            models::UserState::GlobalBan as i32
+        )
+        .fetch_optional(&self.pool)
         .await
-        .unwrap();
+        .ok()??;
 
-        let mut tags = Vec::new();
+        let scopes = row.scopes.map(|scopes| {
+            scopes
+                .iter()
+                .filter_map(|scope| models::TokenScope::from_name(scope))
+                .collect()
+        });
 
-        for tag in tag_rows {
-            // Get tag info
-            let tag_info =
-                sqlx::query!("SELECT icon FROM bot_list_tags WHERE id = $1", tag.tag)
-                    .fetch_one(&self.pool)
-                    .await
-                    .unwrap();
-            tags.push(models::Tag {
-                name: tag.tag.to_title_case(),
-                iconify_data: tag_info.icon,
-                id: tag.tag.to_string(),
-                owner_guild: None,
-            });
-        }
+        Some((row.target_id, scopes))
+    }
 
-        tags
+    /// Creates a full access token for a user logging in. Each login gets its own token as
+    /// the user token is only stored hashed
+    pub async fn create_login_token(&self, user_id: i64, origin: &models::SessionOrigin) -> Result<String, sqlx::Error> {
+        let token = converters::create_scoped_token();
+
+        sqlx::query!(
+            "INSERT INTO api_tokens (target_id, token_type, name, token_hash, scopes, origin, user_agent) 
+            VALUES ($1, $2, $3, $4, NULL, $5, $6)",
+            user_id,
+            models::TokenType::User as i32,
+            "Login",
+            converters::hash_scoped_token(&token),
+            origin.origin,
+            origin.user_agent
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(token)
     }
 
-    pub async fn get_bot_owners(&self, bot_id: i64) -> Vec<models::BotOwner> {
-        let owner_rows = sqlx::query!(
-            "SELECT owner, main FROM bot_owner WHERE bot_id = $1 ORDER BY main DESC",
-            bot_id
+    pub async fn revoke_login_token(&self, user_id: i64, token: &str) {
+        let res = sqlx::query!(
+            "DELETE FROM api_tokens WHERE target_id = $1 AND token_type = $2 AND token_hash = $3 AND scopes IS NULL",
+            user_id,
+            models::TokenType::User as i32,
+            converters::hash_scoped_token(token)
         )
-        .fetch_all(&self.pool)
+        .execute(&self.pool)
         .await;
 
-        if owner_rows.is_err() {
-            return Vec::new();
+        if res.is_err() {
+            error!("Failed to revoke login: {}", res.unwrap_err());
         }
+    }
 
-        let owner_rows = owner_rows.unwrap();
+    // OAuth2
+    //
+    // Codes and tokens are stored as their SHA-256. Tokens issued by the older claw login have no
+    // scopes (full access), only last 15 minutes and are refreshed using ``user_connections``
+    pub async fn create_oauth2_code(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        redirect_uri: &str,
+        scopes: &[models::TokenScope],
+        code_challenge: &str,
+    ) -> Result<String, sqlx::Error> {
+        let code = converters::create_token(64);
+
+        sqlx::query!(
+            "INSERT INTO oauth2_codes (code_hash, client_id, user_id, redirect_uri, scopes, code_challenge) 
+            VALUES ($1, $2, $3, $4, $5, $6)",
+            converters::hash_scoped_token(&code),
+            client_id,
+            user_id,
+            redirect_uri,
+            &scopes.iter().map(|scope| scope.as_str().to_string()).collect::<Vec<String>>(),
+            code_challenge
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(code)
+    }
+
+    /// Gets and deletes a authorization code so it can only be used once
+    pub async fn consume_oauth2_code(&self, code: &str) -> Option<models::Oauth2Code> {
+        let row = sqlx::query!(
+            "DELETE FROM oauth2_codes WHERE code_hash = $1 
+            RETURNING client_id, user_id, redirect_uri, scopes, code_challenge, expires_at",
+            converters::hash_scoped_token(code)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .ok()??;
+
+        Some(models::Oauth2Code {
+            client_id: row.client_id,
+            user_id: row.user_id,
+            redirect_uri: row.redirect_uri,
+            scopes: row.scopes.iter().filter_map(|scope| models::TokenScope::from_name(scope)).collect(),
+            code_challenge: row.code_challenge,
+            expires_at: row.expires_at,
+        })
+    }
+
+    /// Issues a access token (and a refresh token unless ``scopes`` is ``None``) to a client
+    pub async fn create_oauth2_token(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        scopes: Option<&[models::TokenScope]>,
+        origin: &models::SessionOrigin,
+    ) -> Result<models::Oauth2TokenResponse, sqlx::Error> {
+        let access_token = converters::create_access_token();
+
+        let (expires_in, refresh_token) = match scopes {
+            Some(_) => (60 * 60, Some(converters::create_token(128))),
+            None => (15 * 60, None),
+        };
+
+        let scope_names = scopes.map(|scopes| {
+            scopes
+                .iter()
+                .map(|scope| scope.as_str().to_string())
+                .collect::<Vec<String>>()
+        });
+
+        sqlx::query!(
+            "INSERT INTO oauth2_tokens (client_id, user_id, access_token_hash, refresh_token_hash, scopes, 
+            access_expires_at, refresh_expires_at, origin, user_agent) VALUES ($1, $2, $3, $4, $5, 
+            NOW() + make_interval(secs => $6), CASE WHEN $4::text IS NULL THEN NULL ELSE NOW() + interval '30 days' END, $7, $8)",
+            client_id,
+            user_id,
+            converters::hash_scoped_token(&access_token),
+            refresh_token.as_ref().map(|token| converters::hash_scoped_token(token)),
+            scope_names.as_deref(),
+            expires_in as f64,
+            origin.origin,
+            origin.user_agent
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(models::Oauth2TokenResponse {
+            access_token,
+            token_type: "Bearer".to_string(),
+            expires_in,
+            refresh_token,
+            scope: scope_names.unwrap_or_default().join(" "),
+        })
+    }
+
+    /// Exchanges a refresh token for a new access and refresh token. The old tokens stop working
+    pub async fn refresh_oauth2_token(
+        &self,
+        client_id: &str,
+        refresh_token: &str,
+        origin: &models::SessionOrigin,
+    ) -> Result<models::Oauth2TokenResponse, models::Oauth2Error> {
+        let row = sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE client_id = $1 AND refresh_token_hash = $2 AND refresh_expires_at > NOW() 
+            AND NOT EXISTS (SELECT 1 FROM users WHERE users.user_id = oauth2_tokens.user_id AND users.state = $3) 
+            RETURNING user_id, scopes",
+            client_id,
+            converters::hash_scoped_token(refresh_token),
+            models::UserState::GlobalBan as i32
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(models::Oauth2Error::ServerError)?
+        .ok_or_else(|| models::Oauth2Error::InvalidGrant("Invalid or expired refresh token".to_string()))?;
+
+        let scopes: Vec<models::TokenScope> = row
+            .scopes
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|scope| models::TokenScope::from_name(scope))
+            .collect();
+
+        self.create_oauth2_token(client_id, row.user_id, Some(&scopes), origin)
+            .await
+            .map_err(models::Oauth2Error::ServerError)
+    }
+
+    /// Introspects a access or refresh token issued to ``client_id`` (RFC 7662)
+    pub async fn introspect_oauth2_token(&self, client_id: &str, token: &str) -> models::Oauth2Introspection {
+        let hash = converters::hash_scoped_token(token);
+
+        let row = sqlx::query!(
+            "SELECT user_id, scopes, created_at, access_token_hash = $2 AS is_access, 
+            CASE WHEN access_token_hash = $2 THEN access_expires_at ELSE refresh_expires_at END AS expires_at 
+            FROM oauth2_tokens WHERE client_id = $1 AND (access_token_hash = $2 OR refresh_token_hash = $2) 
+            AND NOT EXISTS (SELECT 1 FROM users WHERE users.user_id = oauth2_tokens.user_id AND users.state = $3)",
+            client_id,
+            hash,
+            models::UserState::GlobalBan as i32
+        )
+        .fetch_optional(&self.pool)
+        .await;
+
+        match row {
+            Ok(Some(row)) if row.expires_at.map(|exp| exp > chrono::Utc::now()).unwrap_or(false) => {
+                models::Oauth2Introspection {
+                    active: true,
+                    scope: Some(row.scopes.unwrap_or_default().join(" ")),
+                    client_id: Some(client_id.to_string()),
+                    sub: Some(row.user_id.to_string()),
+                    token_type: Some(if row.is_access.unwrap_or(false) { "access_token" } else { "refresh_token" }.to_string()),
+                    exp: row.expires_at.map(|exp| exp.timestamp()),
+                    iat: Some(row.created_at.timestamp()),
+                }
+            }
+            Ok(_) => models::Oauth2Introspection::default(),
+            Err(err) => {
+                error!("Failed to introspect token: {}", err);
+                models::Oauth2Introspection::default()
+            }
+        }
+    }
+
+    /// Revokes a access or refresh token issued to ``client_id`` along with its pair (RFC 7009)
+    pub async fn revoke_oauth2_token(&self, client_id: &str, token: &str) -> Result<(), sqlx::Error> {
+        let hash = converters::hash_scoped_token(token);
+
+        sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE client_id = $1 AND (access_token_hash = $2 OR refresh_token_hash = $2)",
+            client_id,
+            hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Get bot and its helpers
+    pub async fn get_votes_per_month(&self, bot_id: i64) -> Vec<models::VotesPerMonth> {
+        let mut vpm = Vec::new();
+        let vpm_row = sqlx::query!(
+            "SELECT votes, epoch FROM bot_stats_votes_pm WHERE bot_id = $1",
+            bot_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap();
+
+        for row in vpm_row {
+            vpm.push(models::VotesPerMonth {
+                votes: row.votes.unwrap_or(0),
+                ts: Utc
+                    .timestamp_opt(row.epoch.unwrap_or(0), 0)
+                    .latest()
+                    .unwrap_or_else(|| {
+                        chrono::DateTime::<chrono::Utc>::from_utc(
+                            chrono::NaiveDateTime::from_timestamp(0, 0),
+                            chrono::Utc,
+                        )
+                    }),
+            });
+        }
+
+        vpm
+    }
+
+    pub async fn get_bot_tags(&self, bot_id: i64) -> Vec<models::Tag> {
+        let tag_rows = sqlx::query!("SELECT tag FROM bot_tags WHERE bot_id = $1", bot_id)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap();
+
+        let mut tags = Vec::new();
+
+        for tag in tag_rows {
+            // Get tag info
+            let tag_info =
+                sqlx::query!("SELECT icon FROM bot_list_tags WHERE id = $1", tag.tag)
+                    .fetch_one(&self.pool)
+                    .await
+                    .unwrap();
+            tags.push(models::Tag {
+                name: tag.tag.to_title_case(),
+                iconify_data: tag_info.icon,
+                id: tag.tag.to_string(),
+                owner_guild: None,
+            });
+        }
+
+        tags
+    }
+
+    /// Checks if a user is a owner (main or extra) of a bot
+    pub async fn is_bot_owner(&self, bot_id: i64, user_id: i64) -> bool {
+        let owners = self.get_bot_owners(bot_id).await;
+
+        for owner in owners {
+            if owner.user.id == user_id.to_string() {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub async fn get_bot_owners(&self, bot_id: i64) -> Vec<models::BotOwner> {
+        let owner_rows = sqlx::query!(
+            "SELECT owner, main FROM bot_owner WHERE bot_id = $1 ORDER BY main DESC",
+            bot_id
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        if owner_rows.is_err() {
+            return Vec::new();
+        }
+
+        let owner_rows = owner_rows.unwrap();
 
         let mut owners = Vec::new();
         for row in owner_rows {
@@ -1042,98 +1637,264 @@ impl Database {
         resolved_bots
     }
 
-    pub async fn search(&self, search: models::SearchQuery) -> models::Search {
-        // Get bots row
+    /// ``limit`` and ``offset`` are from ``SearchQuery::limits``
+    pub async fn search(&self, search: models::SearchQuery, limit: i64, offset: i64) -> models::Search {
+
+        // $1 is the prefix tsquery used for ranking, $2 is the raw query used for trigram (typo tolerant) matching
+        let tsquery = converters::search_tsquery(&search.q);
+        let query = search.q.trim().to_string();
+
+        // Filters
+        let tags = search.tag_list();
+        let features = search.feature_list();
+        let sort = search.sort.unwrap_or_default().as_str();
+
+        let state = if search.certified_only.unwrap_or(false) {
+            models::State::Certified
+        } else {
+            models::State::Approved
+        };
+
+        // Every query below filters into a ``matches`` CTE which both the page and the total are taken from. 
+        // The total is joined to the page so it is still returned (as a single row without a page item) past the last page
+
+        // Get bots row, facet counts are over every matching bot, not just the current page
         let bots_row = sqlx::query!(
-            "SELECT DISTINCT bots.bot_id, bots.created_at,
-            bots.description, bots.banner_card AS banner, bots.state, 
-            bots.votes, bots.flags, bots.guild_count FROM bots 
-            INNER JOIN bot_owner ON bots.bot_id = bot_owner.bot_id 
-            WHERE (bots.description ilike $1 
-            OR bots.long_description ilike $1 
-            OR bots.username_cached ilike $1 
-            OR bot_owner.owner::text ilike $1) 
-            AND (bots.state = $2 OR bots.state = $3) 
-            AND (bots.guild_count > $4)
-            AND (($5 = -1::bigint) OR (bots.guild_count < $5))
-            ORDER BY bots.votes DESC, bots.guild_count DESC LIMIT 6",
-            "%".to_string() + &search.q + "%",
-            models::State::Approved as i32,
+            "WITH matches AS (
+                SELECT bots.bot_id, bots.created_at,
+                bots.description, bots.banner_card AS banner, bots.state, 
+                bots.votes, bots.flags, bots.guild_count, bots.features, tags.list AS tags,
+                row_number() OVER (
+                    ORDER BY 
+                    CASE WHEN $15 = 'votes' THEN bots.votes END DESC NULLS LAST,
+                    CASE WHEN $15 = 'guild_count' THEN bots.guild_count END DESC NULLS LAST,
+                    CASE WHEN $15 = 'created_at' THEN bots.created_at END DESC NULLS LAST,
+                    CASE WHEN $15 = 'total_votes' THEN bots.total_votes END DESC NULLS LAST,
+                    CASE WHEN $15 = 'review_average' THEN reviews.average_stars END DESC NULLS LAST,
+                    (
+                        ts_rank(bots.search_vector, to_tsquery('english', $1)) 
+                        + coalesce(ts_rank(tags.vector, to_tsquery('simple', $1)), 0) 
+                        + word_similarity($2, bots.username_cached)
+                    ) DESC, bots.votes DESC, bots.guild_count DESC
+                ) AS position FROM bots 
+                LEFT JOIN LATERAL (
+                    SELECT to_tsvector('simple', string_agg(tag, ' ')) AS vector, 
+                    array_agg(tag) AS list FROM bot_tags WHERE bot_tags.bot_id = bots.bot_id
+                ) tags ON true
+                LEFT JOIN LATERAL (
+                    SELECT AVG(star_rating)::float8 AS average_stars FROM reviews 
+                    WHERE target_id = bots.bot_id AND target_type = 0 AND parent_id IS NULL AND NOT hidden
+                ) reviews ON true
+                WHERE ($2 = '' 
+                OR bots.search_vector @@ to_tsquery('english', $1) 
+                OR tags.vector @@ to_tsquery('simple', $1) 
+                OR word_similarity($2, bots.username_cached) > 0.4 
+                OR bots.bot_id::text = $2 
+                OR EXISTS (SELECT 1 FROM bot_owner WHERE bot_owner.bot_id = bots.bot_id AND bot_owner.owner::text = $2)) 
+                AND (bots.state = $3 OR bots.state = $4) 
+                AND (bots.guild_count > $5)
+                AND (($6 = -1::bigint) OR (bots.guild_count < $6))
+                AND ($9::text[] IS NULL OR tags.list @> $9)
+                AND ($10::text[] IS NULL OR bots.features @> $10)
+                AND ($11::text IS NULL OR bots.bot_library ILIKE $11)
+                AND ($12::bool IS NULL OR $12 = ($13 = ANY(bots.flags)))
+                AND ($14::float8 IS NULL OR reviews.average_stars >= $14)
+            ), stats AS (
+                SELECT COUNT(*) AS total, 
+                (
+                    SELECT jsonb_object_agg(tag, count) FROM (
+                        SELECT tag, COUNT(*) AS count FROM matches, unnest(matches.tags) AS tag GROUP BY tag
+                    ) tag_counts
+                ) AS tag_facets,
+                (
+                    SELECT jsonb_object_agg(feature, count) FROM (
+                        SELECT feature, COUNT(*) AS count FROM matches, unnest(matches.features) AS feature GROUP BY feature
+                    ) feature_counts
+                ) AS feature_facets 
+                FROM matches
+            )
+            SELECT stats.total AS \"total!\", stats.tag_facets AS \"tag_facets?\", stats.feature_facets AS \"feature_facets?\", 
+            page.bot_id AS \"bot_id?\", page.created_at AS \"created_at?\", page.description AS \"description?\", 
+            page.banner AS \"banner?\", page.state AS \"state?\", page.votes AS \"votes?\", page.flags AS \"flags?\", 
+            page.guild_count AS \"guild_count?\" FROM stats 
+            LEFT JOIN (
+                SELECT * FROM matches ORDER BY position LIMIT $7 OFFSET $8
+            ) page ON true ORDER BY page.position",
+            tsquery,
+            query,
+            state as i32,
             models::State::Certified as i32,
             search.gc_from,
-            search.gc_to
+            search.gc_to,
+            limit,
+            offset,
+            tags.as_deref(),
+            features.as_deref(),
+            search.library,
+            search.nsfw,
+            models::Flags::NSFW as i32,
+            search.min_stars,
+            sort
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
+
+        let mut totals = models::SearchTotals::default();
+        let mut facets = models::SearchFacets::default();
+
+        if let Some(stats) = bots_row.first() {
+            totals.bots = stats.total;
+            facets.tags = stats
+                .tag_facets
+                .clone()
+                .and_then(|tags| serde_json::from_value(tags).ok())
+                .unwrap_or_default();
+            facets.features = stats
+                .feature_facets
+                .clone()
+                .and_then(|features| serde_json::from_value(features).ok())
+                .unwrap_or_default();
+        }
+
         let mut bots = Vec::new();
         for bot in bots_row {
+            let (bot_id, created_at) = match (bot.bot_id, bot.created_at) {
+                (Some(bot_id), Some(created_at)) => (bot_id, created_at),
+                _ => continue, // Only the totals, past the last page
+            };
+
             bots.push(models::IndexBot {
                 guild_count: bot.guild_count.unwrap_or_default(),
-                description: bot.description,
+                description: bot.description.unwrap_or_default(),
                 banner: bot.banner.unwrap_or_default(),
                 votes: bot.votes.unwrap_or_default(),
-                state: models::State::try_from(bot.state).unwrap_or(models::State::Approved),
-                flags: bot.flags,
-                created_at: bot.created_at,
-                user: self.get_user(bot.bot_id).await,
+                state: models::State::try_from(bot.state.unwrap_or_default()).unwrap_or(models::State::Approved),
+                flags: bot.flags.unwrap_or_default(),
+                created_at,
+                user: self.get_user(bot_id).await,
             });
         }
 
         // Get servers row
         let servers_row = sqlx::query!(
-            "SELECT DISTINCT servers.guild_id, servers.created_at,
-            servers.description, servers.banner_card, servers.state,
-            servers.votes, servers.guild_count, servers.flags FROM servers
-            WHERE (servers.description ilike $1
-            OR servers.long_description ilike $1
-            OR servers.name_cached ilike $1) AND servers.state = $2
-            ORDER BY servers.votes DESC, servers.guild_count DESC LIMIT 6",
-            "%".to_string() + &search.q + "%",
-            models::State::Approved as i32,
+            "WITH matches AS (
+                SELECT servers.guild_id, servers.created_at,
+                servers.description, servers.banner_card, servers.state,
+                servers.votes, servers.guild_count, servers.flags, 
+                row_number() OVER (
+                    ORDER BY 
+                    CASE WHEN $10 = 'votes' THEN servers.votes END DESC NULLS LAST,
+                    CASE WHEN $10 = 'guild_count' THEN servers.guild_count END DESC NULLS LAST,
+                    CASE WHEN $10 = 'created_at' THEN servers.created_at END DESC NULLS LAST,
+                    CASE WHEN $10 = 'total_votes' THEN servers.total_votes END DESC NULLS LAST,
+                    CASE WHEN $10 = 'review_average' THEN reviews.average_stars END DESC NULLS LAST,
+                    (
+                        ts_rank(servers.search_vector, to_tsquery('english', $1)) 
+                        + ts_rank(to_tsvector('simple', array_to_string(servers.tags, ' ')), to_tsquery('simple', $1)) 
+                        + word_similarity($2, servers.name_cached)
+                    ) DESC, servers.votes DESC, servers.guild_count DESC
+                ) AS position FROM servers
+                LEFT JOIN LATERAL (
+                    SELECT AVG(star_rating)::float8 AS average_stars FROM reviews 
+                    WHERE target_id = servers.guild_id AND target_type = 1 AND parent_id IS NULL AND NOT hidden
+                ) reviews ON true
+                WHERE ($2 = '' 
+                OR servers.search_vector @@ to_tsquery('english', $1)
+                OR to_tsvector('simple', array_to_string(servers.tags, ' ')) @@ to_tsquery('simple', $1)
+                OR word_similarity($2, servers.name_cached) > 0.4
+                OR servers.guild_id::text = $2) AND servers.state = $3
+                AND ($6::text[] IS NULL OR servers.tags @> $6)
+                AND ($7::bool IS NULL OR $7 = ($8 = ANY(servers.flags)))
+                AND ($9::float8 IS NULL OR reviews.average_stars >= $9)
+            )
+            SELECT stats.total AS \"total!\", page.guild_id AS \"guild_id?\", page.created_at AS \"created_at?\", 
+            page.description AS \"description?\", page.banner_card AS \"banner_card?\", page.state AS \"state?\", 
+            page.votes AS \"votes?\", page.guild_count AS \"guild_count?\", page.flags AS \"flags?\" FROM (SELECT COUNT(*) AS total FROM matches) stats 
+            LEFT JOIN (
+                SELECT * FROM matches ORDER BY position LIMIT $4 OFFSET $5
+            ) page ON true ORDER BY page.position",
+            tsquery,
+            query,
+            state as i32,
+            limit,
+            offset,
+            tags.as_deref(),
+            search.nsfw,
+            models::Flags::NSFW as i32,
+            search.min_stars,
+            sort
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
 
+        totals.servers = servers_row.first().map(|stats| stats.total).unwrap_or_default();
+
         let mut servers = Vec::new();
 
         for server in servers_row {
+            let (guild_id, created_at) = match (server.guild_id, server.created_at) {
+                (Some(guild_id), Some(created_at)) => (guild_id, created_at),
+                _ => continue, // Only the totals, past the last page
+            };
+
             servers.push(models::IndexBot {
                 guild_count: server.guild_count.unwrap_or(0),
-                description: server.description,
+                description: server.description.unwrap_or_default(),
                 banner: server.banner_card.unwrap_or_else(|| {
                     self.discord_config.fates_api_url.to_string() + "/static/assets/prod/banner.webp"
                 }),
-                state: models::State::try_from(server.state).unwrap_or(models::State::Approved),
+                state: models::State::try_from(server.state.unwrap_or_default()).unwrap_or(models::State::Approved),
                 votes: server.votes.unwrap_or(0),
-                flags: server.flags,
-                created_at: server.created_at,
-                user: self.get_server_user(server.guild_id).await,
+                flags: server.flags.unwrap_or_default(),
+                created_at,
+                user: self.get_server_user(guild_id).await,
             });
         }
 
         // Profiles
         let profiles_row = sqlx::query!(
-            "SELECT DISTINCT users.user_id, users.description FROM users 
-            INNER JOIN bot_owner ON users.user_id = bot_owner.owner 
-            INNER JOIN bots ON bot_owner.bot_id = bots.bot_id 
-            WHERE ((bots.state = 0 OR bots.state = 6) 
-            AND (bots.username_cached ilike $1 OR bots.description ilike $1 OR bots.bot_id::text ilike $1)) 
-            OR (users.username ilike $1) LIMIT 12", 
-            "%".to_string()+&search.q+"%",
+            "WITH matches AS (
+                SELECT users.user_id, users.description, 
+                row_number() OVER (ORDER BY word_similarity($2, users.username) DESC, users.user_id) AS position FROM users 
+                WHERE $2 = '' 
+                OR word_similarity($2, users.username) > 0.4 
+                OR users.user_id::text = $2 
+                OR EXISTS (
+                    SELECT 1 FROM bot_owner INNER JOIN bots ON bot_owner.bot_id = bots.bot_id 
+                    WHERE bot_owner.owner = users.user_id AND (bots.state = 0 OR bots.state = 6) 
+                    AND (bots.search_vector @@ to_tsquery('english', $1) OR bots.bot_id::text = $2)
+                )
+            )
+            SELECT stats.total AS \"total!\", page.user_id AS \"user_id?\", page.description AS \"description?\" 
+            FROM (SELECT COUNT(*) AS total FROM matches) stats 
+            LEFT JOIN (
+                SELECT * FROM matches ORDER BY position LIMIT $3 OFFSET $4
+            ) page ON true ORDER BY page.position", 
+            tsquery,
+            query,
+            limit,
+            offset
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
 
+        totals.profiles = profiles_row.first().map(|stats| stats.total).unwrap_or_default();
+
         let mut profiles = Vec::new();
 
         for profile in profiles_row {
+            let user_id = match profile.user_id {
+                Some(user_id) => user_id,
+                None => continue, // Only the totals, past the last page
+            };
+
             profiles.push(models::SearchProfile {
                 banner: self.discord_config.fates_api_url.to_string() + "/static/assets/prod/banner.webp",
                 description: profile.description.unwrap_or_default(),
-                user: self.get_user(profile.user_id).await,
+                user: self.get_user(user_id).await,
             });
         }
 
@@ -1145,68 +1906,126 @@ impl Database {
 
         // Packs
         let packs_row = sqlx::query!(
-            "SELECT DISTINCT bot_packs.id, bot_packs.icon, bot_packs.banner, 
-            bot_packs.created_at, bot_packs.owner, bot_packs.bots, 
-            bot_packs.description, bot_packs.name FROM (
-                SELECT id, icon, banner, 
-                created_at, owner, bots, 
-                description, name, unnest(bots) AS bot_id FROM bot_packs
-            ) bot_packs
-            INNER JOIN bots ON bots.bot_id = bot_packs.bot_id 
-            INNER JOIN users ON users.user_id = bot_packs.owner
-            WHERE bot_packs.name ilike $1 OR bot_packs.owner::text 
-            ilike $1 OR users.username ilike $1 OR bots.bot_id::text ilike $1 
-            OR bots.username_cached ilike $1",
-            "%".to_string() + &search.q + "%",
+            "WITH matches AS (
+                SELECT bot_packs.id, bot_packs.icon, bot_packs.banner, 
+                bot_packs.created_at, bot_packs.owner, bot_packs.bots, 
+                bot_packs.description, bot_packs.name, 
+                row_number() OVER (
+                    ORDER BY (
+                        ts_rank(to_tsvector('english', bot_packs.name || ' ' || coalesce(bot_packs.description, '')), to_tsquery('english', $1)) 
+                        + word_similarity($2, bot_packs.name)
+                    ) DESC, bot_packs.created_at DESC
+                ) AS position FROM bot_packs
+                INNER JOIN users ON users.user_id = bot_packs.owner
+                WHERE $2 = '' 
+                OR to_tsvector('english', bot_packs.name || ' ' || coalesce(bot_packs.description, '')) @@ to_tsquery('english', $1) 
+                OR word_similarity($2, bot_packs.name) > 0.4 
+                OR word_similarity($2, users.username) > 0.4 
+                OR bot_packs.owner::text = $2 
+                OR EXISTS (
+                    SELECT 1 FROM bots WHERE bots.bot_id = ANY(bot_packs.bots) 
+                    AND (bots.bot_id::text = $2 OR bots.search_vector @@ to_tsquery('english', $1))
+                )
+            )
+            SELECT stats.total AS \"total!\", page.id AS \"id?\", page.icon AS \"icon?\", page.banner AS \"banner?\", 
+            page.created_at AS \"created_at?\", page.owner AS \"owner?\", page.bots AS \"bots?\", 
+            page.description AS \"description?\", page.name AS \"name?\" FROM (SELECT COUNT(*) AS total FROM matches) stats 
+            LEFT JOIN (
+                SELECT * FROM matches ORDER BY position LIMIT $3 OFFSET $4
+            ) page ON true ORDER BY page.position",
+            tsquery,
+            query,
+            limit,
+            offset
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
 
+        totals.packs = packs_row.first().map(|stats| stats.total).unwrap_or_default();
+
         let mut packs = Vec::new();
 
         for pack in packs_row {
+            let (id, owner) = match (pack.id, pack.owner) {
+                (Some(id), Some(owner)) => (id, owner),
+                _ => continue, // Only the totals, past the last page
+            };
+
             packs.push(models::BotPack {
-                id: pack.id.to_string(),
-                name: pack.name.to_string(),
-                description: pack.description,
+                id: id.to_string(),
+                name: pack.name.unwrap_or_default(),
+                description: pack.description.unwrap_or_default(),
                 icon: pack.icon.unwrap_or_default(),
                 banner: pack.banner.unwrap_or_else(|| {
                     self.discord_config.fates_api_url.to_string() + "/static/assets/prod/banner.webp"
                 }),
-                owner: self.get_user(pack.owner).await,
+                owner: self.get_user(owner).await,
                 created_at: pack.created_at.unwrap_or_else(|| {
                     chrono::DateTime::<chrono::Utc>::from_utc(
                         chrono::NaiveDateTime::from_timestamp(0, 0),
                         chrono::Utc,
                     )
                 }),
-                resolved_bots: self.resolve_pack_bots(pack.bots).await,
+                resolved_bots: self.resolve_pack_bots(pack.bots.unwrap_or_default()).await,
             });
         }
 
-        models::Search { bots, servers, profiles, packs, tags }
+        models::Search { 
+            bots, 
+            servers, 
+            profiles, 
+            packs, 
+            tags, 
+            totals, 
+            facets, 
+            next_cursor: None, // Ranked search uses pages
+            prev_cursor: None,
+        }
     }
 
     // Search bot/server tags
-    pub async fn search_tags(&self, tag: &String) -> models::Search {
+    pub async fn search_tags(&self, tag: &String, cursor: Option<&models::Cursor>, limit: i64) -> models::Search {
         let rows = sqlx::query!(
-            "SELECT DISTINCT bots.bot_id, bots.description, bots.state, bots.created_at, 
-            bots.banner_card, bots.flags, bots.votes, bots.guild_count FROM bots INNER JOIN bot_tags 
-            ON bot_tags.bot_id = bots.bot_id WHERE bot_tags.tag = $1 AND 
+            "SELECT bots.bot_id, bots.description, bots.state, bots.created_at, 
+            bots.banner_card, bots.flags, bots.votes, bots.guild_count FROM bots 
+            WHERE EXISTS (SELECT 1 FROM bot_tags WHERE bot_tags.bot_id = bots.bot_id AND bot_tags.tag = $1) AND 
             (
                 bots.state = 0 
                 OR bots.state = 6
-            ) ORDER BY bots.votes DESC LIMIT 15",
-            tag
+            ) AND (
+                $2::bigint IS NULL
+                OR (NOT $4 AND (coalesce(bots.votes, 0), bots.bot_id) < ($2, $3))
+                OR ($4 AND (coalesce(bots.votes, 0), bots.bot_id) > ($2, $3))
+            ) ORDER BY 
+            CASE WHEN $4 THEN coalesce(bots.votes, 0) END ASC, CASE WHEN $4 THEN bots.bot_id END ASC, 
+            coalesce(bots.votes, 0) DESC, bots.bot_id DESC LIMIT $5",
+            tag,
+            cursor.map(|c| c.key),
+            cursor.map(|c| c.id_i64()),
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
 
+        let total_bots = sqlx::query!(
+            "SELECT COUNT(*) FROM bots WHERE EXISTS (
+                SELECT 1 FROM bot_tags WHERE bot_tags.bot_id = bots.bot_id AND bot_tags.tag = $1
+            ) AND (bots.state = 0 OR bots.state = 6)",
+            tag
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map(|row| row.count.unwrap_or_default())
+        .unwrap_or_default();
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| (row.votes.unwrap_or(0), row.bot_id.to_string()));
+
         let mut bots = Vec::new();
 
-        for row in rows {
+        for row in page.items {
             bots.push(models::IndexBot {
                 guild_count: row.guild_count.unwrap_or(0),
                 description: row.description,
@@ -1249,6 +2068,14 @@ impl Database {
         }
 
         models::Search {
+            totals: models::SearchTotals {
+                bots: total_bots,
+                servers: servers.len() as i64,
+                ..models::SearchTotals::default()
+            },
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+            facets: models::SearchFacets::default(), // Not applicable
             bots,
             servers,
             tags: models::SearchTags {
@@ -1321,74 +2148,207 @@ impl Database {
         index_bot
     }
 
-    pub async fn ws_event<T: 'static + Serialize + Clone + Sync>(&self, event: models::Event<T>) {
-        let mut conn = self.redis.get().await.unwrap();
-        // Push to required channel
-        let hashmap = indexmap![
-            event.m.eid.clone() => &event
-        ];
-        let message: String = serde_json::to_string(&hashmap).unwrap();
-        let channel: String =
-            models::TargetType::to_arg(event.ctx.target_type).to_owned() + "-" + &event.ctx.target;
+    /// Persists an event with the next sequence number of its target, returning it as published (``{eid: event}``).
+    /// The sequence row stays locked until commit so events of a target are always committed in order
+    async fn persist_ws_event<T: 'static + Serialize + Clone + Sync>(
+        &self,
+        target_id: i64,
+        target_type: &str,
+        event: &mut models::Event<T>,
+    ) -> Result<IndexMap<String, serde_json::Value>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
 
-        let _: () = conn.publish(channel, message).await.unwrap();
+        let seq = sqlx::query!(
+            "INSERT INTO ws_event_seqs (id, type, seq) VALUES ($1, $2, 1) 
+            ON CONFLICT (id, type) DO UPDATE SET seq = ws_event_seqs.seq + 1 RETURNING seq",
+            target_id,
+            target_type
+        )
+        .fetch_one(&mut tx)
+        .await?;
 
-        let target_id = event.ctx.target.parse::<i64>().unwrap();
+        event.m.seq = seq.seq;
 
-        let target_type: &str = match event.ctx.target_type {
-            models::TargetType::Bot => "bot",
-            models::TargetType::Server => "server",
-        };
+        let hashmap = indexmap![
+            event.m.eid.clone() => json!(&event)
+        ];
 
         sqlx::query!(
-            "INSERT INTO ws_events (id, type, event) VALUES ($1, $2, $3)",
+            "INSERT INTO ws_events (id, type, event, seq) VALUES ($1, $2, $3, $4)",
             target_id,
             target_type,
-            json!(hashmap)
+            json!(hashmap),
+            event.m.seq
         )
-        .execute(&self.pool)
-        .await
-        .unwrap();
-    }
+        .execute(&mut tx)
+        .await?;
 
-    pub async fn create_user_oauth(
-        &self,
-        user: models::OauthUser,
-    ) -> Result<models::OauthUserLogin, sqlx::Error> {
-        let user_i64 = user.id.parse::<i64>().unwrap();
+        tx.commit().await?;
 
-        let check = sqlx::query!(
-            "SELECT state, api_token, user_css, username, site_lang FROM users WHERE user_id = $1",
-            user_i64,
-        )
-        .fetch_one(&self.pool)
-        .await;
+        Ok(hashmap)
+    }
 
-        let token: String;
-        let mut site_lang: Option<String> = Some("en".to_string());
-        let mut css: Option<String> = Some("".to_string());
-        let mut state = models::UserState::Normal;
+    /// Persists (if sequenced, so resuming clients never miss it) and then publishes an event
+    pub async fn ws_event<T: 'static + Serialize + Clone + Sync>(&self, mut event: models::Event<T>) {
+        let target_id = match event.ctx.target.parse::<i64>() {
+            Ok(target_id) => target_id,
+            Err(err) => {
+                error!("Invalid target {} for event {}: {}", event.ctx.target, event.m.eid, err);
+                return;
+            }
+        };
 
-        match check {
-            Ok(user) => {
-                token = user.api_token; // This must always exist, we *should* panic if not
-                site_lang = user.site_lang;
-                css = user.user_css;
-                state =
-                    models::UserState::try_from(user.state).unwrap_or(models::UserState::Normal);
+        let target_type: &str = match event.ctx.target_type {
+            models::TargetType::Bot => "bot",
+            models::TargetType::Server => "server",
+        };
+
+        let hashmap = if event.m.e.sequenced() {
+            match self.persist_ws_event(target_id, target_type, &mut event).await {
+                Ok(hashmap) => hashmap,
+                Err(err) => {
+                    error!("Failed to persist event {}: {}", event.m.eid, err);
+                    return;
+                }
             }
+        } else {
+            indexmap![
+                event.m.eid.clone() => json!(&event)
+            ]
+        };
+
+        // Push to required channel
+        let mut conn = match self.redis.get().await {
+            Ok(conn) => conn,
             Err(err) => {
-                match err {
-                    sqlx::Error::RowNotFound => {
-                        // We create the new user
-                        token = converters::create_token(128);
-                        sqlx::query!(
-                            "INSERT INTO users (id, user_id, username, user_css, site_lang, api_token) VALUES ($1, $1, $2, $3, $4, $5)",
-                            user_i64,
+                error!("Failed to get redis connection to publish event {}: {}", event.m.eid, err);
+                return;
+            }
+        };
+
+        let message: String = serde_json::to_string(&hashmap).unwrap_or_default();
+        let channel: String =
+            models::TargetType::to_arg(event.ctx.target_type).to_owned() + "-" + &event.ctx.target;
+
+        let res: Result<(), _> = conn.publish(channel, message).await;
+        if let Err(err) = res {
+            error!("Failed to publish event {}: {}", event.m.eid, err);
+        }
+
+        // Fan out to the targets webhook if its subscribed to this event
+        self.webhook_event(target_id, &event).await;
+    }
+
+    /// Queues a event for the targets webhook if it is subscribed to it (see ``webhook_events``)
+    ///
+    /// Vote events are skipped here as the vote handlers send the legacy ``VoteWebhookEvent`` payload instead
+    async fn webhook_event<T: Serialize + Clone + Sync>(&self, target_id: i64, event: &models::Event<T>) {
+        if event.m.e == models::EventName::BotVote || event.m.e == models::EventName::ServerVote {
+            return;
+        }
+
+        let target = match self.get_webhook_target(target_id, event.ctx.target_type).await {
+            Ok(target) => target,
+            Err(err) => {
+                error!("Failed to get webhook for event {}: {}", event.m.eid, err);
+                return;
+            }
+        };
+
+        let webhook = match target.url {
+            Some(ref webhook) if target.events & event.m.e.webhook_bit() != 0 => webhook,
+            _ => return,
+        };
+
+        if let Err(err) = self.enqueue_webhook(target_id, event.ctx.target_type, &event.m.eid, webhook, json!(event)).await {
+            error!("Failed to queue webhook for event {}: {}", event.m.eid, err);
+        }
+    }
+
+    /// Gets the webhook of a bot/server for ``webhook_event``. This is cached, so call
+    /// ``webhook_target_cache.invalidate`` after changing the webhook or its events
+    async fn get_webhook_target(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+    ) -> Result<Arc<models::WebhookTarget>, sqlx::Error> {
+        if let Some(target) = self.webhook_target_cache.get(&(target_type, target_id)) {
+            return Ok(target);
+        }
+
+        let (webhook, webhook_type, events) = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!(
+                    "SELECT webhook, webhook_type, webhook_events FROM bots WHERE bot_id = $1",
+                    target_id
+                )
+                .fetch_one(&self.pool)
+                .await
+                .map(|row| (row.webhook, row.webhook_type, row.webhook_events))?
+            }
+            models::TargetType::Server => {
+                sqlx::query!(
+                    "SELECT webhook, webhook_type, webhook_events FROM servers WHERE guild_id = $1",
+                    target_id
+                )
+                .fetch_one(&self.pool)
+                .await
+                .map(|row| (row.webhook, row.webhook_type, row.webhook_events))?
+            }
+        };
+
+        let target = Arc::new(models::WebhookTarget {
+            // Discord integrations only support votes
+            url: webhook.filter(|webhook| {
+                !webhook.is_empty() && webhook_type != Some(models::WebhookType::DiscordIntegration as i32)
+            }),
+            events,
+        });
+
+        self.webhook_target_cache.insert((target_type, target_id), target.clone()).await;
+
+        Ok(target)
+    }
+
+    pub async fn create_user_oauth(
+        &self,
+        user: models::OauthUser,
+        origin: &models::SessionOrigin,
+    ) -> Result<models::OauthUserLogin, sqlx::Error> {
+        let user_i64 = user.id.parse::<i64>().unwrap();
+
+        let check = sqlx::query!(
+            "SELECT state, user_css, username, site_lang FROM users WHERE user_id = $1",
+            user_i64,
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        let mut site_lang: Option<String> = Some("en".to_string());
+        let mut css: Option<String> = Some("".to_string());
+        let mut state = models::UserState::Normal;
+
+        match check {
+            Ok(user) => {
+                site_lang = user.site_lang;
+                css = user.user_css;
+                state =
+                    models::UserState::try_from(user.state).unwrap_or(models::UserState::Normal);
+            }
+            Err(err) => {
+                match err {
+                    sqlx::Error::RowNotFound => {
+                        // We create the new user
+                        let user_token = converters::create_token(128);
+                        sqlx::query!(
+                            "INSERT INTO users (id, user_id, username, user_css, site_lang, api_token_prefix, api_token_hash) 
+                            VALUES ($1, $1, $2, $3, $4, $5, $6)",
+                            user_i64,
                             user.username,
                             css, // User css is always initially nothing
                             site_lang,
-                            token,
+                            converters::api_token_prefix(&user_token),
+                            converters::hash_api_token(&user_token),
                         )
                         .execute(&self.pool)
                         .await
@@ -1403,6 +2363,8 @@ impl Database {
             }
         }
 
+        let token = self.create_login_token(user_i64, origin).await?;
+
         Ok(models::OauthUserLogin {
             user: models::User {
                 id: user.id.clone(),
@@ -1423,7 +2385,7 @@ impl Database {
         })
     }
 
-    pub async fn get_user_bot_voted(&self, bot_id: i64, user_id: i64) -> models::UserVoted {
+    pub async fn get_user_bot_voted(&self, bot_id: i64, user_id: i64, cursor: Option<&models::Cursor>, limit: i64, vote_rules: &models::VoteRules) -> models::UserVoted {
         let voter_ts = sqlx::query!(
             "SELECT timestamps FROM bot_voters WHERE bot_id = $1 AND user_id = $2",
             bot_id,
@@ -1461,16 +2423,21 @@ impl Database {
         
         let vote_len = vote_ts.len().try_into().unwrap();
 
+        let page = converters::paginate_timestamps(vote_ts, cursor, limit);
+
         models::UserVoted {
             votes: vote_len,
             expiry,
             vote_right_now: now > expiry,
             voted: vote_len > 0,
-            timestamps: vote_ts,
+            timestamps: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+            weight: self.get_vote_multiplier(bot_id, models::TargetType::Bot, vote_rules).await,
         }
     }
 
-    pub async fn get_user_server_voted(&self, server_id: i64, user_id: i64) -> models::UserVoted {
+    pub async fn get_user_server_voted(&self, server_id: i64, user_id: i64, cursor: Option<&models::Cursor>, limit: i64, vote_rules: &models::VoteRules) -> models::UserVoted {
         let voter_ts = sqlx::query!(
             "SELECT timestamps FROM server_voters WHERE guild_id = $1 AND user_id = $2",
             server_id,
@@ -1508,12 +2475,17 @@ impl Database {
         
         let vote_len = vote_ts.len().try_into().unwrap();
 
+        let page = converters::paginate_timestamps(vote_ts, cursor, limit);
+
         models::UserVoted {
             votes: vote_len,
             expiry,
             vote_right_now: now > expiry,
             voted: vote_len > 0,
-            timestamps: vote_ts,
+            timestamps: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+            weight: self.get_vote_multiplier(server_id, models::TargetType::Server, vote_rules).await,
         }
     }
 
@@ -1622,7 +2594,8 @@ impl Database {
         Ok(())
     }
 
-    /// Calls get bot and then fills in `api_token`, `webhook` and `webhook_secret`
+    /// Calls get bot and then fills in `webhook` and `webhook_secret`. The bot token is only 
+    /// stored hashed and is only shown when it is created using ``new_bot_token``
     pub async fn get_bot_settings(
         &self,
         bot_id: i64,
@@ -1633,7 +2606,7 @@ impl Database {
             .ok_or(models::GenericError::NotFound)?;
 
         let sensitive = sqlx::query!(
-            "SELECT api_token, webhook, webhook_secret, webhook_hmac_only
+            "SELECT webhook, webhook_secret, webhook_hmac_only
              FROM bots WHERE bot_id = $1",
             bot_id
         )
@@ -1642,7 +2615,7 @@ impl Database {
         .map_err(models::GenericError::SQLError)?;
 
         let sensitive_bot = models::Bot {
-            api_token: sensitive.api_token,
+            api_token: None,
             webhook: sensitive.webhook,
             webhook_secret: sensitive.webhook_secret,
             webhook_hmac_only: Some(sensitive.webhook_hmac_only.unwrap_or(false)),
@@ -1793,52 +2766,54 @@ impl Database {
 
     // Security functions
 
-    pub async fn new_bot_token(&self, bot_id: i64) {
+    /// Issues a new bot token. Only the hash is stored so this is the only time the token can be seen
+    pub async fn new_bot_token(&self, bot_id: i64) -> String {
         let new_token = converters::create_token(128);
         sqlx::query!(
-            "UPDATE bots SET api_token = $1 WHERE bot_id = $2",
-            new_token,
+            "UPDATE bots SET api_token = NULL, api_token_prefix = $1, api_token_hash = $2 WHERE bot_id = $3",
+            converters::api_token_prefix(&new_token),
+            converters::hash_api_token(&new_token),
             bot_id
         )
         .execute(&self.pool)
         .await
         .unwrap();
+
+        new_token
     }
 
-    pub async fn new_user_token(&self, user_id: i64) {
+    /// Issues a new user token, logging the user out everywhere
+    pub async fn new_user_token(&self, user_id: i64) -> String {
         let new_token = converters::create_token(128);
         sqlx::query!(
-            "UPDATE users SET api_token = $1 WHERE user_id = $2",
-            new_token,
+            "UPDATE users SET api_token = NULL, api_token_prefix = $1, api_token_hash = $2 WHERE user_id = $3",
+            converters::api_token_prefix(&new_token),
+            converters::hash_api_token(&new_token),
             user_id
         )
         .execute(&self.pool)
         .await
         .unwrap();
 
-        // Also invalidate all possible clients
-        let regen = sqlx::query!(
-            "DELETE FROM user_connections WHERE user_id = $1",
-            user_id
-        )
-        .execute(&self.pool)
-        .await;
+        self.logout_everywhere(user_id).await;
 
-        if regen.is_err() {
-            error!("Failed to invalidate user connections: {}", regen.unwrap_err());
-        }
+        new_token
     }
 
-    pub async fn new_server_token(&self, server_id: i64) {
+    /// Issues a new server token. Only the hash is stored so this is the only time the token can be seen
+    pub async fn new_server_token(&self, server_id: i64) -> String {
         let new_token = converters::create_token(128);
         sqlx::query!(
-            "UPDATE servers SET api_token = $1 WHERE guild_id = $2",
-            new_token,
+            "UPDATE servers SET api_token = NULL, api_token_prefix = $1, api_token_hash = $2 WHERE guild_id = $3",
+            converters::api_token_prefix(&new_token),
+            converters::hash_api_token(&new_token),
             server_id
         )
         .execute(&self.pool)
         .await
         .unwrap();
+
+        new_token
     }
 
     pub async fn revoke_client(&self, user_id: i64, client_id: String) {
@@ -1851,26 +2826,308 @@ impl Database {
         .await
         .unwrap();
 
-        // Invalidate all other tokens
-        for (key, value) in self.client_data.iter() {
-            if value.user_id == user_id && value.client_id == client_id {
-                self.client_data.invalidate(&key).await;
+        sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE user_id = $1 AND client_id = $2",
+            user_id,
+            client_id
+        )
+        .execute(&self.pool)
+        .await
+        .unwrap();
+    }
+
+    /// Revokes the tokens a client got from the older claw login, used when refreshing them
+    pub async fn revoke_legacy_client_tokens(&self, user_id: i64, client_id: &str) {
+        let res = sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE user_id = $1 AND client_id = $2 AND scopes IS NULL",
+            user_id,
+            client_id
+        )
+        .execute(&self.pool)
+        .await;
+
+        if res.is_err() {
+            error!("Failed to revoke client tokens: {}", res.unwrap_err());
+        }
+    }
+
+    // Sessions
+    //
+    // A session is a login (a api_tokens row with no scopes), a token pair of a Frostpaw client or
+    // a refresh token from the older claw login (user_connections)
+
+    /// Lists the sessions of a user, newest first. ``current_token`` is the token used to make the request
+    pub async fn get_sessions(&self, user_id: i64, current_token: &str) -> Vec<models::Session> {
+        let current_hash = converters::hash_scoped_token(current_token);
+
+        let mut sessions = Vec::new();
+
+        let logins = sqlx::query!(
+            "SELECT id, created_at, last_used_at, expires_at, origin, user_agent, token_hash = $3 AS current 
+            FROM api_tokens WHERE target_id = $1 AND token_type = $2 AND scopes IS NULL",
+            user_id,
+            models::TokenType::User as i32,
+            current_hash
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        for login in logins {
+            sessions.push(models::Session {
+                id: login.id.to_string(),
+                session_type: models::SessionType::Login,
+                client: None,
+                created_at: login.created_at,
+                last_used_at: login.last_used_at,
+                expires_at: login.expires_at,
+                origin: login.origin,
+                user_agent: login.user_agent,
+                current: login.current.unwrap_or(false),
+            });
+        }
+
+        let mut clients: HashMap<String, Option<models::FrostpawClient>> = HashMap::new();
+
+        let tokens = sqlx::query!(
+            "SELECT id, client_id, created_at, last_used_at, origin, user_agent, access_token_hash = $2 AS current, 
+            COALESCE(refresh_expires_at, access_expires_at) AS expires_at FROM oauth2_tokens 
+            WHERE user_id = $1 AND COALESCE(refresh_expires_at, access_expires_at) > NOW()",
+            user_id,
+            current_hash
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        for token in tokens {
+            if !clients.contains_key(&token.client_id) {
+                clients.insert(token.client_id.clone(), self.get_frostpaw_client(&token.client_id).await);
+            }
+
+            sessions.push(models::Session {
+                id: token.id.to_string(),
+                session_type: models::SessionType::Client,
+                client: clients.get(&token.client_id).cloned().flatten(),
+                created_at: token.created_at,
+                last_used_at: token.last_used_at,
+                expires_at: token.expires_at,
+                origin: token.origin,
+                user_agent: token.user_agent,
+                current: token.current.unwrap_or(false),
+            });
+        }
+
+        // These have no created time so it is worked out from the expiry (7 days)
+        let connections = sqlx::query!(
+            "SELECT _lynxtag, client_id, expires_on FROM user_connections WHERE user_id = $1 AND expires_on > NOW()",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        for conn in connections {
+            if !clients.contains_key(&conn.client_id) {
+                clients.insert(conn.client_id.clone(), self.get_frostpaw_client(&conn.client_id).await);
             }
+
+            sessions.push(models::Session {
+                id: conn._lynxtag.to_string(),
+                session_type: models::SessionType::ClientConnection,
+                client: clients.get(&conn.client_id).cloned().flatten(),
+                created_at: conn.expires_on - chrono::Duration::days(7),
+                last_used_at: None,
+                expires_at: Some(conn.expires_on),
+                origin: None,
+                user_agent: None,
+                current: false,
+            });
+        }
+
+        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        sessions
+    }
+
+    pub async fn revoke_session(&self, user_id: i64, session_id: uuid::Uuid) -> Result<(), models::GenericError> {
+        let logins = sqlx::query!(
+            "DELETE FROM api_tokens WHERE id = $1 AND target_id = $2 AND token_type = $3 AND scopes IS NULL",
+            session_id,
+            user_id,
+            models::TokenType::User as i32
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        let tokens = sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE id = $1 AND user_id = $2",
+            session_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        let connections = sqlx::query!(
+            "DELETE FROM user_connections WHERE _lynxtag = $1 AND user_id = $2",
+            session_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        if logins.rows_affected() + tokens.rows_affected() + connections.rows_affected() == 0 {
+            return Err(models::GenericError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Revokes every session of a user. Their user token (and scoped API tokens) keep working
+    pub async fn logout_everywhere(&self, user_id: i64) {
+        let logins = sqlx::query!(
+            "DELETE FROM api_tokens WHERE target_id = $1 AND token_type = $2 AND scopes IS NULL",
+            user_id,
+            models::TokenType::User as i32
+        )
+        .execute(&self.pool)
+        .await;
+
+        if logins.is_err() {
+            error!("Failed to invalidate user logins: {}", logins.unwrap_err());
+        }
+
+        // Also invalidate all possible clients
+        let tokens = sqlx::query!(
+            "DELETE FROM oauth2_tokens WHERE user_id = $1",
+            user_id
+        )
+        .execute(&self.pool)
+        .await;
+
+        if tokens.is_err() {
+            error!("Failed to invalidate client tokens: {}", tokens.unwrap_err());
+        }
+
+        let regen = sqlx::query!(
+            "DELETE FROM user_connections WHERE user_id = $1",
+            user_id
+        )
+        .execute(&self.pool)
+        .await;
+
+        if regen.is_err() {
+            error!("Failed to invalidate user connections: {}", regen.unwrap_err());
+        }
+    }
+
+    pub async fn get_api_tokens(&self, target_id: i64, token_type: models::TokenType) -> Vec<models::ApiToken> {
+        let rows = sqlx::query!(
+            "SELECT id, name, scopes, created_at, expires_at, last_used_at FROM api_tokens 
+            WHERE target_id = $1 AND token_type = $2 AND scopes IS NOT NULL ORDER BY created_at DESC",
+            target_id,
+            token_type as i32
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        rows.into_iter()
+            .map(|row| models::ApiToken {
+                id: row.id.to_string(),
+                name: row.name,
+                scopes: row.scopes.unwrap_or_default().iter().filter_map(|scope| models::TokenScope::from_name(scope)).collect(),
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+                last_used_at: row.last_used_at,
+            })
+            .collect()
+    }
+
+    pub async fn create_api_token(
+        &self,
+        target_id: i64,
+        token_type: models::TokenType,
+        token: &models::CreateApiToken,
+    ) -> Result<models::NewApiToken, models::ApiTokenError> {
+        let count = sqlx::query!(
+            "SELECT COUNT(*) FROM api_tokens WHERE target_id = $1 AND token_type = $2 AND scopes IS NOT NULL",
+            target_id,
+            token_type as i32
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(models::ApiTokenError::SQLError)?;
+
+        if count.count.unwrap_or(0) >= 25 {
+            return Err(models::ApiTokenError::TooManyTokens);
+        }
+
+        let new_token = converters::create_scoped_token();
+
+        let row = sqlx::query!(
+            "INSERT INTO api_tokens (target_id, token_type, name, token_hash, scopes, expires_at) 
+            VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            target_id,
+            token_type as i32,
+            token.name,
+            converters::hash_scoped_token(&new_token),
+            &token.scopes.iter().map(|scope| scope.as_str().to_string()).collect::<Vec<String>>(),
+            token.expires_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(models::ApiTokenError::SQLError)?;
+
+        Ok(models::NewApiToken {
+            id: row.id.to_string(),
+            token: new_token,
+        })
+    }
+
+    pub async fn delete_api_token(
+        &self,
+        target_id: i64,
+        token_type: models::TokenType,
+        token_id: uuid::Uuid,
+    ) -> Result<(), models::ApiTokenError> {
+        let res = sqlx::query!(
+            "DELETE FROM api_tokens WHERE id = $1 AND target_id = $2 AND token_type = $3 AND scopes IS NOT NULL",
+            token_id,
+            target_id,
+            token_type as i32
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::ApiTokenError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::ApiTokenError::NotFound);
         }
+
+        Ok(())
     }
 
     pub async fn add_bot(&self, bot: &models::Bot) -> Result<(), sqlx::Error> {
         let id = bot.user.id.parse::<i64>().unwrap();
         let client_id = bot.client_id.parse::<i64>().unwrap_or(id);
 
+        // Owners get a token using ``new_bot_token``, only the hash of this one is ever stored
+        let api_token = converters::create_token(128);
+
         // Step 1: Delete old stale data
         let mut tx = self.pool.begin().await?;
         sqlx::query!("DELETE FROM bots WHERE bot_id = $1", id)
             .execute(&mut tx)
             .await?;
-        sqlx::query!("DELETE FROM bot_owner WHERE bot_id = $1", id)
-            .execute(&mut tx)
+        let stale_owners = sqlx::query!("DELETE FROM bot_owner WHERE bot_id = $1 RETURNING owner", id)
+            .fetch_all(&mut tx)
             .await?;
+
+        let mut owner_changes: Vec<i64> = stale_owners.into_iter().map(|row| row.owner).collect();
         sqlx::query!("DELETE FROM vanity WHERE redirect = $1", id)
             .execute(&mut tx)
             .await?;
@@ -1900,11 +3157,11 @@ impl Database {
             bot_id, prefix, bot_library,
             invite, banner_card, banner_page,
             long_description, description,
-            api_token, features, long_description_type, 
+            api_token_hash, features, long_description_type, 
             css, webhook, webhook_type, webhook_secret, webhook_hmac_only,
             extra_links, client_id, guild_count, flags, page_style, 
-            id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 
-            $13, $14, $15, $16, $17, $18, $19, $20, $21, $1)",
+            id, api_token_prefix) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, 
+            $13, $14, $15, $16, $17, $18, $19, $20, $21, $1, $22)",
             id,
             bot.prefix,
             bot.library,
@@ -1917,7 +3174,7 @@ impl Database {
             bot.banner_page,
             bot.long_description,
             bot.description,
-            converters::create_token(132),
+            converters::hash_api_token(&api_token),
             &features,
             bot.long_description_type as i32,
             bot.css,
@@ -1929,7 +3186,8 @@ impl Database {
             client_id,
             bot.guild_count,
             &flags,
-            bot.page_style as i32
+            bot.page_style as i32,
+            converters::api_token_prefix(&api_token)
         )
         .execute(&mut tx)
         .await?;
@@ -1946,6 +3204,7 @@ impl Database {
 
         // Handle bot owners
         for owner in &bot.owners {
+            owner_changes.push(owner.user.id.parse::<i64>().unwrap());
             sqlx::query!(
                 "INSERT INTO bot_owner (bot_id, owner, main) VALUES ($1, $2, $3)",
                 id,
@@ -1969,6 +3228,8 @@ impl Database {
 
         tx.commit().await?;
 
+        self.notify_owner_change(&owner_changes).await;
+
         Ok(())
     }
 
@@ -2043,18 +3304,22 @@ impl Database {
         .execute(&mut tx)
         .await?;
 
-        sqlx::query!(
-            "DELETE FROM bot_owner WHERE bot_id = $1 AND main = false",
+        let removed_owners = sqlx::query!(
+            "DELETE FROM bot_owner WHERE bot_id = $1 AND main = false RETURNING owner",
             id
         )
-        .execute(&mut tx)
+        .fetch_all(&mut tx)
         .await?;
 
+        // Users whose owned bots may have changed, for user gateways
+        let mut owner_changes: Vec<i64> = removed_owners.into_iter().map(|row| row.owner).collect();
+
         // Handle bot owners
         for owner in &bot.owners {
             if owner.main {
                 continue;
             }
+            owner_changes.push(owner.user.id.parse::<i64>().unwrap());
             sqlx::query!(
                 "INSERT INTO bot_owner (bot_id, owner, main) VALUES ($1, $2, $3)",
                 id,
@@ -2080,17 +3345,24 @@ impl Database {
             .await?;
         }
 
-        sqlx::query!(
-            "INSERT INTO user_bot_logs (user_id, bot_id, action) VALUES ($1, $2, $3)",
-            user_id,
-            id,
-            models::UserBotAction::EditBot as i32
+        Database::insert_audit_log(
+            &mut tx,
+            &models::NewAuditLog {
+                target_id: id,
+                target_type: models::AuditTargetType::Bot,
+                actor_id: user_id,
+                action: models::AuditAction::EditBot,
+                context: None,
+            },
         )
-        .execute(&mut tx)
         .await?;
 
         tx.commit().await?;
 
+        self.webhook_target_cache.invalidate(&(models::TargetType::Bot, id)).await;
+
+        self.notify_owner_change(&owner_changes).await;
+
         Ok(())
     }
 
@@ -2127,47 +3399,131 @@ impl Database {
         .await
         .unwrap();
 
-        sqlx::query!(
-            "INSERT INTO user_bot_logs (user_id, bot_id, action, context) VALUES ($1, $2, $3, $4)",
-            prev_owner,
-            bot_id,
-            models::UserBotAction::TransferOwnership as i32,
-            owner.user.id
+        Database::insert_audit_log(
+            &mut tx,
+            &models::NewAuditLog {
+                target_id: bot_id,
+                target_type: models::AuditTargetType::Bot,
+                actor_id: prev_owner,
+                action: models::AuditAction::TransferOwnership,
+                context: Some(owner.user.id.clone()),
+            },
         )
-        .execute(&mut tx)
         .await
         .unwrap();
 
         tx.commit().await.unwrap();
+
+        self.notify_owner_change(&[prev_owner, owner.user.id.parse::<i64>().unwrap()]).await;
     }
 
-    pub async fn delete_bot(&self, user_id: i64, bot_id: i64) -> Result<(), sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
+    /// Tells the user gateways of these users that the bots/servers they own have changed
+    pub async fn notify_owner_change(&self, user_ids: &[i64]) {
+        let mut conn = match self.redis.get().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                error!("Failed to notify owner change: {}", err);
+                return;
+            }
+        };
+
+        for user_id in user_ids {
+            let res: Result<(), deadpool_redis::redis::RedisError> = conn.publish("user-".to_string() + &user_id.to_string(), "OWNERCHANGE").await;
+
+            if let Err(err) = res {
+                error!("Failed to notify owner change: {}", err);
+            }
+        }
+    }
+
+    /// Returns every bot and server a user owns (including as a extra owner)
+    pub async fn get_user_targets(&self, user_id: i64) -> Vec<models::GatewayTarget> {
+        let mut targets = Vec::new();
+
+        let bots = sqlx::query!(
+            "SELECT DISTINCT bot_id FROM bot_owner WHERE owner = $1",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        match bots {
+            Ok(bots) => {
+                for bot in bots {
+                    targets.push(models::GatewayTarget {
+                        id: bot.bot_id.to_string(),
+                        target_type: models::TargetType::Bot,
+                    });
+                }
+            }
+            Err(err) => error!("Failed to get user bots: {}", err),
+        }
+
+        let servers = sqlx::query!(
+            "SELECT guild_id FROM servers WHERE owner_id = $1",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        match servers {
+            Ok(servers) => {
+                for server in servers {
+                    targets.push(models::GatewayTarget {
+                        id: server.guild_id.to_string(),
+                        target_type: models::TargetType::Server,
+                    });
+                }
+            }
+            Err(err) => error!("Failed to get user servers: {}", err),
+        }
+
+        targets
+    }
+
+    pub async fn delete_bot(&self, user_id: i64, bot_id: i64) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let owners = sqlx::query!("DELETE FROM bot_owner WHERE bot_id = $1 RETURNING owner", bot_id)
+            .fetch_all(&mut tx)
+            .await?;
 
         sqlx::query!("DELETE FROM bots WHERE bot_id = $1", bot_id)
             .execute(&mut tx)
             .await?;
 
         sqlx::query!(
-            "DELETE FROM vanity WHERE redirect = $1 AND type = 1",
-            bot_id
+            "DELETE FROM api_tokens WHERE target_id = $1 AND token_type = $2",
+            bot_id,
+            models::TokenType::Bot as i32
         )
         .execute(&mut tx)
         .await?;
 
         sqlx::query!(
-            "INSERT INTO user_bot_logs (user_id, bot_id, action, context) VALUES ($1, $2, $3, $4)",
-            user_id,
-            bot_id,
-            models::UserBotAction::DeleteBot as i32,
-            "".to_string(),
+            "DELETE FROM vanity WHERE redirect = $1 AND type = 1",
+            bot_id
         )
         .execute(&mut tx)
-        .await
-        .unwrap();
+        .await?;
+
+        Database::insert_audit_log(
+            &mut tx,
+            &models::NewAuditLog {
+                target_id: bot_id,
+                target_type: models::AuditTargetType::Bot,
+                actor_id: user_id,
+                action: models::AuditAction::DeleteBot,
+                context: None,
+            },
+        )
+        .await?;
 
         tx.commit().await?;
 
+        let owners: Vec<i64> = owners.into_iter().map(|row| row.owner).collect();
+        self.notify_owner_change(&owners).await;
+
         Ok(())
     }
 
@@ -2265,7 +3621,12 @@ impl Database {
         if default_user_experiments.is_ok() {
             let experiments = default_user_experiments.unwrap();
             for experiment in experiments.default_user_experiments.unwrap_or_default() {
-                user_experiments.push(models::UserExperiments::try_from(experiment).unwrap_or(models::UserExperiments::Unknown))
+                let experiment = models::UserExperiments::try_from(experiment).unwrap_or(models::UserExperiments::Unknown);
+
+                // The dev portal needs explicit whitelisting
+                if experiment != models::UserExperiments::DevPortal {
+                    user_experiments.push(experiment)
+                }
             }
         }
 
@@ -2412,42 +3773,34 @@ impl Database {
         // Get user connections
         let mut connections = Vec::new();
 
+        // Clients using OAuth2 are connected as long as they have a refresh token
         let connections_row = sqlx::query!(
-            "SELECT COUNT(client_id) AS count, client_id FROM user_connections WHERE user_id = $1 GROUP BY client_id",
+            "SELECT COUNT(*) AS count, client_id, MAX(expires_on) AS expires_on FROM (
+                SELECT client_id, expires_on FROM user_connections WHERE user_id = $1
+                UNION ALL
+                SELECT client_id, refresh_expires_at AS expires_on FROM oauth2_tokens 
+                WHERE user_id = $1 AND refresh_expires_at > NOW()
+            ) AS conns GROUP BY client_id",
             user_id
         )
         .fetch_all(&self.pool)
         .await;
 
-        let mut used_ids = Vec::new();
-
         if let Ok(conns) = connections_row {
             for conn in conns {
-                if used_ids.contains(&conn.client_id) {
-                    continue;
-                }
-                used_ids.push(conn.client_id.clone());
+                let client_id = conn.client_id.unwrap_or_default();
 
-                let cli = self.get_frostpaw_client(&conn.client_id).await;
+                let cli = self.get_frostpaw_client(&client_id).await;
 
                 if cli.is_none() {
                     continue;
                 }
 
-                let expires_on = sqlx::query!(
-                    "SELECT expires_on From user_connections WHERE user_id = $1 AND client_id = $2 ORDER BY expires_on DESC LIMIT 1",
-                    user_id,
-                    conn.client_id
-                )
-                .fetch_one(&self.pool)
-                .await
-                .unwrap();
-
                 connections.push(models::FrostpawUserConnection {
                     client: cli.unwrap(),
-                    expires_on: expires_on.expires_on,
+                    expires_on: conn.expires_on.unwrap_or_else(chrono::Utc::now),
                     user_id,
-                    repeats: conn.count.unwrap(),
+                    repeats: conn.count.unwrap_or_default(),
                 });
             }
         }
@@ -2605,21 +3958,57 @@ impl Database {
     }
 
     // Reviews
+
+    /// Gets a page of the (visible) replies of a review along with ``depth - 1`` levels of their replies
     #[async_recursion]
-    async fn get_review_replies(&self, parent_id: uuid::Uuid) -> Vec<models::Review> {
+    pub async fn get_review_replies(
+        &self,
+        parent_id: uuid::Uuid,
+        order: models::ReplyOrder,
+        cursor: Option<models::Cursor>,
+        limit: i64,
+        depth: i32,
+    ) -> models::CursorPage<models::Review> {
+        let cursor_id = cursor.as_ref().map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
+        // Newest first unless walking backwards
+        let desc = (order == models::ReplyOrder::Newest) != cursor.as_ref().map(|c| c.rev).unwrap_or(false);
+
         let rows = sqlx::query!(
-            "SELECT id, user_id, star_rating, epoch, review_text, flagged FROM reviews 
-            WHERE parent_id = $1",
+            "SELECT id, user_id, star_rating, epoch, review_text, flagged, owner_response, 
+            EXISTS(SELECT 1 FROM reviews r WHERE r.parent_id = reviews.id AND NOT r.hidden) AS \"has_replies!\" 
+            FROM reviews WHERE parent_id = $1 AND NOT hidden AND NOT owner_response AND (
+                $2::bigint IS NULL
+                OR ($4 AND (coalesce(epoch[1], 0), id) < ($2, $3::uuid))
+                OR (NOT $4 AND (coalesce(epoch[1], 0), id) > ($2, $3::uuid))
+            ) ORDER BY 
+            CASE WHEN $4 THEN coalesce(epoch[1], 0) END DESC, CASE WHEN $4 THEN id END DESC, 
+            coalesce(epoch[1], 0) ASC, id ASC LIMIT $5",
             parent_id,
+            cursor.as_ref().map(|c| c.key),
+            cursor_id,
+            desc,
+            limit + 1
         )
         .fetch_all(&self.pool)
-        .await
-        .unwrap();
+        .await;
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("Error getting review replies: {}", err);
+                Vec::new()
+            }
+        };
+
+        let page = models::Cursor::paginate(rows, limit, cursor.as_ref(), |row| {
+            (row.epoch.first().copied().unwrap_or_default(), row.id.to_string())
+        });
 
         let mut reviews = Vec::new();
 
-        for row in rows {
-            reviews.push(models::Review {
+        for row in page.items {
+            let mut review = models::Review {
                 id: Some(row.id),
                 user: self.get_user(row.user_id).await,
                 star_rating: row.star_rating,
@@ -2627,12 +4016,133 @@ impl Database {
                 votes: self.get_review_votes(row.id).await,
                 review_text: row.review_text,
                 flagged: row.flagged,
-                replies: self.get_review_replies(row.id).await,
+                hidden: false,
+                owner_response: row.owner_response,
+                response: None,
+                replies: Vec::new(),
+                more_replies: row.has_replies,
+                replies_cursor: None,
                 parent_id: Some(parent_id),
-            });
+            };
+
+            if depth > 1 && row.has_replies {
+                let replies = self.get_review_replies(row.id, order, None, limit, depth - 1).await;
+
+                review.more_replies = replies.next_cursor.is_some();
+                review.replies_cursor = replies.next_cursor;
+                review.replies = replies.items;
+            }
+
+            reviews.push(review);
+        }
+
+        models::CursorPage {
+            items: reviews,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
+    }
+
+    /// Loads the owner response and the first ``inline_depth`` levels of replies of a top level review
+    async fn load_review_thread(
+        &self,
+        review: &mut models::Review,
+        order: models::ReplyOrder,
+        threads: &models::ReviewThreadConfig,
+    ) {
+        let review_id = match review.id {
+            Some(review_id) => review_id,
+            None => return,
+        };
+
+        let depth = threads.inline_depth.max(1);
+
+        let replies = self
+            .get_review_replies(review_id, order, None, threads.inline_replies, depth)
+            .await;
+
+        review.more_replies = replies.next_cursor.is_some();
+        review.replies_cursor = replies.next_cursor;
+        review.replies = replies.items;
+
+        let response = sqlx::query!(
+            "SELECT id, user_id, star_rating, epoch, review_text, flagged, 
+            EXISTS(SELECT 1 FROM reviews r WHERE r.parent_id = reviews.id AND NOT r.hidden) AS \"has_replies!\" 
+            FROM reviews WHERE parent_id = $1 AND owner_response AND NOT hidden",
+            review_id
+        )
+        .fetch_optional(&self.pool)
+        .await;
+
+        match response {
+            Ok(Some(row)) => {
+                let mut response = models::Review {
+                    id: Some(row.id),
+                    user: self.get_user(row.user_id).await,
+                    star_rating: row.star_rating,
+                    epoch: row.epoch,
+                    votes: self.get_review_votes(row.id).await,
+                    review_text: row.review_text,
+                    flagged: row.flagged,
+                    hidden: false,
+                    owner_response: true,
+                    response: None,
+                    replies: Vec::new(),
+                    more_replies: row.has_replies,
+                    replies_cursor: None,
+                    parent_id: Some(review_id),
+                };
+
+                // Replies to the response count towards the depth like any other reply
+                if depth > 1 && row.has_replies {
+                    let replies = self
+                        .get_review_replies(row.id, order, None, threads.inline_replies, depth - 1)
+                        .await;
+
+                    response.more_replies = replies.next_cursor.is_some();
+                    response.replies_cursor = replies.next_cursor;
+                    response.replies = replies.items;
+                }
+
+                review.response = Some(Box::new(response));
+            }
+            Ok(None) => {}
+            Err(err) => error!("Error getting owner response: {}", err),
         }
+    }
+
+    /// Gets where a review is in its thread, ``None`` if the review does not exist
+    pub async fn get_review_thread(&self, review_id: uuid::Uuid) -> Option<models::ReviewThread> {
+        let row = sqlx::query!(
+            "WITH RECURSIVE chain AS (
+                SELECT id, parent_id, 0 AS depth FROM reviews WHERE id = $1
+                UNION ALL
+                SELECT reviews.id, reviews.parent_id, chain.depth + 1 FROM reviews 
+                INNER JOIN chain ON reviews.id = chain.parent_id WHERE chain.depth < 100
+            )
+            SELECT target_id, target_type, (SELECT MAX(depth) FROM chain) AS \"depth!\", 
+            EXISTS(SELECT 1 FROM reviews WHERE parent_id = $1 AND owner_response) AS \"has_owner_response!\" 
+            FROM reviews WHERE id = $1",
+            review_id
+        )
+        .fetch_optional(&self.pool)
+        .await;
 
-        reviews
+        match row {
+            Ok(row) => row.map(|row| models::ReviewThread {
+                target_id: row.target_id,
+                target_type: match row.target_type {
+                    Some(1) => models::TargetType::Server,
+                    _ => models::TargetType::Bot,
+                },
+                depth: row.depth,
+                has_owner_response: row.has_owner_response,
+            }),
+            Err(err) => {
+                error!("Error getting review thread: {}", err);
+                None
+            }
+        }
     }
 
     pub async fn get_reviews(
@@ -2641,7 +4151,10 @@ impl Database {
         target_type: models::TargetType,
         limit: i64,
         offset: i64,
-    ) -> Vec<models::Review> {
+        cursor: Option<&models::Cursor>,
+        order: models::ReplyOrder,
+        threads: &models::ReviewThreadConfig,
+    ) -> models::CursorPage<models::Review> {
         let mut reviews = Vec::new();
 
         let target_type_num = match target_type {
@@ -2649,35 +4162,63 @@ impl Database {
             models::TargetType::Server => 1,
         };
 
+        // Reviews are ordered newest first, the first epoch is when the review was made
+        let cursor_id = cursor.map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
         // trim(stringexpression) != ''
         let rows = sqlx::query!(
             "SELECT id, user_id, star_rating, epoch, review_text, flagged FROM reviews 
-            WHERE target_id = $1 AND target_type = $2 AND parent_id IS NULL 
-            LIMIT $3 OFFSET $4",
+            WHERE target_id = $1 AND target_type = $2 AND parent_id IS NULL AND NOT hidden AND (
+                $5::bigint IS NULL
+                OR (NOT $7 AND (coalesce(epoch[1], 0), id) < ($5, $6::uuid))
+                OR ($7 AND (coalesce(epoch[1], 0), id) > ($5, $6::uuid))
+            ) ORDER BY 
+            CASE WHEN $7 THEN coalesce(epoch[1], 0) END ASC, CASE WHEN $7 THEN id END ASC, 
+            coalesce(epoch[1], 0) DESC, id DESC LIMIT $3 OFFSET $4",
             target_id,
             target_type_num,
-            limit,
-            offset
+            limit + 1,
+            offset,
+            cursor.map(|c| c.key),
+            cursor_id,
+            cursor.map(|c| c.rev).unwrap_or(false)
         )
         .fetch_all(&self.pool)
         .await
         .unwrap();
 
-        for row in rows {
-            reviews.push(models::Review {
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.epoch.first().copied().unwrap_or_default(), row.id.to_string())
+        });
+
+        for row in page.items {
+            let mut review = models::Review {
                 id: Some(row.id),
                 user: self.get_user(row.user_id).await,
                 review_text: row.review_text,
                 epoch: row.epoch,
                 flagged: row.flagged,
+                hidden: false,
+                owner_response: false,
                 votes: self.get_review_votes(row.id).await,
                 star_rating: row.star_rating,
-                replies: self.get_review_replies(row.id).await,
+                response: None,
+                replies: Vec::new(),
+                more_replies: false,
+                replies_cursor: None,
                 parent_id: None,
-            });
+            };
+
+            self.load_review_thread(&mut review, order, threads).await;
+
+            reviews.push(review);
         }
 
-        reviews
+        models::CursorPage {
+            items: reviews,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
     }
 
     pub async fn get_review_stats(
@@ -2691,7 +4232,7 @@ impl Database {
         };
 
         let stats = sqlx::query!(
-            "SELECT COUNT(*), AVG(star_rating) AS average_stars FROM reviews WHERE target_id = $1 AND target_type = $2 AND parent_id IS NULL",
+            "SELECT COUNT(*), AVG(star_rating) AS average_stars FROM reviews WHERE target_id = $1 AND target_type = $2 AND parent_id IS NULL AND NOT hidden",
             target_id,
             target_type_num
         )
@@ -2720,9 +4261,11 @@ impl Database {
         user_id: i64,
         target_id: i64,
         target_type: models::TargetType,
+        order: models::ReplyOrder,
+        threads: &models::ReviewThreadConfig,
     ) -> Option<models::Review> {
         let review = sqlx::query!(
-            "SELECT id, review_text, epoch, star_rating, flagged FROM reviews 
+            "SELECT id, review_text, epoch, star_rating, flagged, hidden FROM reviews 
             WHERE target_id = $1 AND target_type = $2 AND user_id = $3 AND parent_id 
             IS NULL",
             target_id,
@@ -2738,26 +4281,37 @@ impl Database {
 
         let row = review.unwrap();
 
-        return Some(models::Review {
+        let mut review = models::Review {
             id: Some(row.id),
             user: self.get_user(user_id).await,
             review_text: row.review_text,
             epoch: row.epoch,
             flagged: row.flagged,
+            hidden: row.hidden,
+            owner_response: false,
             votes: self.get_review_votes(row.id).await,
             star_rating: row.star_rating,
-            replies: self.get_review_replies(row.id).await,
+            response: None,
+            replies: Vec::new(),
+            more_replies: false,
+            replies_cursor: None,
             parent_id: None,
-        });
+        };
+
+        self.load_review_thread(&mut review, order, threads).await;
+
+        Some(review)
     }
 
+    /// Adds a review, reviews with a ``filter_reason`` (from the spam filter) are flagged and hidden
     pub async fn add_review(
         &self,
         review: models::Review,
         user_id: i64,
         target_id: i64,
         target_type: models::TargetType,
-    ) -> Result<(), sqlx::Error> {
+        filter_reason: Option<String>,
+    ) -> Result<uuid::Uuid, sqlx::Error> {
         let review_id = uuid::Uuid::new_v4();
 
         let review_type = match target_type {
@@ -2767,8 +4321,8 @@ impl Database {
 
         sqlx::query!(
             "INSERT INTO reviews (id, user_id, target_id, target_type, parent_id, 
-            star_rating, review_text, flagged) 
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            star_rating, review_text, flagged, hidden, filter_reason, owner_response) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8, $9, $10)",
             review_id,
             user_id,
             target_id,
@@ -2776,20 +4330,26 @@ impl Database {
             review.parent_id,
             review.star_rating,
             review.review_text,
-            review.flagged
+            filter_reason.is_some(),
+            filter_reason,
+            review.owner_response
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(review_id)
     }
 
-    pub async fn edit_review(&self, review: models::Review) -> Result<(), sqlx::Error> {
+    /// Edits a review. Edits flagged by the spam filter hide the review, clean edits do not unhide it
+    pub async fn edit_review(&self, review: models::Review, filter_reason: Option<String>) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "UPDATE reviews SET star_rating = $1, review_text = $2 WHERE id = $3",
+            "UPDATE reviews SET star_rating = $1, review_text = $2, flagged = flagged OR $4, 
+            hidden = hidden OR $4, filter_reason = COALESCE($5, filter_reason) WHERE id = $3",
             review.star_rating,
             review.review_text,
             review.id,
+            filter_reason.is_some(),
+            filter_reason
         )
         .execute(&self.pool)
         .await?;
@@ -2835,7 +4395,7 @@ impl Database {
     /// Gets a single review (including replies)
     pub async fn get_single_review(&self, review_id: uuid::Uuid) -> Option<models::Review> {
         let row = sqlx::query!(
-            "SELECT id, user_id, review_text, epoch, star_rating, flagged, parent_id 
+            "SELECT id, user_id, review_text, epoch, star_rating, flagged, hidden, owner_response, parent_id 
             FROM reviews WHERE id = $1",
             review_id,
         )
@@ -2854,9 +4414,14 @@ impl Database {
             review_text: row.review_text,
             epoch: row.epoch,
             flagged: row.flagged,
+            hidden: row.hidden,
+            owner_response: row.owner_response,
             votes: self.get_review_votes(row.id).await,
             star_rating: row.star_rating,
+            response: None,
             replies: Vec::new(),
+            more_replies: false,
+            replies_cursor: None,
             parent_id: row.parent_id,
         });
     }
@@ -2889,6 +4454,253 @@ impl Database {
         Ok(())
     }
 
+    /// Runs the spam filter on the text of a review. ``review_id`` is the review being edited, if any
+    pub async fn filter_review(
+        &self,
+        user_id: i64,
+        review_id: Option<uuid::Uuid>,
+        text: &str,
+        rules: &models::ReviewFilterConfig,
+    ) -> models::ReviewFilterResult {
+        let mut result = rules.score(text);
+
+        if !rules.enabled {
+            return result;
+        }
+
+        let duplicate = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM reviews WHERE user_id = $1 AND lower(review_text) = lower($2) 
+            AND ($3::uuid IS NULL OR id <> $3)) AS \"exists!\"",
+            user_id,
+            text,
+            review_id
+        )
+        .fetch_one(&self.pool)
+        .await;
+
+        match duplicate {
+            Ok(row) if row.exists => result.add(rules.duplicate_score, "duplicate review"),
+            Ok(_) => {}
+            Err(err) => error!("Error checking for duplicate reviews: {}", err),
+        }
+
+        result
+    }
+
+    pub async fn report_review(
+        &self,
+        review_id: uuid::Uuid,
+        user_id: i64,
+        report: &models::ReviewReport,
+    ) -> Result<(), models::ReviewReportError> {
+        let author = sqlx::query!("SELECT user_id FROM reviews WHERE id = $1", review_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(models::ReviewReportError::SQLError)?
+            .ok_or(models::ReviewReportError::NotFound)?
+            .user_id;
+
+        if author == user_id {
+            return Err(models::ReviewReportError::OwnReview);
+        }
+
+        let res = sqlx::query!(
+            "INSERT INTO review_reports (review_id, user_id, reason, context) VALUES ($1, $2, $3, $4) 
+            ON CONFLICT (review_id, user_id) DO NOTHING",
+            review_id,
+            user_id,
+            report.reason as i32,
+            report.context
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::ReviewReportError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::ReviewReportError::AlreadyReported);
+        }
+
+        Ok(())
+    }
+
+    /// Returns reviews that were flagged or have open reports, oldest first
+    pub async fn get_review_queue(
+        &self,
+        hidden: Option<bool>,
+        cursor: Option<&models::Cursor>,
+        limit: i64,
+    ) -> Result<models::CursorPage<models::ReviewQueueEntry>, models::GenericError> {
+        let cursor_id = cursor.map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
+        let rows = sqlx::query!(
+            "SELECT id, target_id, target_type, user_id, star_rating, epoch, review_text, flagged, hidden, 
+            owner_response, filter_reason, parent_id FROM reviews 
+            WHERE (flagged OR EXISTS (SELECT 1 FROM review_reports WHERE review_id = reviews.id AND resolved_at IS NULL)) 
+            AND ($1::boolean IS NULL OR hidden = $1) AND (
+                $2::bigint IS NULL
+                OR (NOT $4 AND (coalesce(epoch[1], 0), id) > ($2, $3::uuid))
+                OR ($4 AND (coalesce(epoch[1], 0), id) < ($2, $3::uuid))
+            ) ORDER BY 
+            CASE WHEN $4 THEN coalesce(epoch[1], 0) END DESC, CASE WHEN $4 THEN id END DESC, 
+            coalesce(epoch[1], 0) ASC, id ASC LIMIT $5",
+            hidden,
+            cursor.map(|c| c.key),
+            cursor_id,
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.epoch.first().copied().unwrap_or_default(), row.id.to_string())
+        });
+
+        let mut queue = Vec::new();
+
+        for row in page.items {
+            let reports = sqlx::query!(
+                "SELECT user_id, reason, context, created_at FROM review_reports 
+                WHERE review_id = $1 AND resolved_at IS NULL ORDER BY created_at",
+                row.id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(models::GenericError::SQLError)?;
+
+            queue.push(models::ReviewQueueEntry {
+                review: models::Review {
+                    id: Some(row.id),
+                    user: self.get_user(row.user_id).await,
+                    review_text: row.review_text,
+                    epoch: row.epoch,
+                    flagged: row.flagged,
+                    hidden: row.hidden,
+                    owner_response: row.owner_response,
+                    votes: self.get_review_votes(row.id).await,
+                    star_rating: row.star_rating,
+                    response: None,
+                    replies: Vec::new(),
+                    more_replies: false,
+                    replies_cursor: None,
+                    parent_id: row.parent_id,
+                },
+                target_id: row.target_id.to_string(),
+                target_type: match row.target_type {
+                    Some(1) => models::TargetType::Server,
+                    _ => models::TargetType::Bot,
+                },
+                filter_reason: row.filter_reason,
+                reports: reports
+                    .into_iter()
+                    .map(|report| models::ReviewReportEntry {
+                        user_id: report.user_id.to_string(),
+                        reason: models::ReviewReportReason::try_from(report.reason)
+                            .unwrap_or(models::ReviewReportReason::Other),
+                        context: report.context,
+                        created_at: report.created_at,
+                    })
+                    .collect(),
+            });
+        }
+
+        Ok(models::CursorPage {
+            items: queue,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Hides, restores or deletes a review, resolving its reports. Both hiding and restoring take the
+    /// review out of the queue
+    pub async fn moderate_review(
+        &self,
+        staff_id: i64,
+        review_id: uuid::Uuid,
+        action: models::ReviewModAction,
+        reason: Option<&str>,
+    ) -> Result<(), models::GenericError> {
+        let mut tx = self.pool.begin().await.map_err(models::GenericError::SQLError)?;
+
+        let review = sqlx::query!(
+            "SELECT target_id, target_type FROM reviews WHERE id = $1 FOR UPDATE",
+            review_id
+        )
+        .fetch_optional(&mut tx)
+        .await
+        .map_err(models::GenericError::SQLError)?
+        .ok_or(models::GenericError::NotFound)?;
+
+        let audit_action = match action {
+            models::ReviewModAction::Hide => {
+                sqlx::query!("UPDATE reviews SET hidden = true, flagged = false WHERE id = $1", review_id)
+                    .execute(&mut tx)
+                    .await
+                    .map_err(models::GenericError::SQLError)?;
+
+                models::AuditAction::HideReview
+            }
+            models::ReviewModAction::Restore => {
+                sqlx::query!(
+                    "UPDATE reviews SET hidden = false, flagged = false, filter_reason = NULL WHERE id = $1",
+                    review_id
+                )
+                .execute(&mut tx)
+                .await
+                .map_err(models::GenericError::SQLError)?;
+
+                models::AuditAction::RestoreReview
+            }
+            models::ReviewModAction::Delete => {
+                // Reports are deleted along with the review
+                sqlx::query!("DELETE FROM reviews WHERE id = $1", review_id)
+                    .execute(&mut tx)
+                    .await
+                    .map_err(models::GenericError::SQLError)?;
+
+                models::AuditAction::DeleteReview
+            }
+        };
+
+        if action != models::ReviewModAction::Delete {
+            sqlx::query!(
+                "UPDATE review_reports SET resolved_by = $1, resolved_at = NOW() 
+                WHERE review_id = $2 AND resolved_at IS NULL",
+                staff_id,
+                review_id
+            )
+            .execute(&mut tx)
+            .await
+            .map_err(models::GenericError::SQLError)?;
+        }
+
+        let context = match reason {
+            Some(reason) => format!("{}: {}", review_id, reason),
+            None => review_id.to_string(),
+        };
+
+        Database::insert_audit_log(
+            &mut tx,
+            &models::NewAuditLog {
+                target_id: review.target_id,
+                target_type: match review.target_type {
+                    Some(1) => models::AuditTargetType::Server,
+                    _ => models::AuditTargetType::Bot,
+                },
+                actor_id: staff_id,
+                action: audit_action,
+                context: Some(context),
+            },
+        )
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        tx.commit().await.map_err(models::GenericError::SQLError)?;
+
+        Ok(())
+    }
+
     // Stats functions
     pub async fn get_bot_count(&self) -> i64 {
         let row = sqlx::query!("SELECT COUNT(*) FROM bots")
@@ -2931,24 +4743,44 @@ impl Database {
         row.count.unwrap()
     }
 
-    pub async fn get_all_bots(&self) -> Vec<models::IndexBot> {
+    /// Returns all bots (or a page of them if ``limit`` is set)
+    pub async fn get_all_bots(&self, cursor: Option<&models::Cursor>, limit: Option<i64>) -> models::CursorPage<models::IndexBot> {
+        // LIMIT NULL is the same as no limit
         let rows = sqlx::query!(
             "SELECT bot_id, username_cached, avatar_cached, disc_cached, created_at,
             guild_count, banner_card, description, votes, state, flags 
-            FROM bots ORDER BY votes DESC"
+            FROM bots WHERE (
+                $1::bigint IS NULL
+                OR (NOT $3 AND (coalesce(votes, 0), bot_id) < ($1, $2))
+                OR ($3 AND (coalesce(votes, 0), bot_id) > ($1, $2))
+            ) ORDER BY 
+            CASE WHEN $3 THEN coalesce(votes, 0) END ASC, CASE WHEN $3 THEN bot_id END ASC, 
+            coalesce(votes, 0) DESC, bot_id DESC LIMIT $4",
+            cursor.map(|c| c.key),
+            cursor.map(|c| c.id_i64()),
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit.map(|limit| limit + 1)
         )
         .fetch_all(&self.pool)
         .await;
 
         if rows.is_err() {
-            return Vec::new();
+            return models::CursorPage {
+                items: Vec::new(),
+                next_cursor: None,
+                prev_cursor: None,
+            };
         }
 
         let rows = rows.unwrap();
 
+        let page = models::Cursor::paginate(rows, limit.unwrap_or(i64::MAX), cursor, |row| {
+            (row.votes.unwrap_or(0), row.bot_id.to_string())
+        });
+
         let mut list = Vec::new();
 
-        for row in rows {
+        for row in page.items {
             let mut user = models::User {
                 id: row.bot_id.to_string(),
                 username: row.username_cached,
@@ -2978,7 +4810,11 @@ impl Database {
             });
         }
 
-        list
+        models::CursorPage {
+            items: list,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
     }
 
     pub async fn get_all_servers(&self) -> Vec<models::IndexBot> {
@@ -3218,9 +5054,15 @@ impl Database {
         user_id: i64,
         bot_id: i64,
         test: bool,
-    ) -> Result<(), models::VoteBotError> {
+        meta: &models::VoteMeta,
+        rules: &models::VoteRiskConfig,
+        vote_rules: &models::VoteRules,
+    ) -> Result<models::VoteRiskState, models::VoteBotError> {
+        let multiplier = self.get_vote_multiplier(bot_id, models::TargetType::Bot, vote_rules).await;
+
         if test {
-            return self.final_vote_handler_bot(user_id, bot_id, test).await;
+            self.final_vote_handler_bot(user_id, bot_id, test, multiplier).await?;
+            return Ok(models::VoteRiskState::Counted);
         }
 
         /* Let errors be the thing that tells if a vote has happened
@@ -3230,9 +5072,11 @@ impl Database {
         In this case, we error out
         */
         let check = sqlx::query!(
-            "INSERT INTO user_vote_table (user_id, bot_id) VALUES ($1, $2)",
+            "INSERT INTO user_vote_table (user_id, bot_id, expires_on) 
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))",
             user_id,
             bot_id,
+            vote_rules.cooldown as f64,
         )
         .execute(&self.pool)
         .await;
@@ -3253,7 +5097,7 @@ impl Database {
                     .execute(&self.pool)
                     .await
                     .unwrap();
-                return self.vote_bot(user_id, bot_id, test).await;
+                return self.vote_bot(user_id, bot_id, test, meta, rules, vote_rules).await;
             } 
             let expiry_time = sqlx::query!(
                 "SELECT expires_on FROM user_vote_table WHERE user_id = $1",
@@ -3277,7 +5121,20 @@ impl Database {
             )));
         }
 
-        self.final_vote_handler_bot(user_id, bot_id, test).await
+        let state = self
+            .check_vote_risk(user_id, bot_id, models::TargetType::Bot, multiplier, meta, rules)
+            .await?;
+
+        self.schedule_vote_reminder(user_id, bot_id, models::TargetType::Bot).await;
+
+        // Held votes are only counted once staff confirm them
+        if state == models::VoteRiskState::Held {
+            return Ok(state);
+        }
+
+        self.final_vote_handler_bot(user_id, bot_id, test, multiplier).await?;
+
+        Ok(state)
     }
 
     // Vote server
@@ -3288,11 +5145,16 @@ impl Database {
         user_id: i64,
         server_id: i64,
         test: bool,
-    ) -> Result<(), models::VoteBotError> {
+        meta: &models::VoteMeta,
+        rules: &models::VoteRiskConfig,
+        vote_rules: &models::VoteRules,
+    ) -> Result<models::VoteRiskState, models::VoteBotError> {
+        let multiplier = self.get_vote_multiplier(server_id, models::TargetType::Server, vote_rules).await;
+
         if test {
-            return self
-                .final_vote_handler_server(discord_server_http, user_id, server_id, test)
-                .await;
+            self.final_vote_handler_server(discord_server_http, user_id, server_id, test, multiplier)
+                .await?;
+            return Ok(models::VoteRiskState::Counted);
         }
 
         /* Let errors be the thing that tells if a vote has happened
@@ -3302,9 +5164,11 @@ impl Database {
         In this case, we error out
         */
         let check = sqlx::query!(
-            "INSERT INTO user_server_vote_table (user_id, guild_id) VALUES ($1, $2)",
+            "INSERT INTO user_server_vote_table (user_id, guild_id, expires_on) 
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))",
             user_id,
             server_id,
+            vote_rules.cooldown as f64,
         )
         .execute(&self.pool)
         .await;
@@ -3329,7 +5193,7 @@ impl Database {
                 .await
                 .unwrap();
                 return self
-                    .vote_server(discord_server_http, user_id, server_id, test)
+                    .vote_server(discord_server_http, user_id, server_id, test, meta, rules, vote_rules)
                     .await;
             } else {
                 let expiry_time = sqlx::query!(
@@ -3355,80 +5219,454 @@ impl Database {
             }
         }
 
-        self.final_vote_handler_server(discord_server_http, user_id, server_id, test)
-            .await
+        let state = self
+            .check_vote_risk(user_id, server_id, models::TargetType::Server, multiplier, meta, rules)
+            .await?;
+
+        self.schedule_vote_reminder(user_id, server_id, models::TargetType::Server).await;
+
+        // Held votes are only counted once staff confirm them
+        if state == models::VoteRiskState::Held {
+            return Ok(state);
+        }
+
+        self.final_vote_handler_server(discord_server_http, user_id, server_id, test, multiplier)
+            .await?;
+
+        Ok(state)
     }
 
-    async fn final_vote_handler_bot(
+    /// Gets how many votes a vote for a bot/server made right now counts as
+    pub async fn get_vote_multiplier(&self, target_id: i64, target_type: models::TargetType, vote_rules: &models::VoteRules) -> i64 {
+        let state = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!("SELECT state FROM bots WHERE bot_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map(|row| row.state)
+            }
+            models::TargetType::Server => {
+                sqlx::query!("SELECT state FROM servers WHERE guild_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map(|row| row.state)
+            }
+        };
+
+        let state = match state {
+            Ok(state) => models::State::try_from(state).unwrap_or(models::State::Approved),
+            Err(err) => {
+                error!("Failed to get state of {}: {}", target_id, err);
+                models::State::Approved
+            }
+        };
+
+        vote_rules.multiplier(state, Utc::now())
+    }
+
+    /// Scores a vote using ``rules``, returning the score and the reasons for it
+    async fn score_vote(
         &self,
         user_id: i64,
-        bot_id: i64,
-        test: bool,
-    ) -> Result<(), models::VoteBotError> {
-        debug!("Test vote: {}", test);
-        let mut webhook_user_id = user_id;
-        if test {
-            webhook_user_id = 519850436899897346;
+        target_id: i64,
+        target_type_num: i32,
+        meta: &models::VoteMeta,
+        rules: &models::VoteRiskConfig,
+    ) -> Result<(i32, Vec<String>), sqlx::Error> {
+        let mut score = 0;
+        let mut reasons = Vec::new();
+
+        if !rules.enabled {
+            return Ok((score, reasons));
         }
 
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(models::VoteBotError::SQLError)?;
+        let account_age = Utc::now() - converters::snowflake_timestamp(user_id);
 
-        // Add votes
-        if !test {
-            sqlx::query!(
-                "UPDATE bots SET votes = votes + 1, 
-                total_votes = total_votes + 1 WHERE bot_id = $1",
-                bot_id,
+        if account_age < chrono::Duration::days(rules.new_account_days) {
+            score += rules.new_account_score;
+            reasons.push("NewAccount".to_string());
+        }
+
+        if let Some(ref origin) = meta.origin {
+            let row = sqlx::query!(
+                "SELECT COUNT(*) FROM vote_risk WHERE origin = $1 
+                AND created_at > NOW() - make_interval(secs => $2)",
+                origin,
+                rules.burst_window as f64
             )
-            .execute(&mut tx)
-            .await
-            .map_err(models::VoteBotError::SQLError)?;
+            .fetch_one(&self.pool)
+            .await?;
+
+            if row.count.unwrap_or_default() > rules.network_burst_votes {
+                score += rules.network_burst_score;
+                reasons.push("NetworkBurst".to_string());
+            }
+
+            let row = sqlx::query!(
+                "SELECT COUNT(DISTINCT user_id) FROM vote_risk WHERE origin = $1 
+                AND target_id = $2 AND target_type = $3 AND user_id != $4
+                AND created_at > NOW() - make_interval(secs => $5)",
+                origin,
+                target_id,
+                target_type_num,
+                user_id,
+                rules.ring_window as f64
+            )
+            .fetch_one(&self.pool)
+            .await?;
+
+            if row.count.unwrap_or_default() > rules.ring_votes {
+                score += rules.ring_score;
+                reasons.push("VoteRing".to_string());
+            }
+        }
 
+        if let Some(ref fingerprint) = meta.fingerprint {
             let row = sqlx::query!(
-                "SELECT COUNT(1) FROM bot_voters WHERE user_id = $1 AND bot_id = $2",
+                "SELECT COUNT(DISTINCT user_id) FROM vote_risk WHERE fingerprint = $1 
+                AND user_id != $2 AND created_at > NOW() - make_interval(secs => $3)",
+                fingerprint,
                 user_id,
-                bot_id,
+                rules.burst_window as f64
             )
             .fetch_one(&self.pool)
+            .await?;
+
+            if row.count.unwrap_or_default() > rules.fingerprint_burst_votes {
+                score += rules.fingerprint_burst_score;
+                reasons.push("FingerprintBurst".to_string());
+            }
+        }
+
+        Ok((score, reasons))
+    }
+
+    /// Scores and records a vote, returning whether it should be counted or held for staff
+    async fn check_vote_risk(
+        &self,
+        user_id: i64,
+        target_id: i64,
+        target_type: models::TargetType,
+        weight: i64,
+        meta: &models::VoteMeta,
+        rules: &models::VoteRiskConfig,
+    ) -> Result<models::VoteRiskState, models::VoteBotError> {
+        let target_type_num = match target_type {
+            models::TargetType::Bot => 0,
+            models::TargetType::Server => 1,
+        };
+
+        let (score, reasons) = self
+            .score_vote(user_id, target_id, target_type_num, meta, rules)
             .await
             .map_err(models::VoteBotError::SQLError)?;
 
-            if row.count.unwrap_or_default() == 0 {
+        let state = if rules.enabled && score >= rules.hold_score {
+            models::VoteRiskState::Held
+        } else {
+            models::VoteRiskState::Counted
+        };
+
+        sqlx::query!(
+            "INSERT INTO vote_risk (user_id, target_id, target_type, origin, fingerprint, 
+            account_created_at, score, reasons, state, weight) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            user_id,
+            target_id,
+            target_type_num,
+            meta.origin,
+            meta.fingerprint,
+            converters::snowflake_timestamp(user_id),
+            score,
+            &reasons,
+            state as i32,
+            weight
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::VoteBotError::SQLError)?;
+
+        if state == models::VoteRiskState::Held {
+            debug!("Holding vote of {} for {} (score {}: {:?})", user_id, target_id, score, reasons);
+        }
+
+        Ok(state)
+    }
+
+    /// Returns votes with a non-zero risk score in a state, newest first
+    pub async fn get_flagged_votes(
+        &self,
+        state: models::VoteRiskState,
+        target_id: Option<i64>,
+        cursor: Option<&models::Cursor>,
+        limit: i64,
+    ) -> Result<models::CursorPage<models::FlaggedVote>, models::GenericError> {
+        let cursor_id = cursor.map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
+        let rows = sqlx::query!(
+            "SELECT id, user_id, target_id, target_type, origin, account_created_at, score, reasons, 
+            state, reviewed_by, created_at FROM vote_risk WHERE state = $1 AND score > 0 
+            AND ($2::bigint IS NULL OR target_id = $2) 
+            AND (
+                $3::bigint IS NULL
+                OR (NOT $5 AND (created_at, id) < (TIMESTAMPTZ 'epoch' + $3 * interval '1 microsecond', $4::uuid))
+                OR ($5 AND (created_at, id) > (TIMESTAMPTZ 'epoch' + $3 * interval '1 microsecond', $4::uuid))
+            ) ORDER BY 
+            CASE WHEN $5 THEN created_at END ASC, CASE WHEN $5 THEN id END ASC, 
+            created_at DESC, id DESC LIMIT $6",
+            state as i32,
+            target_id,
+            cursor.map(|c| c.key),
+            cursor_id,
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.created_at.timestamp_nanos() / 1000, row.id.to_string())
+        });
+
+        let user_ids: Vec<i64> = page.items.iter().map(|row| row.user_id).collect();
+        let users = self.get_users(&user_ids).await;
+
+        let votes = page
+            .items
+            .into_iter()
+            .map(|row| models::FlaggedVote {
+                id: row.id,
+                user: users.get(&row.user_id).cloned().unwrap_or_default(),
+                target_id: row.target_id.to_string(),
+                target_type: if row.target_type == 1 {
+                    models::TargetType::Server
+                } else {
+                    models::TargetType::Bot
+                },
+                origin: row.origin,
+                account_created_at: row.account_created_at,
+                score: row.score,
+                reasons: row.reasons,
+                state: models::VoteRiskState::try_from(row.state).unwrap_or_default(),
+                reviewed_by: row.reviewed_by.map(|id| id.to_string()),
+                created_at: row.created_at,
+            })
+            .collect();
+
+        Ok(models::CursorPage {
+            items: votes,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Confirms or voids a flagged vote. Confirming a held vote counts it, voiding a counted vote removes it
+    pub async fn review_vote(
+        &self,
+        discord_server_http: &serenity::http::Http,
+        vote_id: uuid::Uuid,
+        staff_id: i64,
+        confirm: bool,
+    ) -> Result<(), models::VoteReviewError> {
+        let row = sqlx::query!(
+            "SELECT user_id, target_id, target_type, state, weight FROM vote_risk WHERE id = $1",
+            vote_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(models::VoteReviewError::SQLError)?
+        .ok_or(models::VoteReviewError::NotFound)?;
+
+        let old_state = models::VoteRiskState::try_from(row.state).unwrap_or_default();
+
+        if old_state == models::VoteRiskState::Confirmed || old_state == models::VoteRiskState::Voided {
+            return Err(models::VoteReviewError::AlreadyReviewed);
+        }
+
+        let new_state = if confirm {
+            models::VoteRiskState::Confirmed
+        } else {
+            models::VoteRiskState::Voided
+        };
+
+        // The state change and the vote count change must both happen (or neither) so a 
+        // vote is never counted twice or voided without being taken off
+        let mut tx = self.pool.begin().await.map_err(models::VoteReviewError::SQLError)?;
+
+        // Only update if no one else reviewed it in the meantime
+        let res = sqlx::query!(
+            "UPDATE vote_risk SET state = $1, reviewed_by = $2, reviewed_at = NOW() 
+            WHERE id = $3 AND state = $4",
+            new_state as i32,
+            staff_id,
+            vote_id,
+            old_state as i32
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(models::VoteReviewError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::VoteReviewError::AlreadyReviewed);
+        }
+
+        match (old_state, confirm, row.target_type) {
+            (models::VoteRiskState::Held, true, 1) => {
+                Database::count_server_vote(&mut tx, row.user_id, row.target_id, row.weight)
+                    .await
+                    .map_err(models::VoteReviewError::SQLError)?;
+            }
+            (models::VoteRiskState::Held, true, _) => {
+                Database::count_bot_vote(&mut tx, row.user_id, row.target_id, row.weight)
+                    .await
+                    .map_err(models::VoteReviewError::SQLError)?;
+            }
+            (models::VoteRiskState::Counted, false, 1) => {
                 sqlx::query!(
-                    "INSERT INTO bot_voters (user_id, bot_id) VALUES ($1, $2)",
-                    user_id,
-                    bot_id
+                    "UPDATE servers SET votes = GREATEST(votes - $2, 0), 
+                    total_votes = GREATEST(total_votes - $2, 0) WHERE guild_id = $1",
+                    row.target_id,
+                    row.weight
                 )
                 .execute(&mut tx)
                 .await
-                .map_err(models::VoteBotError::SQLError)?;
-            } else {
+                .map_err(models::VoteReviewError::SQLError)?;
+            }
+            (models::VoteRiskState::Counted, false, _) => {
                 sqlx::query!(
-                    "UPDATE bot_voters SET timestamps = array_append(timestamps, NOW()) WHERE user_id = $1 AND bot_id = $2",
-                    user_id,
-                    bot_id
+                    "UPDATE bots SET votes = GREATEST(votes - $2, 0), 
+                    total_votes = GREATEST(total_votes - $2, 0) WHERE bot_id = $1",
+                    row.target_id,
+                    row.weight
                 )
                 .execute(&mut tx)
                 .await
-                .map_err(models::VoteBotError::SQLError)?;
+                .map_err(models::VoteReviewError::SQLError)?;
+            }
+            // Confirming a counted vote or voiding a held vote changes nothing else
+            _ => {}
+        }
+
+        tx.commit().await.map_err(models::VoteReviewError::SQLError)?;
+
+        match (old_state, confirm, row.target_type) {
+            (models::VoteRiskState::Held, true, 1) => {
+                self.avid_voter_flag(row.user_id)
+                    .await
+                    .map_err(models::VoteReviewError::VoteError)?;
+                self.server_vote_event(discord_server_http, row.user_id, row.target_id, false, row.weight)
+                    .await
+                    .map_err(models::VoteReviewError::VoteError)?;
+            }
+            (models::VoteRiskState::Held, true, _) => {
+                self.avid_voter_flag(row.user_id)
+                    .await
+                    .map_err(models::VoteReviewError::VoteError)?;
+                self.bot_vote_event(row.user_id, row.target_id, false, row.weight)
+                    .await
+                    .map_err(models::VoteReviewError::VoteError)?;
             }
+            (models::VoteRiskState::Counted, false, 1) => self.server_cache.invalidate(&row.target_id).await,
+            (models::VoteRiskState::Counted, false, _) => self.bot_cache.invalidate(&row.target_id).await,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn final_vote_handler_bot(
+        &self,
+        user_id: i64,
+        bot_id: i64,
+        test: bool,
+        multiplier: i64,
+    ) -> Result<(), models::VoteBotError> {
+        debug!("Test vote: {}", test);
+
+        // Add votes
+        if !test {
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(models::VoteBotError::SQLError)?;
+
+            Database::count_bot_vote(&mut tx, user_id, bot_id, multiplier)
+                .await
+                .map_err(models::VoteBotError::SQLError)?;
+
+            tx.commit().await.map_err(models::VoteBotError::SQLError)?;
 
             self.avid_voter_flag(user_id).await?;
         }
 
-        tx.commit().await.map_err(models::VoteBotError::SQLError)?;
+        self.bot_vote_event(user_id, bot_id, test, multiplier).await
+    }
+
+    /// Adds a vote to the votes of a bot and records the voter as part of ``tx``
+    async fn count_bot_vote(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+        bot_id: i64,
+        multiplier: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE bots SET votes = votes + $2, 
+            total_votes = total_votes + $2 WHERE bot_id = $1",
+            bot_id,
+            multiplier,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let row = sqlx::query!(
+            "SELECT COUNT(1) FROM bot_voters WHERE user_id = $1 AND bot_id = $2",
+            user_id,
+            bot_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if row.count.unwrap_or_default() == 0 {
+            sqlx::query!(
+                "INSERT INTO bot_voters (user_id, bot_id) VALUES ($1, $2)",
+                user_id,
+                bot_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE bot_voters SET timestamps = array_append(timestamps, NOW()) WHERE user_id = $1 AND bot_id = $2",
+                user_id,
+                bot_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the vote event of a (counted) bot vote over websocket and webhook
+    async fn bot_vote_event(
+        &self,
+        user_id: i64,
+        bot_id: i64,
+        test: bool,
+        multiplier: i64,
+    ) -> Result<(), models::VoteBotError> {
+        let mut webhook_user_id = user_id;
+        if test {
+            webhook_user_id = 519850436899897346;
+        }
 
         // Send the event here
         let event_id = uuid::Uuid::new_v4();
 
         // Current votes
         let row = sqlx::query!(
-            "SELECT votes, webhook, webhook_secret, webhook_type,
-            webhook_hmac_only, api_token FROM bots WHERE bot_id = $1",
+            "SELECT votes, webhook, webhook_type, webhook_events FROM bots WHERE bot_id = $1",
             bot_id
         )
         .fetch_one(&self.pool)
@@ -3447,6 +5685,7 @@ impl Database {
             m: models::EventMeta {
                 e: models::EventName::BotVote,
                 eid: event_id.to_string(),
+                seq: 0,
             },
             ctx: models::EventContext {
                 target: bot_id.to_string(),
@@ -3457,22 +5696,14 @@ impl Database {
             props: models::BotVoteProp {
                 test,
                 votes: row.votes.unwrap_or_default(),
+                multiplier,
             },
         };
         self.ws_event(event).await;
 
-        // Send vote event over webhook too
-        if row.webhook.is_some() {
+        // Send vote event over webhook too (if subscribed to it)
+        if row.webhook.is_some() && row.webhook_events & models::EventName::BotVote.webhook_bit() != 0 {
             let webhook = row.webhook.unwrap();
-            let mut webhook_token: String;
-            if row.webhook_secret.is_some() {
-                webhook_token = row.webhook_secret.unwrap();
-                if webhook_token.is_empty() {
-                    webhook_token = row.api_token.unwrap();
-                }
-            } else {
-                webhook_token = row.api_token.unwrap();
-            }
 
             let vote_event = models::VoteWebhookEvent {
                 eid: event_id.to_string(),
@@ -3481,6 +5712,7 @@ impl Database {
                 votes: row.votes.unwrap_or_default(),
                 ts: chrono::Utc::now().timestamp(),
                 test,
+                multiplier,
             };
 
             if row.webhook_type.is_none() {
@@ -3497,14 +5729,10 @@ impl Database {
                     vote_event,
                 ));
             } else {
-                // Send over webhook
-                task::spawn(converters::send_vote_webhook(
-                    self.requests.clone(),
-                    webhook,
-                    webhook_token,
-                    row.webhook_hmac_only.unwrap_or(false),
-                    vote_event,
-                ));
+                // Queue for the webhook worker, which signs and retries it
+                if let Err(err) = self.enqueue_webhook(bot_id, models::TargetType::Bot, &vote_event.eid, &webhook, json!(vote_event)).await {
+                    error!("Failed to queue vote webhook: {}", err);
+                }
             }
         }
 
@@ -3517,71 +5745,95 @@ impl Database {
         user_id: i64,
         server_id: i64,
         test: bool,
+        multiplier: i64,
     ) -> Result<(), models::VoteBotError> {
         debug!("Test vote: {}", test);
-        let mut webhook_user_id = user_id;
-        if test {
-            webhook_user_id = 519850436899897346;
-        }
-
-        let mut tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(models::VoteBotError::SQLError)?;
 
         // Add votes
         if !test {
-            sqlx::query!(
-                "UPDATE servers SET votes = votes + 1, 
-                total_votes = total_votes + 1 WHERE guild_id = $1",
-                server_id,
-            )
-            .execute(&mut tx)
-            .await
-            .map_err(models::VoteBotError::SQLError)?;
-
-            let row = sqlx::query!(
-                "SELECT COUNT(1) FROM server_voters WHERE user_id = $1 AND guild_id = $2",
-                user_id,
-                server_id,
-            )
-            .fetch_one(&self.pool)
-            .await
-            .map_err(models::VoteBotError::SQLError)?;
-
-            if row.count.unwrap_or_default() == 0 {
-                sqlx::query!(
-                    "INSERT INTO server_voters (user_id, guild_id) VALUES ($1, $2)",
-                    user_id,
-                    server_id
-                )
-                .execute(&mut tx)
+            let mut tx = self
+                .pool
+                .begin()
                 .await
                 .map_err(models::VoteBotError::SQLError)?;
-            } else {
-                sqlx::query!(
-                    "UPDATE server_voters SET timestamps = array_append(timestamps, NOW()) WHERE user_id = $1 AND guild_id = $2",
-                    user_id,
-                    server_id
-                )
-                .execute(&mut tx)
+
+            Database::count_server_vote(&mut tx, user_id, server_id, multiplier)
                 .await
                 .map_err(models::VoteBotError::SQLError)?;
-            }
+
+            tx.commit().await.map_err(models::VoteBotError::SQLError)?;
 
             self.avid_voter_flag(user_id).await?;
         }
 
-        tx.commit().await.map_err(models::VoteBotError::SQLError)?;
+        self.server_vote_event(discord_server_http, user_id, server_id, test, multiplier).await
+    }
 
-        // Send the event here
-        let event_id = uuid::Uuid::new_v4();
+    /// Adds a vote to the votes of a server and records the voter as part of ``tx``
+    async fn count_server_vote(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i64,
+        server_id: i64,
+        multiplier: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE servers SET votes = votes + $2, 
+            total_votes = total_votes + $2 WHERE guild_id = $1",
+            server_id,
+            multiplier,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let row = sqlx::query!(
+            "SELECT COUNT(1) FROM server_voters WHERE user_id = $1 AND guild_id = $2",
+            user_id,
+            server_id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if row.count.unwrap_or_default() == 0 {
+            sqlx::query!(
+                "INSERT INTO server_voters (user_id, guild_id) VALUES ($1, $2)",
+                user_id,
+                server_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                "UPDATE server_voters SET timestamps = array_append(timestamps, NOW()) WHERE user_id = $1 AND guild_id = $2",
+                user_id,
+                server_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the vote event of a (counted) server vote over websocket and webhook
+    async fn server_vote_event(
+        &self,
+        discord_server_http: &serenity::http::Http,
+        user_id: i64,
+        server_id: i64,
+        test: bool,
+        multiplier: i64,
+    ) -> Result<(), models::VoteBotError> {
+        let mut webhook_user_id = user_id;
+        if test {
+            webhook_user_id = 519850436899897346;
+        }
+
+        // Send the event here
+        let event_id = uuid::Uuid::new_v4();
 
         // Current votes
         let row = sqlx::query!(
-            "SELECT votes, webhook, webhook_secret, webhook_type, autorole_votes,
-            webhook_hmac_only, api_token FROM servers WHERE guild_id = $1",
+            "SELECT votes, webhook, webhook_type, webhook_events, autorole_votes FROM servers WHERE guild_id = $1",
             server_id
         )
         .fetch_one(&self.pool)
@@ -3600,6 +5852,7 @@ impl Database {
             m: models::EventMeta {
                 e: models::EventName::ServerVote,
                 eid: event_id.to_string(),
+                seq: 0,
             },
             ctx: models::EventContext {
                 target: server_id.to_string(),
@@ -3610,22 +5863,14 @@ impl Database {
             props: models::BotVoteProp {
                 test,
                 votes: row.votes.unwrap_or_default(),
+                multiplier,
             },
         };
         self.ws_event(event).await;
 
-        // Send vote event over webhook too
-        if row.webhook.is_some() {
+        // Send vote event over webhook too (if subscribed to it)
+        if row.webhook.is_some() && row.webhook_events & models::EventName::ServerVote.webhook_bit() != 0 {
             let webhook = row.webhook.unwrap();
-            let mut webhook_token: String;
-            if row.webhook_secret.is_some() {
-                webhook_token = row.webhook_secret.unwrap();
-                if webhook_token.is_empty() {
-                    webhook_token = row.api_token;
-                }
-            } else {
-                webhook_token = row.api_token;
-            }
 
             let vote_event = models::VoteWebhookEvent {
                 eid: event_id.to_string(),
@@ -3634,6 +5879,7 @@ impl Database {
                 votes: row.votes.unwrap_or_default(),
                 ts: chrono::Utc::now().timestamp(),
                 test,
+                multiplier,
             };
 
             if row.webhook_type.is_none() {
@@ -3650,14 +5896,10 @@ impl Database {
                     vote_event,
                 ));
             } else {
-                // Send over webhook
-                task::spawn(converters::send_vote_webhook(
-                    self.requests.clone(),
-                    webhook,
-                    webhook_token,
-                    row.webhook_hmac_only.unwrap_or(false),
-                    vote_event,
-                ));
+                // Queue for the webhook worker, which signs and retries it
+                if let Err(err) = self.enqueue_webhook(server_id, models::TargetType::Server, &vote_event.eid, &webhook, json!(vote_event)).await {
+                    error!("Failed to queue vote webhook: {}", err);
+                }
             }
         }
 
@@ -3685,7 +5927,8 @@ impl Database {
 
     pub async fn get_frostpaw_client(&self, id: &str) -> Option<models::FrostpawClient> {
         let row = sqlx::query!(
-            "SELECT id, name, domain, verified, privacy_policy, secret, owner_id FROM frostpaw_clients WHERE id = $1",
+            "SELECT id, name, domain, verified, privacy_policy, secret, owner_id, redirect_domains, 
+            verification_state FROM frostpaw_clients WHERE id = $1",
             id
         )
         .fetch_one(&self.pool)
@@ -3703,10 +5946,182 @@ impl Database {
             privacy_policy: row.privacy_policy,
             secret: row.secret,
 	    verified: row.verified,
-            owner: self.get_user(row.owner_id).await
+            owner: self.get_user(row.owner_id).await,
+            redirect_domains: row.redirect_domains,
+            verification_state: models::ClientVerificationState::try_from(row.verification_state).unwrap_or_default(),
+        })
+    }
+
+    // Dev portal
+
+    pub async fn get_user_clients(&self, user_id: i64) -> Vec<models::FrostpawClient> {
+        let rows = sqlx::query!(
+            "SELECT id FROM frostpaw_clients WHERE owner_id = $1 ORDER BY created_at",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        let mut clients = Vec::new();
+
+        for row in rows {
+            if let Some(client) = self.get_frostpaw_client(&row.id).await {
+                clients.push(client);
+            }
+        }
+
+        clients
+    }
+
+    pub async fn create_frostpaw_client(
+        &self,
+        user_id: i64,
+        client: &models::EditFrostpawClient,
+    ) -> Result<models::FrostpawClientSecret, models::FrostpawClientError> {
+        let count = sqlx::query!(
+            "SELECT COUNT(*) FROM frostpaw_clients WHERE owner_id = $1",
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(models::FrostpawClientError::SQLError)?;
+
+        if count.count.unwrap_or(0) >= 10 {
+            return Err(models::FrostpawClientError::TooManyClients);
+        }
+
+        let new_client = models::FrostpawClientSecret {
+            id: converters::create_token(32),
+            secret: converters::create_token(128),
+        };
+
+        sqlx::query!(
+            "INSERT INTO frostpaw_clients (id, name, domain, privacy_policy, secret, owner_id, redirect_domains) 
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            new_client.id,
+            client.name,
+            client.domain,
+            client.privacy_policy,
+            new_client.secret,
+            user_id,
+            &client.redirect_domains
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::FrostpawClientError::SQLError)?;
+
+        Ok(new_client)
+    }
+
+    /// Edits a client. Verified clients go back to being unverified if their name or domain is changed
+    pub async fn edit_frostpaw_client(
+        &self,
+        user_id: i64,
+        client_id: &str,
+        client: &models::EditFrostpawClient,
+    ) -> Result<(), models::FrostpawClientError> {
+        let res = sqlx::query!(
+            "UPDATE frostpaw_clients SET name = $3, domain = $4, privacy_policy = $5, redirect_domains = $6, 
+            verified = verified AND name = $3 AND domain = $4, 
+            verification_state = CASE WHEN name = $3 AND domain = $4 THEN verification_state ELSE $7 END 
+            WHERE id = $1 AND owner_id = $2",
+            client_id,
+            user_id,
+            client.name,
+            client.domain,
+            client.privacy_policy,
+            &client.redirect_domains,
+            models::ClientVerificationState::Unverified as i32
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::FrostpawClientError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::FrostpawClientError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes a client, logging every user out of it
+    pub async fn delete_frostpaw_client(&self, user_id: i64, client_id: &str) -> Result<(), models::FrostpawClientError> {
+        let res = sqlx::query!(
+            "DELETE FROM frostpaw_clients WHERE id = $1 AND owner_id = $2",
+            client_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::FrostpawClientError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::FrostpawClientError::NotFound);
+        }
+
+        sqlx::query!("DELETE FROM user_connections WHERE client_id = $1", client_id)
+            .execute(&self.pool)
+            .await
+            .map_err(models::FrostpawClientError::SQLError)?;
+
+        sqlx::query!("DELETE FROM oauth2_tokens WHERE client_id = $1", client_id)
+            .execute(&self.pool)
+            .await
+            .map_err(models::FrostpawClientError::SQLError)?;
+
+        sqlx::query!("DELETE FROM oauth2_codes WHERE client_id = $1", client_id)
+            .execute(&self.pool)
+            .await
+            .map_err(models::FrostpawClientError::SQLError)?;
+
+        Ok(())
+    }
+
+    pub async fn rotate_frostpaw_client_secret(
+        &self,
+        user_id: i64,
+        client_id: &str,
+    ) -> Result<models::FrostpawClientSecret, models::FrostpawClientError> {
+        let secret = converters::create_token(128);
+
+        let res = sqlx::query!(
+            "UPDATE frostpaw_clients SET secret = $3 WHERE id = $1 AND owner_id = $2",
+            client_id,
+            user_id,
+            secret
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::FrostpawClientError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::FrostpawClientError::NotFound);
+        }
+
+        Ok(models::FrostpawClientSecret {
+            id: client_id.to_string(),
+            secret,
         })
     }
 
+    pub async fn set_client_verification_state(
+        &self,
+        client_id: &str,
+        state: models::ClientVerificationState,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE frostpaw_clients SET verification_state = $2, verified = $3 WHERE id = $1",
+            client_id,
+            state as i32,
+            state == models::ClientVerificationState::Verified
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn subscribe_notifs(&self, id: i64, notif: models::NotificationSub) -> Result<(), models::NotifSubError> {
         /* Remove old subscriptions, if any, we don't care if this fails
            We call this _e to get clippy to shut up
@@ -3757,6 +6172,14 @@ impl Database {
 
     pub async fn test_notifs(&self, id: i64) {
         // Test API call
+        self.send_push(id, json!({
+            "title": "Test notification",
+            "icon": self.discord_config.fates_api_url.to_string() + "/static/botlisticon.webp" 
+        })).await;
+    }
+
+    /// Sends a push notification to every device of a user through flamepaw, returning how many were sent
+    pub async fn send_push(&self, id: i64, data: serde_json::Value) -> usize {
         let devices = sqlx::query!(
             "SELECT endpoint, p256dh, auth FROM push_notifications WHERE user_id = $1",
             id
@@ -3766,10 +6189,11 @@ impl Database {
 
         if devices.is_err() {
             debug!("Failed to get devices for user {}", id);
-            return;
+            return 0;
         }
 
         let devices = devices.unwrap();
+        let mut sent = 0;
 
         for device in devices {
             // Call flamepaw
@@ -3778,19 +6202,1045 @@ impl Database {
                 endpoint: device.endpoint,
                 p256dh: device.p256dh,
                 auth: device.auth,
-                data: serde_json::to_string(&json!({
-                    "title": "Test notification",
-                    "icon": self.discord_config.fates_api_url.to_string() + "/static/botlisticon.webp" 
-                })).unwrap(),
+                data: data.to_string(),
             })
             .send()
             .await;
 
-            if res.is_err() {
-                error!("Failed to send test notification to user {}", id);
+            match res {
+                Ok(res) => {
+                    debug!("{}", res.status());
+                    if res.status().is_success() {
+                        sent += 1;
+                    }
+                }
+                Err(err) => error!("Failed to send notification to user {}: {}", id, err),
+            }
+        }
+
+        sent
+    }
+
+    // Vote reminders
+
+    pub async fn get_vote_reminders(&self, user_id: i64) -> Vec<models::VoteReminder> {
+        let rows = sqlx::query!(
+            "SELECT id, target_id, target_type, method, remind_at, last_sent_at, created_at 
+            FROM vote_reminders WHERE user_id = $1 ORDER BY created_at",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("Failed to get vote reminders: {}", err);
+                return Vec::new();
+            }
+        };
+
+        rows.into_iter()
+            .map(|row| models::VoteReminder {
+                id: row.id,
+                target_id: row.target_id.to_string(),
+                target_type: if row.target_type == 1 {
+                    models::TargetType::Server
+                } else {
+                    models::TargetType::Bot
+                },
+                method: models::ReminderMethod::try_from(row.method).unwrap_or_default(),
+                remind_at: row.remind_at,
+                last_sent_at: row.last_sent_at,
+                created_at: row.created_at,
+            })
+            .collect()
+    }
+
+    /// Creates a vote reminder (or changes the method of an existing one). If the user is waiting
+    /// to vote, the reminder is sent when they can vote again
+    pub async fn create_vote_reminder(
+        &self,
+        user_id: i64,
+        reminder: &models::CreateVoteReminder,
+    ) -> Result<(), models::VoteReminderError> {
+        let (exists, target_type_num) = match reminder.target_type {
+            models::TargetType::Bot => (
+                sqlx::query!("SELECT COUNT(1) FROM bots WHERE bot_id = $1", reminder.target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::VoteReminderError::SQLError)?
+                    .count,
+                0,
+            ),
+            models::TargetType::Server => (
+                sqlx::query!("SELECT COUNT(1) FROM servers WHERE guild_id = $1", reminder.target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::VoteReminderError::SQLError)?
+                    .count,
+                1,
+            ),
+        };
+
+        if exists.unwrap_or_default() == 0 {
+            return Err(models::VoteReminderError::TargetNotFound);
+        }
+
+        let count = sqlx::query!(
+            "SELECT COUNT(1) FROM vote_reminders WHERE user_id = $1 
+            AND NOT (target_id = $2 AND target_type = $3)",
+            user_id,
+            reminder.target_id,
+            target_type_num
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(models::VoteReminderError::SQLError)?;
+
+        if count.count.unwrap_or_default() >= 50 {
+            return Err(models::VoteReminderError::TooManyReminders);
+        }
+
+        sqlx::query!(
+            "INSERT INTO vote_reminders (user_id, target_id, target_type, method) VALUES ($1, $2, $3, $4) 
+            ON CONFLICT (user_id, target_id, target_type) DO UPDATE SET method = excluded.method",
+            user_id,
+            reminder.target_id,
+            target_type_num,
+            reminder.method as i32
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::VoteReminderError::SQLError)?;
+
+        self.schedule_vote_reminder(user_id, reminder.target_id, reminder.target_type).await;
+
+        Ok(())
+    }
+
+    pub async fn delete_vote_reminder(&self, user_id: i64, id: uuid::Uuid) -> Result<(), models::VoteReminderError> {
+        let res = sqlx::query!(
+            "DELETE FROM vote_reminders WHERE id = $1 AND user_id = $2",
+            id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::VoteReminderError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::VoteReminderError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Schedules the reminder of a user for a bot/server (if any) for when their vote cooldown expires
+    pub async fn schedule_vote_reminder(&self, user_id: i64, target_id: i64, target_type: models::TargetType) {
+        let res = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!(
+                    "UPDATE vote_reminders SET remind_at = (
+                        SELECT expires_on FROM user_vote_table WHERE user_id = $1 AND expires_on > NOW()
+                    ) WHERE user_id = $1 AND target_id = $2 AND target_type = 0",
+                    user_id,
+                    target_id
+                )
+                .execute(&self.pool)
+                .await
+            }
+            models::TargetType::Server => {
+                sqlx::query!(
+                    "UPDATE vote_reminders SET remind_at = (
+                        SELECT expires_on FROM user_server_vote_table WHERE user_id = $1 AND expires_on > NOW()
+                    ) WHERE user_id = $1 AND target_id = $2 AND target_type = 1",
+                    user_id,
+                    target_id
+                )
+                .execute(&self.pool)
+                .await
+            }
+        };
+
+        if let Err(err) = res {
+            error!("Failed to schedule vote reminder for {}: {}", user_id, err);
+        }
+    }
+
+    /// Claims up to ``limit`` reminders that are due. Claimed reminders are leased for a few minutes 
+    /// so a crashed worker doesn't lose them and multiple workers never send the same reminder
+    pub async fn claim_vote_reminders(&self, limit: i64) -> Vec<models::PendingVoteReminder> {
+        let rows = sqlx::query!(
+            "UPDATE vote_reminders SET leased_until = NOW() + interval '5 minutes' 
+            WHERE id IN (
+                SELECT id FROM vote_reminders WHERE remind_at <= NOW() 
+                AND (leased_until IS NULL OR leased_until <= NOW()) 
+                ORDER BY remind_at LIMIT $1 FOR UPDATE SKIP LOCKED
+            ) RETURNING id, user_id, target_id, target_type, method, remind_at AS \"remind_at!\", attempts",
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        match rows {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|row| models::PendingVoteReminder {
+                    id: row.id,
+                    user_id: row.user_id,
+                    target_id: row.target_id,
+                    target_type: if row.target_type == 1 {
+                        models::TargetType::Server
+                    } else {
+                        models::TargetType::Bot
+                    },
+                    method: models::ReminderMethod::try_from(row.method).unwrap_or_default(),
+                    remind_at: row.remind_at,
+                    attempts: row.attempts,
+                })
+                .collect(),
+            Err(err) => {
+                error!("Failed to claim vote reminders: {}", err);
+                Vec::new()
             }
+        }
+    }
 
-            debug!("{}", res.unwrap().status());
+    /// Releases a claimed reminder. If ``retry_at`` is set, sending failed and the reminder is retried then, 
+    /// otherwise it is cleared until the user votes again. ``last_sent_at`` is only set if it was ``sent``
+    ///
+    /// ``remind_at`` is left alone if the user voted again (rescheduling the reminder) while it was being sent
+    pub async fn finish_vote_reminder(
+        &self,
+        reminder: &models::PendingVoteReminder,
+        sent: bool,
+        retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        let res = sqlx::query!(
+            "UPDATE vote_reminders SET leased_until = NULL, 
+            attempts = CASE WHEN $3::timestamptz IS NULL THEN 0 ELSE attempts + 1 END, 
+            last_sent_at = CASE WHEN $4 THEN NOW() ELSE last_sent_at END, 
+            remind_at = CASE WHEN remind_at = $2 THEN $3 ELSE remind_at END WHERE id = $1",
+            reminder.id,
+            reminder.remind_at,
+            retry_at,
+            sent
+        )
+        .execute(&self.pool)
+        .await;
+
+        if let Err(err) = res {
+            error!("Failed to update vote reminder {}: {}", reminder.id, err);
         }
     }
+
+    /// Sends a vote reminder using its method. Returns whether the reminder reached the user 
+    /// (through any of its methods) or no longer needs to be sent
+    pub async fn send_vote_reminder(&self, reminder: &models::PendingVoteReminder) -> bool {
+        let (name, path) = match reminder.target_type {
+            models::TargetType::Bot => (
+                sqlx::query!("SELECT username_cached FROM bots WHERE bot_id = $1", reminder.target_id)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|row| row.username_cached.unwrap_or_default()),
+                "/bot/",
+            ),
+            models::TargetType::Server => (
+                sqlx::query!("SELECT name_cached FROM servers WHERE guild_id = $1", reminder.target_id)
+                    .fetch_optional(&self.pool)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|row| row.name_cached),
+                "/server/",
+            ),
+        };
+
+        let name = match name {
+            Some(name) => name,
+            None => {
+                // Bot/server no longer exists
+                let _e = self.delete_vote_reminder(reminder.user_id, reminder.id).await;
+                return true;
+            }
+        };
+
+        let url = self.discord_config.site_url.to_string() + path + &reminder.target_id.to_string();
+
+        let mut sent = false;
+
+        if reminder.method != models::ReminderMethod::Dm {
+            sent = self.send_push(reminder.user_id, json!({
+                "title": format!("You can vote for {} again!", name),
+                "icon": self.discord_config.fates_api_url.to_string() + "/static/botlisticon.webp",
+                "url": url,
+            })).await > 0;
+        }
+
+        if reminder.method != models::ReminderMethod::Push {
+            let channel = UserId(reminder.user_id as u64)
+                .create_dm_channel(&self.discord_main)
+                .await;
+
+            let msg = match channel {
+                Ok(channel) => channel
+                    .send_message(&self.discord_main, |m| {
+                        m.embed(|e| {
+                            e.title("Vote Reminder");
+                            e.description(format!("You can vote for **{}** again!", name));
+                            e.url(&url);
+                            e.color(0x0000_ff00);
+                            e
+                        });
+                        m
+                    })
+                    .await
+                    .map(|_| ()),
+                Err(err) => Err(err),
+            };
+
+            match msg {
+                Ok(()) => sent = true,
+                Err(err) => error!("Failed to DM vote reminder to {}: {}", reminder.user_id, err),
+            }
+        }
+
+        sent
+    }
+
+    // Webhook delivery queue
+
+    /// Queues a webhook to be sent by the webhook worker
+    pub async fn enqueue_webhook(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+        eid: &str,
+        url: &str,
+        payload: serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO webhook_deliveries (target_id, target_type, eid, url, payload) 
+            VALUES ($1, $2, $3, $4, $5)",
+            target_id,
+            target_type as i32,
+            eid,
+            url,
+            payload
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claims up to ``limit`` due deliveries. Claimed deliveries are leased for a few minutes 
+    /// so a crashed worker doesn't lose them and multiple workers never send the same delivery
+    pub async fn claim_webhook_deliveries(&self, limit: i64) -> Vec<models::PendingWebhookDelivery> {
+        let rows = sqlx::query!(
+            "UPDATE webhook_deliveries SET next_attempt_at = NOW() + interval '5 minutes' 
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries WHERE status = $1 AND next_attempt_at <= NOW() 
+                ORDER BY next_attempt_at LIMIT $2 FOR UPDATE SKIP LOCKED
+            ) RETURNING id, target_id, target_type, eid, url, payload, attempts",
+            models::WebhookDeliveryStatus::Pending as i32,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await;
+
+        match rows {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|row| models::PendingWebhookDelivery {
+                    id: row.id,
+                    target_id: row.target_id,
+                    target_type: if row.target_type == models::TargetType::Server as i32 {
+                        models::TargetType::Server
+                    } else {
+                        models::TargetType::Bot
+                    },
+                    eid: row.eid,
+                    url: row.url,
+                    payload: row.payload,
+                    attempts: row.attempts,
+                })
+                .collect(),
+            Err(err) => {
+                error!("Failed to claim webhook deliveries: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Returns the secrets to sign webhooks with and whether ``webhook_hmac_only`` is set
+    ///
+    /// The secondary secret is only set while a secret is being rotated. Returns ``Ok(None)`` if the bot/server no longer exists
+    pub async fn get_webhook_secret(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+    ) -> Result<Option<models::WebhookSecrets>, models::WebhookError> {
+        let (webhook_secret, secondary, hmac_only) = match target_type {
+            models::TargetType::Bot => {
+                let row = sqlx::query!(
+                    "SELECT webhook_secret, webhook_secret_secondary, webhook_hmac_only FROM bots WHERE bot_id = $1",
+                    target_id
+                )
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(models::WebhookError::SQLError)?;
+
+                match row {
+                    Some(row) => (row.webhook_secret, row.webhook_secret_secondary, row.webhook_hmac_only),
+                    None => return Ok(None),
+                }
+            }
+            models::TargetType::Server => {
+                let row = sqlx::query!(
+                    "SELECT webhook_secret, webhook_secret_secondary, webhook_hmac_only FROM servers WHERE guild_id = $1",
+                    target_id
+                )
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(models::WebhookError::SQLError)?;
+
+                match row {
+                    Some(row) => (row.webhook_secret, row.webhook_secret_secondary, row.webhook_hmac_only),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        // Webhooks used to be signed with the API token if no secret was set. API tokens are now 
+        // only stored hashed so a secret is created instead
+        let (secret, secondary) = match webhook_secret {
+            Some(secret) if !secret.is_empty() => (secret, secondary.filter(|secret| !secret.is_empty())),
+            _ => {
+                let secret = converters::create_token(64);
+                // As there is no current secret, this leaves no secondary secret
+                self.rotate_webhook_secret(target_id, target_type, &secret).await?;
+                (secret, None)
+            }
+        };
+
+        Ok(Some(models::WebhookSecrets {
+            secret,
+            secondary,
+            hmac_only: hmac_only.unwrap_or(false),
+        }))
+    }
+
+    /// Rotates the webhook secret of a bot/server. The current secret is kept as the secondary 
+    /// secret (and still signed with) until ``clear_secondary_webhook_secret`` is called
+    pub async fn rotate_webhook_secret(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+        secret: &str,
+    ) -> Result<(), models::WebhookError> {
+        let res = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!(
+                    "UPDATE bots SET webhook_secret_secondary = nullif(webhook_secret, ''), 
+                    webhook_secret = $2 WHERE bot_id = $1",
+                    target_id,
+                    secret
+                )
+                .execute(&self.pool)
+                .await
+            }
+            models::TargetType::Server => {
+                sqlx::query!(
+                    "UPDATE servers SET webhook_secret_secondary = nullif(webhook_secret, ''), 
+                    webhook_secret = $2 WHERE guild_id = $1",
+                    target_id,
+                    secret
+                )
+                .execute(&self.pool)
+                .await
+            }
+        };
+
+        res.map_err(models::WebhookError::SQLError)?;
+
+        Ok(())
+    }
+
+    pub async fn clear_secondary_webhook_secret(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+    ) -> Result<(), models::WebhookError> {
+        let res = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!("UPDATE bots SET webhook_secret_secondary = NULL WHERE bot_id = $1", target_id)
+                    .execute(&self.pool)
+                    .await
+            }
+            models::TargetType::Server => {
+                sqlx::query!("UPDATE servers SET webhook_secret_secondary = NULL WHERE guild_id = $1", target_id)
+                    .execute(&self.pool)
+                    .await
+            }
+        };
+
+        res.map_err(models::WebhookError::SQLError)?;
+
+        Ok(())
+    }
+
+    /// Records the result of a delivery attempt, ``retry_at`` being ``None`` dead-letters the delivery
+    pub async fn finish_webhook_delivery(
+        &self,
+        id: uuid::Uuid,
+        status: models::WebhookDeliveryStatus,
+        status_code: Option<i32>,
+        error: Option<String>,
+        retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        let res = sqlx::query!(
+            "UPDATE webhook_deliveries SET status = $2, attempts = attempts + 1, 
+            last_status_code = $3, last_error = $4, next_attempt_at = coalesce($5, next_attempt_at), 
+            delivered_at = CASE WHEN $2 = $6 THEN NOW() ELSE delivered_at END WHERE id = $1",
+            id,
+            status as i32,
+            status_code,
+            error,
+            retry_at,
+            models::WebhookDeliveryStatus::Delivered as i32
+        )
+        .execute(&self.pool)
+        .await;
+
+        if let Err(err) = res {
+            error!("Failed to update webhook delivery {}: {}", id, err);
+        }
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+        cursor: Option<&models::Cursor>,
+        limit: i64,
+    ) -> Result<models::CursorPage<models::WebhookDelivery>, models::WebhookError> {
+        let cursor_id = cursor.map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
+        let rows = sqlx::query!(
+            "SELECT id, eid, url, status, attempts, last_status_code, last_error, 
+            next_attempt_at, created_at, delivered_at FROM webhook_deliveries 
+            WHERE target_id = $1 AND target_type = $2 AND (
+                $3::bigint IS NULL
+                OR (NOT $5 AND (created_at, id) < (TIMESTAMPTZ 'epoch' + $3 * interval '1 microsecond', $4::uuid))
+                OR ($5 AND (created_at, id) > (TIMESTAMPTZ 'epoch' + $3 * interval '1 microsecond', $4::uuid))
+            ) ORDER BY 
+            CASE WHEN $5 THEN created_at END ASC, CASE WHEN $5 THEN id END ASC, 
+            created_at DESC, id DESC LIMIT $6",
+            target_id,
+            target_type as i32,
+            cursor.map(|c| c.key),
+            cursor_id,
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(models::WebhookError::SQLError)?;
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.created_at.timestamp_nanos() / 1000, row.id.to_string())
+        });
+
+        let deliveries = page
+            .items
+            .into_iter()
+            .map(|row| models::WebhookDelivery {
+                id: row.id.to_string(),
+                eid: row.eid,
+                url: row.url,
+                status: models::WebhookDeliveryStatus::try_from(row.status).unwrap_or_default(),
+                attempts: row.attempts,
+                last_status_code: row.last_status_code,
+                last_error: row.last_error,
+                next_attempt_at: row.next_attempt_at,
+                created_at: row.created_at,
+                delivered_at: row.delivered_at,
+            })
+            .collect();
+
+        Ok(models::CursorPage {
+            items: deliveries,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Queues a new delivery of a previously sent event to the current webhook URL
+    pub async fn redeliver_webhook(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+        eid: &str,
+    ) -> Result<(), models::WebhookError> {
+        let webhook = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!("SELECT webhook FROM bots WHERE bot_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?
+                    .webhook
+            }
+            models::TargetType::Server => {
+                sqlx::query!("SELECT webhook FROM servers WHERE guild_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?
+                    .webhook
+            }
+        };
+
+        let webhook = match webhook {
+            Some(webhook) if !webhook.is_empty() => webhook,
+            _ => return Err(models::WebhookError::NoWebhook),
+        };
+
+        let res = sqlx::query!(
+            "INSERT INTO webhook_deliveries (target_id, target_type, eid, url, payload) 
+            SELECT target_id, target_type, eid, $4, payload FROM webhook_deliveries 
+            WHERE target_id = $1 AND target_type = $2 AND eid = $3 
+            ORDER BY created_at DESC LIMIT 1",
+            target_id,
+            target_type as i32,
+            eid,
+            webhook
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(models::WebhookError::SQLError)?;
+
+        if res.rows_affected() == 0 {
+            return Err(models::WebhookError::UnknownEvent);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the webhook event subscription mask of a bot/server
+    pub async fn get_webhook_events(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+    ) -> Result<i64, models::WebhookError> {
+        let mask = match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!("SELECT webhook_events FROM bots WHERE bot_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?
+                    .webhook_events
+            }
+            models::TargetType::Server => {
+                sqlx::query!("SELECT webhook_events FROM servers WHERE guild_id = $1", target_id)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?
+                    .webhook_events
+            }
+        };
+
+        Ok(mask)
+    }
+
+    pub async fn set_webhook_events(
+        &self,
+        target_id: i64,
+        target_type: models::TargetType,
+        mask: i64,
+    ) -> Result<(), models::WebhookError> {
+        match target_type {
+            models::TargetType::Bot => {
+                sqlx::query!("UPDATE bots SET webhook_events = $2 WHERE bot_id = $1", target_id, mask)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?;
+            }
+            models::TargetType::Server => {
+                sqlx::query!("UPDATE servers SET webhook_events = $2 WHERE guild_id = $1", target_id, mask)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(models::WebhookError::SQLError)?;
+            }
+        }
+
+        self.webhook_target_cache.invalidate(&(target_type, target_id)).await;
+
+        Ok(())
+    }
+
+    /// Returns a page of the staff queue, oldest first
+    pub async fn get_queue(
+        &self,
+        target_type: models::TargetType,
+        state: models::State,
+        cursor: Option<&models::Cursor>,
+        limit: i64,
+    ) -> Result<models::CursorPage<models::QueueEntry>, models::StaffActionError> {
+        let cursor_key = cursor.map(|c| c.key);
+        let cursor_id = cursor.map(|c| c.id_i64());
+        let rev = cursor.map(|c| c.rev).unwrap_or(false);
+
+        // (id, created_at, state, verifier, description)
+        let rows: Vec<(i64, chrono::DateTime<Utc>, i32, Option<i64>, String)> = match target_type {
+            models::TargetType::Bot => sqlx::query!(
+                "SELECT bot_id, created_at, state, verifier, description FROM bots WHERE state = $1 AND (
+                    $2::bigint IS NULL
+                    OR (NOT $4 AND (created_at, bot_id) > (TIMESTAMPTZ 'epoch' + $2 * interval '1 microsecond', $3))
+                    OR ($4 AND (created_at, bot_id) < (TIMESTAMPTZ 'epoch' + $2 * interval '1 microsecond', $3))
+                ) ORDER BY 
+                CASE WHEN $4 THEN created_at END DESC, CASE WHEN $4 THEN bot_id END DESC, 
+                created_at ASC, bot_id ASC LIMIT $5",
+                state as i32,
+                cursor_key,
+                cursor_id,
+                rev,
+                limit + 1
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(models::StaffActionError::SQLError)?
+            .into_iter()
+            .map(|row| (row.bot_id, row.created_at, row.state, row.verifier, row.description))
+            .collect(),
+            models::TargetType::Server => sqlx::query!(
+                "SELECT guild_id, created_at, state, verifier, description FROM servers WHERE state = $1 AND (
+                    $2::bigint IS NULL
+                    OR (NOT $4 AND (created_at, guild_id) > (TIMESTAMPTZ 'epoch' + $2 * interval '1 microsecond', $3))
+                    OR ($4 AND (created_at, guild_id) < (TIMESTAMPTZ 'epoch' + $2 * interval '1 microsecond', $3))
+                ) ORDER BY 
+                CASE WHEN $4 THEN created_at END DESC, CASE WHEN $4 THEN guild_id END DESC, 
+                created_at ASC, guild_id ASC LIMIT $5",
+                state as i32,
+                cursor_key,
+                cursor_id,
+                rev,
+                limit + 1
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(models::StaffActionError::SQLError)?
+            .into_iter()
+            .map(|row| (row.guild_id, row.created_at, row.state, row.verifier, row.description))
+            .collect(),
+        };
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.1.timestamp_nanos() / 1000, row.0.to_string())
+        });
+
+        let mut queue = Vec::new();
+
+        for (id, created_at, row_state, verifier, description) in page.items {
+            queue.push(models::QueueEntry {
+                user: match target_type {
+                    models::TargetType::Bot => self.get_user(id).await,
+                    models::TargetType::Server => self.get_server_user(id).await,
+                },
+                target_type,
+                state: models::State::try_from(row_state).unwrap_or(state),
+                verifier: verifier.map(|id| id.to_string()),
+                description,
+                created_at,
+            });
+        }
+
+        Ok(models::CursorPage {
+            items: queue,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Claims, unclaims, approves, denies, bans, certifies or requeues a bot or server as staff, returning
+    /// the state it was in before. This is logged to ``audit_logs`` and ``user_bot_logs`` (bots) or
+    /// ``server_audit_logs`` (servers)
+    pub async fn staff_action(
+        &self,
+        staff_id: i64,
+        target_id: i64,
+        target_type: models::TargetType,
+        action: models::UserBotAction,
+        reason: Option<&str>,
+    ) -> Result<models::State, models::StaffActionError> {
+        // The states the action can be done from (None meaning any state but the new one) and the new state
+        let (from, to) = match action {
+            models::UserBotAction::Claim => (Some(vec![models::State::Pending]), models::State::UnderReview),
+            models::UserBotAction::Unclaim => (Some(vec![models::State::UnderReview]), models::State::Pending),
+            models::UserBotAction::Approve => (Some(vec![models::State::UnderReview]), models::State::Approved),
+            models::UserBotAction::Deny => (Some(vec![models::State::UnderReview]), models::State::Denied),
+            models::UserBotAction::Ban => (None, models::State::Banned),
+            models::UserBotAction::Certify => (Some(vec![models::State::Approved]), models::State::Certified),
+            models::UserBotAction::Requeue => (
+                Some(vec![models::State::Denied, models::State::Banned]),
+                models::State::Pending,
+            ),
+            _ => return Err(models::StaffActionError::UnsupportedAction),
+        };
+
+        let reason = reason.map(|reason| reason.trim()).filter(|reason| !reason.is_empty());
+
+        if reason.is_none() && (action == models::UserBotAction::Deny || action == models::UserBotAction::Ban) {
+            return Err(models::StaffActionError::ReasonRequired);
+        }
+
+        // Claiming, approving and denying set the verifier, unclaiming and requeueing clear it
+        let (verifier, keep_verifier) = match action {
+            models::UserBotAction::Claim | models::UserBotAction::Approve | models::UserBotAction::Deny => {
+                (Some(staff_id), false)
+            }
+            models::UserBotAction::Unclaim | models::UserBotAction::Requeue => (None, false),
+            _ => (None, true),
+        };
+
+        let from: Option<Vec<i32>> = from.map(|states| states.into_iter().map(|state| state as i32).collect());
+
+        let new_log = |target_type| models::NewAuditLog {
+            target_id,
+            target_type,
+            actor_id: staff_id,
+            action: models::AuditAction::try_from(action as i32).unwrap_or_default(),
+            context: reason.map(|reason| reason.to_string()),
+        };
+
+        let mut tx = self.pool.begin().await.map_err(models::StaffActionError::SQLError)?;
+
+        // The state is checked in the update itself so two staff members cannot act on the same bot at once
+        let old_state = match target_type {
+            models::TargetType::Bot => {
+                let old_state = sqlx::query!("SELECT state FROM bots WHERE bot_id = $1 FOR UPDATE", target_id)
+                    .fetch_optional(&mut tx)
+                    .await
+                    .map_err(models::StaffActionError::SQLError)?
+                    .ok_or(models::StaffActionError::NotFound)?
+                    .state;
+
+                let res = sqlx::query!(
+                    "UPDATE bots SET state = $1, verifier = CASE WHEN $2 THEN verifier ELSE $3 END 
+                    WHERE bot_id = $4 AND (($5::integer[] IS NULL AND state <> $1) OR state = ANY($5))",
+                    to as i32,
+                    keep_verifier,
+                    verifier,
+                    target_id,
+                    from.as_deref()
+                )
+                .execute(&mut tx)
+                .await
+                .map_err(models::StaffActionError::SQLError)?;
+
+                if res.rows_affected() == 0 {
+                    return Err(models::StaffActionError::InvalidState(
+                        models::State::try_from(old_state).unwrap_or(models::State::Pending),
+                    ));
+                }
+
+                Database::insert_audit_log(&mut tx, &new_log(models::AuditTargetType::Bot))
+                    .await
+                    .map_err(models::StaffActionError::SQLError)?;
+
+                old_state
+            }
+            models::TargetType::Server => {
+                let old_state = sqlx::query!("SELECT state FROM servers WHERE guild_id = $1 FOR UPDATE", target_id)
+                    .fetch_optional(&mut tx)
+                    .await
+                    .map_err(models::StaffActionError::SQLError)?
+                    .ok_or(models::StaffActionError::NotFound)?
+                    .state;
+
+                let res = sqlx::query!(
+                    "UPDATE servers SET state = $1, verifier = CASE WHEN $2 THEN verifier ELSE $3 END 
+                    WHERE guild_id = $4 AND (($5::integer[] IS NULL AND state <> $1) OR state = ANY($5))",
+                    to as i32,
+                    keep_verifier,
+                    verifier,
+                    target_id,
+                    from.as_deref()
+                )
+                .execute(&mut tx)
+                .await
+                .map_err(models::StaffActionError::SQLError)?;
+
+                if res.rows_affected() == 0 {
+                    return Err(models::StaffActionError::InvalidState(
+                        models::State::try_from(old_state).unwrap_or(models::State::Pending),
+                    ));
+                }
+
+                let audit_id = Database::insert_audit_log(&mut tx, &new_log(models::AuditTargetType::Server))
+                    .await
+                    .map_err(models::StaffActionError::SQLError)?;
+
+                let staff = self.get_user(staff_id).await;
+
+                // Same ID so the audit_logs migration does not copy it again
+                sqlx::query!(
+                    "INSERT INTO server_audit_logs (guild_id, user_id, username, user_guild_perms, field, value, action_id) 
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    target_id,
+                    staff_id,
+                    staff.username,
+                    "staff",
+                    format!("{:?}", action),
+                    reason.unwrap_or_default(),
+                    audit_id
+                )
+                .execute(&mut tx)
+                .await
+                .map_err(models::StaffActionError::SQLError)?;
+
+                old_state
+            }
+        };
+
+        tx.commit().await.map_err(models::StaffActionError::SQLError)?;
+
+        match target_type {
+            models::TargetType::Bot => self.bot_cache.invalidate(&target_id).await,
+            models::TargetType::Server => self.server_cache.invalidate(&target_id).await,
+        }
+
+        self.index_cache.invalidate(&target_type).await;
+
+        Ok(models::State::try_from(old_state).unwrap_or(models::State::Pending))
+    }
+
+    /// Records who did what in ``audit_logs``. Bot actions that are also a ``UserBotAction`` are kept in
+    /// ``user_bot_logs`` too as they are shown in ``Bot.action_logs``
+    async fn insert_audit_log(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        log: &models::NewAuditLog,
+    ) -> Result<uuid::Uuid, sqlx::Error> {
+        let id = sqlx::query!(
+            "INSERT INTO audit_logs (target_id, target_type, actor_id, action, context) 
+            VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            log.target_id,
+            log.target_type as i32,
+            log.actor_id,
+            log.action as i32,
+            log.context
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .id;
+
+        if log.target_type == models::AuditTargetType::Bot && models::UserBotAction::try_from(log.action as i32).is_ok() {
+            // Same ID so the audit_logs migration does not copy it again
+            sqlx::query!(
+                "INSERT INTO user_bot_logs (user_id, bot_id, action, context, _lynxtag) VALUES ($1, $2, $3, $4, $5)",
+                log.actor_id,
+                log.target_id,
+                log.action as i32,
+                log.context,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        Ok(id)
+    }
+
+    /// Records a audit log outside of a transaction, failures are only logged
+    pub async fn audit_log(&self, log: models::NewAuditLog) {
+        let res: Result<(), sqlx::Error> = async {
+            let mut tx = self.pool.begin().await?;
+            Database::insert_audit_log(&mut tx, &log).await?;
+            tx.commit().await
+        }
+        .await;
+
+        if let Err(err) = res {
+            error!("Failed to record audit log for {}: {}", log.target_id, err);
+        }
+    }
+
+    /// Returns a page of the audit log of a bot, server or user, newest first
+    pub async fn get_audit_logs(
+        &self,
+        target_id: i64,
+        target_type: models::AuditTargetType,
+        filter: &models::AuditLogQuery,
+        cursor: Option<&models::Cursor>,
+        limit: i64,
+    ) -> Result<models::CursorPage<models::AuditLog>, models::GenericError> {
+        let cursor_id = cursor.map(|c| uuid::Uuid::parse_str(&c.id).unwrap_or_default());
+
+        let rows = sqlx::query!(
+            "SELECT id, target_id, target_type, actor_id, action, context, created_at FROM audit_logs 
+            WHERE target_id = $1 AND target_type = $2 
+            AND ($3::integer IS NULL OR action = $3) 
+            AND ($4::bigint IS NULL OR actor_id = $4) 
+            AND ($5::bigint IS NULL OR created_at >= TO_TIMESTAMP($5)) 
+            AND ($6::bigint IS NULL OR created_at < TO_TIMESTAMP($6)) 
+            AND (
+                $7::bigint IS NULL
+                OR (NOT $9 AND (created_at, id) < (TIMESTAMPTZ 'epoch' + $7 * interval '1 microsecond', $8::uuid))
+                OR ($9 AND (created_at, id) > (TIMESTAMPTZ 'epoch' + $7 * interval '1 microsecond', $8::uuid))
+            ) ORDER BY 
+            CASE WHEN $9 THEN created_at END ASC, CASE WHEN $9 THEN id END ASC, 
+            created_at DESC, id DESC LIMIT $10",
+            target_id,
+            target_type as i32,
+            filter.action.map(|action| action as i32),
+            filter.actor,
+            filter.from,
+            filter.to,
+            cursor.map(|c| c.key),
+            cursor_id,
+            cursor.map(|c| c.rev).unwrap_or(false),
+            limit + 1
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(models::GenericError::SQLError)?;
+
+        let page = models::Cursor::paginate(rows, limit, cursor, |row| {
+            (row.created_at.timestamp_nanos() / 1000, row.id.to_string())
+        });
+
+        let logs = page
+            .items
+            .into_iter()
+            .map(|row| models::AuditLog {
+                id: row.id,
+                target_id: row.target_id.to_string(),
+                target_type: models::AuditTargetType::try_from(row.target_type).unwrap_or(target_type),
+                actor_id: row.actor_id.to_string(),
+                action: models::AuditAction::try_from(row.action).unwrap_or_default(),
+                context: row.context,
+                created_at: row.created_at,
+            })
+            .collect();
+
+        Ok(models::CursorPage {
+            items: logs,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    pub async fn is_server_owner(&self, server_id: i64, user_id: i64) -> bool {
+        sqlx::query!(
+            "SELECT COUNT(*) FROM servers WHERE guild_id = $1 AND owner_id = $2",
+            server_id,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map(|row| row.count.unwrap_or(0) > 0)
+        .unwrap_or(false)
+    }
 }
@@ -0,0 +1,247 @@
+// Dev portal endpoints for users to manage their Frostpaw clients
+use crate::auth;
+use crate::models;
+use actix_web::{delete, get, http, patch, post, web, HttpRequest, HttpResponse, ResponseError};
+use log::error;
+use serenity::model::prelude::*;
+
+/// Checks the request is from the user (using a full access token) and that they have the dev portal
+async fn check_dev(data: &models::AppState, auth: &auth::UserAuth, user_id: i64) -> Result<(), HttpResponse> {
+    if let Err(err) = auth.ensure(user_id) {
+        error!("Dev Portal Auth error");
+        return Err(err.error_response());
+    }
+
+    let user_experiments = data.database.get_user_experiments(user_id).await;
+
+    if !user_experiments.contains(&models::UserExperiments::DevPortal) {
+        return Err(models::UserExperiments::DevPortal.not_enabled());
+    }
+
+    Ok(())
+}
+
+/// Normalizes a domain, which must be a bare host (with an optional port) such as ``example.com``
+fn check_domain(domain: &str) -> Result<String, models::FrostpawClientError> {
+    let domain = domain.trim().to_lowercase();
+
+    let url = reqwest::Url::parse(&("https://".to_string() + &domain))
+        .map_err(|_| models::FrostpawClientError::InvalidDomain(domain.clone()))?;
+
+    let host = url.host_str().unwrap_or_default();
+    let authority = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+
+    if authority != domain || url.path() != "/" {
+        return Err(models::FrostpawClientError::InvalidDomain(domain));
+    }
+
+    Ok(domain)
+}
+
+fn client_check(client: &mut models::EditFrostpawClient) -> Result<(), models::FrostpawClientError> {
+    client.name = client.name.trim().to_string();
+
+    if client.name.len() < 3 || client.name.len() > 64 {
+        return Err(models::FrostpawClientError::NameError);
+    }
+
+    if !client.privacy_policy.starts_with("https://") {
+        return Err(models::FrostpawClientError::InvalidPrivacyPolicy);
+    }
+
+    client.domain = check_domain(&client.domain)?;
+
+    let mut redirect_domains = Vec::new();
+
+    for domain in &client.redirect_domains {
+        let domain = check_domain(domain)?;
+
+        if !redirect_domains.contains(&domain) {
+            redirect_domains.push(domain);
+        }
+    }
+
+    // The main domain can always be redirected to
+    if !redirect_domains.contains(&client.domain) {
+        redirect_domains.insert(0, client.domain.clone());
+    }
+
+    if redirect_domains.len() > 10 {
+        return Err(models::FrostpawClientError::TooManyDomains);
+    }
+
+    client.redirect_domains = redirect_domains;
+
+    Ok(())
+}
+
+#[get("/users/{id}/clients")]
+async fn get_user_clients(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, id.id).await {
+        return err;
+    }
+
+    HttpResponse::Ok().json(data.database.get_user_clients(id.id).await)
+}
+
+#[post("/users/{id}/clients")]
+async fn create_client(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    client: web::Json<models::EditFrostpawClient>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, id.id).await {
+        return err;
+    }
+
+    let mut client = client.into_inner();
+
+    if let Err(err) = client_check(&mut client) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err));
+    }
+
+    match data.database.create_frostpaw_client(id.id, &client).await {
+        Ok(new_client) => HttpResponse::Ok().json(new_client),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+#[patch("/users/{user_id}/clients/{client_id}")]
+async fn edit_client(
+    req: HttpRequest,
+    info: web::Path<models::UserClientPath>,
+    client: web::Json<models::EditFrostpawClient>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, info.user_id).await {
+        return err;
+    }
+
+    let mut client = client.into_inner();
+
+    if let Err(err) = client_check(&mut client) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err));
+    }
+
+    match data.database.edit_frostpaw_client(info.user_id, &info.client_id, &client).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(models::FrostpawClientError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::FrostpawClientError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+#[delete("/users/{user_id}/clients/{client_id}")]
+async fn delete_client(req: HttpRequest, info: web::Path<models::UserClientPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, info.user_id).await {
+        return err;
+    }
+
+    match data.database.delete_frostpaw_client(info.user_id, &info.client_id).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(models::FrostpawClientError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::FrostpawClientError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Rotates the secret of a client. The old secret stops working immediately
+#[post("/users/{user_id}/clients/{client_id}/secret")]
+async fn rotate_client_secret(req: HttpRequest, info: web::Path<models::UserClientPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, info.user_id).await {
+        return err;
+    }
+
+    match data.database.rotate_frostpaw_client_secret(info.user_id, &info.client_id).await {
+        Ok(secret) => HttpResponse::Ok().json(secret),
+        Err(models::FrostpawClientError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::FrostpawClientError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Submits a client to staff for verification
+#[post("/users/{user_id}/clients/{client_id}/verification")]
+async fn request_client_verification(
+    req: HttpRequest,
+    info: web::Path<models::UserClientPath>,
+    request: web::Json<models::ClientVerificationRequest>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = check_dev(data, &auth, info.user_id).await {
+        return err;
+    }
+
+    let client = match data.database.get_frostpaw_client(&info.client_id).await {
+        Some(client) if client.owner.id == info.user_id.to_string() => client,
+        _ => return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::FrostpawClientError::NotFound)),
+    };
+
+    match client.verification_state {
+        models::ClientVerificationState::Verified => {
+            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::FrostpawClientError::AlreadyVerified));
+        }
+        models::ClientVerificationState::Pending => {
+            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::FrostpawClientError::VerificationPending));
+        }
+        _ => {}
+    }
+
+    if request.reason.len() < 7 || request.reason.len() > 4000 {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::FrostpawClientError::VerificationReasonError));
+    }
+
+    if let Err(err) = data.database.set_client_verification_state(&client.id, models::ClientVerificationState::Pending).await {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::FrostpawClientError::SQLError(err)));
+    }
+
+    let msg = data.config.discord.channels.appeals_channel.send_message(&data.config.discord_http, |m| {
+        m.content(data.config.discord.roles.staff_ping_add_role.mention());
+
+        m.embed(|e| {
+            e.title("Client Verification Request");
+            e.color(0x0000_ff00);
+            e.description(
+                format!(
+                    "{user} has requested verification for their client {name} (``{id}``)",
+                    user = UserId(info.user_id as u64).mention(),
+                    name = client.name,
+                    id = client.id,
+                )
+            );
+
+            e.field("Domain", &client.domain, true);
+            e.field("Privacy Policy", &client.privacy_policy, true);
+            e.field("Reason", &request.reason, false);
+
+            e
+        });
+        m
+    }).await;
+
+    if msg.is_err() {
+        // Let them try again
+        let _e = data.database.set_client_verification_state(&client.id, client.verification_state).await;
+
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse {
+            done: false,
+            reason: Some("Failed to send verification request. Please try again.".to_string()),
+            context: None,
+        });
+    }
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
+}
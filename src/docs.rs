@@ -13,6 +13,38 @@ const QUERY_PARAMS: &str = "Query Parameters";
 const REQ_BODY: &str = "Request Body";
 const RESP_BODY: &str = "Response Body";
 
+/// Webhook signature test vectors (secret, timestamp, eid, body, ``v2`` signature) for receivers.
+/// These are checked against ``converters::verify_webhook`` in the tests below
+const WEBHOOK_TEST_VECTORS: [(&str, i64, &str, &str, &str); 2] = [
+    (
+        "primary-secret-0123456789",
+        1660000000,
+        "b6b4c8e2-8f0a-4a59-9d1c-3f0c8a1e2d7b",
+        r#"{"votes":10}"#,
+        "1fff9ea655c99f9f2df54d85d8a8d7f2b370cc134e675758275607a49db3e28541af236db334f336f63cd9cd5619ada60b9862e1368835eca819d482e24ad285",
+    ),
+    (
+        "secondary-secret-0123456789",
+        1660000000,
+        "b6b4c8e2-8f0a-4a59-9d1c-3f0c8a1e2d7b",
+        r#"{"votes":10}"#,
+        "77c1c65f05ef02862f0648ced034fe38ec5f4d18a655e45accf4c0b9122dcf56bb6a53388cccc462caf977d30796c472ada6d317c64fb14f9959797fcd67aada",
+    ),
+];
+
+fn webhook_test_vectors() -> String {
+    let mut vectors = String::new();
+
+    for (secret, timestamp, eid, body, signature) in WEBHOOK_TEST_VECTORS {
+        writeln!(
+            vectors,
+            "- **Secret:** ``{secret}``\n  **X-Webhook-Timestamp:** ``{timestamp}``\n  **X-Webhook-Event-Id:** ``{eid}``\n  **Body:** ``{body}``\n  **Signature:** ``v2={signature}``\n"
+        ).unwrap();
+    }
+
+    vectors
+}
+
 
 fn body<T: Serialize>(typ: &str, obj: T) -> String {
     if typ == PATH_PARAMS || typ == QUERY_PARAMS {
@@ -258,7 +290,8 @@ pub fn document_routes() {
 ## Authorization
 
 - **Bot:** These endpoints require a bot token. 
-You can get this from Bot Settings. Make sure to keep this safe and in 
+You can get this from Bot Settings (tokens are only stored hashed, so a token is only 
+shown once when it is created). Make sure to keep this safe and in 
 a .gitignore/.env. A prefix of `Bot` before the bot token such as 
 `Bot abcdef` is supported and can be used to avoid ambiguity but is not 
 required. The default auth scheme if no prefix is given depends on the
@@ -273,7 +306,7 @@ apply here. A prefix of ``Server`` before the server token is
 supported and can be used to avoid ambiguity but is not required.
 
 - **User:** These endpoints require a user token. You can get this 
-from your profile under the User Token section. If you are using this 
+from your profile under the User Token section (this is only shown once when it is created). If you are using this 
 for voting, make sure to allow users to opt out! A prefix of `User` 
 before the user token such as `User abcdef` is supported and can be 
 used to avoid ambiguity but is not required outside of endpoints that 
@@ -285,6 +318,15 @@ can also be used on *most* endpoints as long as the token is prefixed with
 
 - **Special:** These endpoint employ their own authentication system (such as ``slwebset``)
 
+If authorization fails, a 403 with a ``reason`` of ``Forbidden`` is returned. The ``context``
+will be one of ``MissingToken``, ``InvalidToken``, ``WrongTokenType`` (such as a bot token on a
+user endpoint), ``WrongTarget`` (the token is valid but not for the user/bot/server in the path)
+or ``MissingScope`` (a scoped API token without the [scope](./enums#tokenscope) the endpoint needs)
+
+- **Scoped API tokens:** Users and bots can also create named tokens (starting with ``fls_``) that can only 
+do what their scopes allow and can expire. See [Security](./security) to create them. These are sent 
+like other tokens of the same type
+
 ## Base Response
 
 A default API Response will be of the below format:
@@ -296,6 +338,31 @@ A default API Response will be of the below format:
     context: "" | null
 }
 ```
+
+## Rate Limits
+
+All endpoints are rate limited. Endpoints are split into buckets (``global``, ``votes``, ``reviews``,
+``review_votes``, ``search``, ``stats``, ``gateway`` and ``login``), each with its own limit. Requests
+with a valid token are limited per user/bot/server, certified bots and staff get higher limits. Requests
+without a valid token are limited by IP
+
+Every response has the following headers:
+
+- ``X-RateLimit-Limit``: How many requests can be made in the current window
+- ``X-RateLimit-Remaining``: How many requests are left in the current window
+- ``X-RateLimit-Reset``: When the current window ends (as a unix timestamp in seconds)
+- ``X-RateLimit-Bucket``: The bucket the endpoint is in
+
+If you are rate limited, a 429 is returned with a ``Retry-After`` header (in seconds) and the below
+response:
+
+```json
+{
+    done: false,
+    reason: "You have been rate limited for 30 seconds",
+    context: null
+}
+```
 "#;
 
     let index_bots = vec![models::IndexBot::default()];
@@ -318,8 +385,13 @@ A default API Response will be of the below format:
                         path_params: "",
                         query_params: &body(QUERY_PARAMS, &models::IndexQuery {
                             target_type: models::TargetType::Server,
+                            cursor: Some("next_cursor or prev_cursor from a previous response".to_string()),
                         }),
-                        description: "Returns the index for bots and servers",
+                        description: r#"
+Returns the index for bots and servers
+
+``next_cursor`` and ``prev_cursor`` can be passed back as ``cursor`` to walk through ``top_voted``. 
+They are ``null`` if there is no next or previous page. All other fields are the same regardless of cursor"#,
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::Index {
 			    random: models::IndexBot::default(),
@@ -328,6 +400,8 @@ A default API Response will be of the below format:
                             new: index_bots.clone(),
                             tags: tags.clone(),
                             features: features.clone(),
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
                         }),
                         auth_types: vec![]
                     },
@@ -416,11 +490,29 @@ A default API Response will be of the below format:
                             q: "mew".to_string(),
                             gc_from: 1,
                             gc_to: -1,
+                            page: Some(1),
+                            per_page: Some(6),
+                            tags: Some("music,utility".to_string()),
+                            features: Some("slash_commands".to_string()),
+                            library: Some("discord.py".to_string()),
+                            certified_only: Some(false),
+                            nsfw: Some(false),
+                            min_stars: Some(3.5),
+                            sort: Some(models::SearchSort::Votes),
                         }),
                         description: r#"
 Searches the list based on a query named ``q``. 
         
-Using -1 for ``gc_to`` will disable ``gc_to`` field"#,
+Using -1 for ``gc_to`` will disable ``gc_to`` field
+
+- Results are ranked by relevance (full text search with prefix matching) and are typo tolerant
+- ``page`` defaults to 1 and ``per_page`` defaults to 6 (maximum of 50). Pages past 1000 are rejected with a ``InvalidPage`` error
+- ``totals`` contains the total amount of hits for each category, regardless of pagination
+- ``tags`` and ``features`` are comma seperated lists. Every tag/feature given must be present
+- ``library`` and ``features`` only apply to bots. ``certified_only``, ``nsfw``, ``min_stars`` and ``tags`` apply to both bots and servers
+- ``nsfw`` set to ``true`` only returns NSFW bots/servers, ``false`` excludes them and omitting it returns both
+- ``sort`` is a [SearchSort](./enums#searchsort) and defaults to ``relevance``
+- ``facets`` contains the amount of matching bots per tag and per feature (for example ``utility (412)``)"#,
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::Search {
                             bots: vec![models::IndexBot::default()],
@@ -431,6 +523,13 @@ Using -1 for ``gc_to`` will disable ``gc_to`` field"#,
                                 bots: vec![models::Tag::default()],
                                 servers: vec![models::Tag::default()]
                             },
+                            totals: models::SearchTotals::default(),
+                            facets: models::SearchFacets {
+                                tags: std::collections::HashMap::from([("utility".to_string(), 412)]),
+                                features: std::collections::HashMap::from([("slash_commands".to_string(), 39)]),
+                            },
+                            next_cursor: None,
+                            prev_cursor: None,
                         }),
                         auth_types: vec![]
                     },
@@ -442,8 +541,14 @@ Using -1 for ``gc_to`` will disable ``gc_to`` field"#,
                         path_params: "",
                         query_params: &body(QUERY_PARAMS, &models::SearchTagQuery {
                             q: "mew".to_string(),
+                            cursor: Some("next_cursor or prev_cursor from a previous response".to_string()),
+                            limit: Some(15),
                         }),
-                        description: "Searches the list based on a tag named ``q``.",
+                        description: r#"
+Searches the list based on a tag named ``q``.
+
+``bots`` is paginated using ``next_cursor`` and ``prev_cursor`` which can be passed back as ``cursor``. 
+``limit`` defaults to 15 and can be at most 100"#,
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::Search {
                             bots: vec![models::IndexBot::default()],
@@ -454,6 +559,13 @@ Using -1 for ``gc_to`` will disable ``gc_to`` field"#,
                                 bots: vec![models::Tag::default()],
                                 servers: vec![models::Tag::default()]
                             },
+                            totals: models::SearchTotals::default(),
+                            facets: models::SearchFacets {
+                                tags: std::collections::HashMap::from([("utility".to_string(), 412)]),
+                                features: std::collections::HashMap::from([("slash_commands".to_string(), 39)]),
+                            },
+                            next_cursor: None,
+                            prev_cursor: None,
                         }),
                         auth_types: vec![]
                     },
@@ -472,6 +584,8 @@ Using -1 for ``gc_to`` will disable ``gc_to`` field"#,
                             certified: Vec::new(),
                             tags: tags.clone(),
                             features: features.clone(),
+                            next_cursor: None,
+                            prev_cursor: None,
                         }),
                         description: r#"
 Returns a mini-index which is basically a Index but with only ``tags``
@@ -581,6 +695,279 @@ by the site to login.
                 ] 
             },
 
+            models::RouteList {
+                file_name: "oauth.md",
+                routes: vec![
+                    models::Route {
+                        title: "OAuth2 Authorize Info",
+                        method: "GET",
+                        path: "/frostpaw/oauth2/authorize",
+                        description: r#"
+Validates a authorization request (RFC 6749 authorization code flow) and returns the client and 
+scopes the user is being asked to approve. This is used by the site to show the consent page.
+
+- ``response_type`` must be ``code``
+- ``redirect_uri`` must be on one of the redirect domains of the client and use ``https`` (``http`` is allowed for ``localhost``)
+- ``scope`` is a space separated list of user [token scopes](#tokenscope). A client with no scopes can only 
+introspect its own tokens to find out who the user is
+- PKCE (RFC 7636) is required. ``code_challenge_method`` must be ``S256``
+
+Errors are sent as ``{"error": "invalid_request", "error_description": "..."}``
+                "#,
+                        path_params: "",
+                        query_params: &body(QUERY_PARAMS, &models::Oauth2AuthorizeQuery {
+                            response_type: "code".to_string(),
+                            client_id: "client id here".to_string(),
+                            redirect_uri: "https://example.com/callback".to_string(),
+                            scope: "votes:write votes:read".to_string(),
+                            state: Some("random state here".to_string()),
+                            code_challenge: Some("base64url encoded SHA-256 of the code verifier".to_string()),
+                            code_challenge_method: Some("S256".to_string()),
+                        }),
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::Oauth2AuthorizeInfo {
+                            client: models::FrostpawClient::default(),
+                            scopes: vec![models::TokenScope::VotesWrite, models::TokenScope::VotesRead],
+                            redirect_uri: "https://example.com/callback".to_string(),
+                        }),
+                        auth_types: vec![]
+                    },
+
+                    models::Route {
+                        title: "OAuth2 Authorize",
+                        method: "POST",
+                        path: "/frostpaw/oauth2/authorize",
+                        description: r#"
+Approves (or denies) a authorization request on behalf of the user. Takes the same query parameters 
+as [OAuth2 Authorize Info](#oauth2-authorize-info).
+
+Returns the URL the user should be sent to. This has the authorization code as ``code`` (or 
+``error=access_denied`` if the user denied the request) along with ``state``. Codes can only be 
+used once and expire after 10 minutes.
+
+This needs a full access user token, Frostpaw clients cannot authorize other clients.
+                "#,
+                        path_params: "",
+                        query_params: &body(QUERY_PARAMS, &models::Oauth2AuthorizeQuery::default()),
+                        request_body: &body(REQ_BODY, &models::Oauth2Consent { approve: true }),
+                        response_body: &body(RESP_BODY, &models::Oauth2Redirect {
+                            redirect_uri: "https://example.com/callback?code=code&state=random+state+here".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "OAuth2 Token",
+                        method: "POST",
+                        path: "/frostpaw/oauth2/token",
+                        description: r#"
+Issues a access token. The body is sent as ``application/x-www-form-urlencoded``.
+
+- ``grant_type=authorization_code`` exchanges a code, ``code``, ``redirect_uri`` and ``code_verifier`` are required
+- ``grant_type=refresh_token`` exchanges a refresh token. Refresh tokens can only be used once, the 
+new refresh token must be used from then on
+
+Confidential clients (clients with a secret) must authenticate using HTTP Basic auth or ``client_secret``. 
+Only public clients (clients without a secret, which rely on PKCE alone) may omit it and just send ``client_id``.
+
+Access tokens last an hour and refresh tokens 30 days. Access tokens are sent in the ``Authorization`` 
+header (with or without ``Bearer ``) and can only be used for the scopes the user approved.
+                "#,
+                        path_params: "",
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::Oauth2TokenRequest {
+                            grant_type: "authorization_code".to_string(),
+                            code: Some("code here".to_string()),
+                            redirect_uri: Some("https://example.com/callback".to_string()),
+                            code_verifier: Some("code verifier here".to_string()),
+                            refresh_token: None,
+                            client_id: Some("client id here".to_string()),
+                            client_secret: None,
+                        }),
+                        response_body: &body(RESP_BODY, &models::Oauth2TokenResponse::default()),
+                        auth_types: vec![]
+                    },
+
+                    models::Route {
+                        title: "OAuth2 Introspect",
+                        method: "POST",
+                        path: "/frostpaw/oauth2/introspect",
+                        description: r#"
+Returns whether a access or refresh token is active along with its scopes, user (``sub``) and expiry 
+as per RFC 7662. The body is sent as ``application/x-www-form-urlencoded``.
+
+The client secret is required (using HTTP Basic auth or ``client_secret``) and clients can only 
+introspect their own tokens. Any other token is returned as ``{"active": false}``.
+                "#,
+                        path_params: "",
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::Oauth2TokenQuery {
+                            token: "Frostpaw.access token here".to_string(),
+                            token_type_hint: None,
+                            client_id: Some("client id here".to_string()),
+                            client_secret: Some("client secret here".to_string()),
+                        }),
+                        response_body: &body(RESP_BODY, &models::Oauth2Introspection {
+                            active: true,
+                            scope: Some("votes:write votes:read".to_string()),
+                            client_id: Some("client id here".to_string()),
+                            sub: Some("0".to_string()),
+                            token_type: Some("access_token".to_string()),
+                            exp: Some(0),
+                            iat: Some(0),
+                        }),
+                        auth_types: vec![]
+                    },
+
+                    models::Route {
+                        title: "OAuth2 Revoke",
+                        method: "POST",
+                        path: "/frostpaw/oauth2/revoke",
+                        description: r#"
+Revokes a access or refresh token (and the token issued with it) as per RFC 7009. The body is 
+sent as ``application/x-www-form-urlencoded``. Returns a empty 200 even if the token is unknown.
+                "#,
+                        path_params: "",
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::Oauth2TokenQuery {
+                            token: "refresh token here".to_string(),
+                            token_type_hint: Some("refresh_token".to_string()),
+                            client_id: Some("client id here".to_string()),
+                            client_secret: None,
+                        }),
+                        response_body: "",
+                        auth_types: vec![]
+                    },
+                ]
+            },
+
+            models::RouteList {
+                file_name: "dev-portal.md",
+                routes: vec![
+                    models::Route {
+                        title: "Get User Clients",
+                        method: "GET",
+                        path: "/users/{id}/clients",
+                        description: r#"
+Returns the Frostpaw clients owned by a user.
+
+All dev portal endpoints need the ``DevPortal`` experiment, which is only given out on request.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &vec![models::FrostpawClient::default()]),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Create Client",
+                        method: "POST",
+                        path: "/users/{id}/clients",
+                        description: r#"
+Creates a Frostpaw client. Users can have up to 10 clients.
+
+- ``domain`` and ``redirect_domains`` are bare hosts (with a optional port) such as ``example.com``. Users 
+can only be redirected to (and logged in from) the redirect domains of a client. ``domain`` is always a redirect domain
+- ``privacy_policy`` must be a ``https`` URL
+
+Returns the client ID and secret. The secret can only be seen again by rotating it.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::EditFrostpawClient {
+                            name: "My Client".to_string(),
+                            domain: "example.com".to_string(),
+                            privacy_policy: "https://example.com/privacy".to_string(),
+                            redirect_domains: vec!["example.com".to_string(), "localhost:3000".to_string()],
+                        }),
+                        response_body: &body(RESP_BODY, &models::FrostpawClientSecret {
+                            id: "client id here".to_string(),
+                            secret: "client secret here".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Edit Client",
+                        method: "PATCH",
+                        path: "/users/{user_id}/clients/{client_id}",
+                        description: r#"
+Edits a Frostpaw client. See [Create Client](#create-client) for what is allowed. 
+
+Verified clients must be verified again if their name or domain is changed.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::UserClientPath {
+                            user_id: 0,
+                            client_id: "client id here".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::EditFrostpawClient::default()),
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Delete Client",
+                        method: "DELETE",
+                        path: "/users/{user_id}/clients/{client_id}",
+                        description: r#"
+Deletes a Frostpaw client. All users are logged out of the client.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::UserClientPath {
+                            user_id: 0,
+                            client_id: "client id here".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Rotate Client Secret",
+                        method: "POST",
+                        path: "/users/{user_id}/clients/{client_id}/secret",
+                        description: r#"
+Issues a new client secret. The old secret stops working immediately, tokens already issued to the client keep working.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::UserClientPath {
+                            user_id: 0,
+                            client_id: "client id here".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::FrostpawClientSecret {
+                            id: "client id here".to_string(),
+                            secret: "new client secret here".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Request Client Verification",
+                        method: "POST",
+                        path: "/users/{user_id}/clients/{client_id}/verification",
+                        description: r#"
+Submits a Frostpaw client to staff for verification. ``verification_state`` of the client becomes ``Pending`` 
+until staff verify or deny it. Denied clients can be submitted again.
+
+``reason`` must be between 7 and 4000 characters.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::UserClientPath {
+                            user_id: 0,
+                            client_id: "client id here".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::ClientVerificationRequest {
+                            reason: "What the client does and why it should be verified".to_string(),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+                ]
+            },
+
             models::RouteList {
                 file_name: "security.md",
                 routes: vec![
@@ -590,14 +977,17 @@ by the site to login.
                         path: "/bots/{id}/token",
                         description: r#"
 'Deletes' a bot token and reissues a new bot token. Use this if your bots
-token ever gets leaked! Also used by the official client"#,
+token ever gets leaked! Also used by the official client
+
+The new token is sent as ``context``. Tokens are only stored hashed so 
+**this is the only time the token can be seen**"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
                         query_params: "",
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::APIResponse {
                             done: true,
                             reason: None,
-                            context: None,
+                            context: Some("new token".to_string()),
                         }),
                         auth_types: vec![models::RouteAuthType::Bot],
                     },
@@ -608,14 +998,17 @@ token ever gets leaked! Also used by the official client"#,
                         path: "/users/{id}/token",
                         description: r#"
 'Deletes' a user token and reissues a new user token. Use this if your bots
-token ever gets leaked! Also used by the official client"#,
+token ever gets leaked! Also used by the official client
+
+This also logs you out everywhere (including custom clients). The new token is sent 
+as ``context``, **this is the only time the token can be seen**"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
                         query_params: "",
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::APIResponse {
                             done: true,
                             reason: None,
-                            context: None,
+                            context: Some("new token".to_string()),
                         }),
                         auth_types: vec![models::RouteAuthType::User],
                     },
@@ -626,7 +1019,9 @@ token ever gets leaked! Also used by the official client"#,
                         path: "/servers/{id}/token",
                         description: r#"
 'Deletes' a server token and reissues a new server token. Use this if your server
-token ever gets leaked."#,
+token ever gets leaked.
+
+The new token is sent as ``context``, **this is the only time the token can be seen**"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { 
                             id: 0 
                         }),
@@ -635,7 +1030,7 @@ token ever gets leaked."#,
                         response_body: &body(RESP_BODY, &models::APIResponse {
                             done: true,
                             reason: None,
-                            context: None,
+                            context: Some("new token".to_string()),
                         }),
                         auth_types: vec![models::RouteAuthType::Server],
                     },
@@ -662,113 +1057,285 @@ token ever gets leaked.
                         auth_types: vec![models::RouteAuthType::User],
                     },
 
-                ]
-            },
-
-            models::RouteList {
-                file_name: "bot-actions.md",
-                routes: vec![
-                    models::Route {
-                        title: "Post Stats",
-                        method: "POST",
-                        path: "/bots/{id}/stats",
-                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
-                            id: 0,
-                        }),
-                        query_params: "",
-                        request_body: &body(REQ_BODY, &models::BotStats {
-                            guild_count: 3939,
-                            shard_count: Some(48484),
-                            shards: Some(vec![149, 22020]),
-                            user_count: Some(39393),
-                        }),
-                        response_body: &body(RESP_BODY, &models::APIResponse::default()),
-                        description: r#"
-Post stats to the list
-
-Example:
-```py
-import requests
-
-# On dpy, guild_count is usually the below
-guild_count = len(client.guilds)
-
-# If you are using sharding
-shard_count = len(client.shards)
-shards = client.shards.keys()
-
-# Optional: User count (this is not accurate for larger bots)
-user_count = len(client.users) 
-
-def post_stats(bot_id: int, guild_count: int):
-    res = requests.post(f"{api_url}/bots/{bot_id}/stats", json={"guild_count": guild_count})
-    json = res.json()
-    if res.status != 200:
-        # Handle an error in the api
-        ...
-    return json
-```"#,
-                        auth_types: vec![models::RouteAuthType::Bot]
-                    },
-
                     models::Route {
-                        title: "Get Bot",
+                        title: "Get User Sessions",
                         method: "GET",
-                        path: "/bots/{id}",
-                        path_params: &body(PATH_PARAMS, &models::FetchBotPath::default()),
-                        query_params: "",
+                        path: "/users/{id}/sessions",
                         description: r#"
-Fetches bot information given a bot ID. If not found, 404 will be returned. 
-
-This endpoint handles both bot IDs and client IDs
-
-- ``long_description/css`` is sanitized with ammonia by default, use `long_description_raw` if you want the unsanitized version
-- All responses are cached for a short period of time. There is *no* way to opt out at this time
-- Some fields have been renamed or removed from API v2 (such as ``promos`` which may be readded at a later date)
+Returns the active sessions of a user, newest first. A session is a login on the site (``Login``), 
+a token of a Frostpaw client (``Client``) or a refresh token from the older custom client login 
+(``ClientConnection``).
 
-This API returns some empty fields such as ``webhook``, ``webhook_secret``, ``api_token`` and more. 
-This is to allow reuse of the Bot struct in Get Bot Settings which *does* contain this sensitive data. 
+``origin`` is only the network the session was created from (the /24 for IPv4 and /48 for IPv6) 
+and ``current`` is set on the session used to make the request.
 
-**Set the Frostpaw header if you are a custom client. Send Frostpaw-Invite header on invites**"#,
+Session endpoints need a full access user token and cannot be used by Frostpaw clients.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
                         request_body: "",
-                        response_body: &body(RESP_BODY, &models::Bot::default()),
-                        auth_types: vec![],
+                        response_body: &body(RESP_BODY, &vec![models::Session::default()]),
+                        auth_types: vec![models::RouteAuthType::User],
                     },
 
                     models::Route {
-                        title: "Get Bot Settings",
-                        method: "GET",
-                        path: "/users/{user_id}/bots/{bot_id}/settings",
-                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                        title: "Revoke User Session",
+                        method: "DELETE",
+                        path: "/users/{user_id}/sessions/{session_id}",
+                        description: r#"
+Revokes a session of a user. Revoking a ``Client`` session revokes both its access and refresh token.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::UserSessionPath {
                             user_id: 0,
-                            bot_id: 0,
+                            session_id: "session id".to_string(),
                         }),
                         query_params: "",
                         request_body: "",
-                        response_body: &body(RESP_BODY, &models::BotSettings {
-                            bot: models::Bot::default(),
-                            context: models::BotSettingsContext { tags, features },
-                        }),
-                        description: r#"
-Returns the bot settings.
-
-The ``bot`` here is equivalent to a Get Bot response with the following
-differences:
-
-- Sensitive fields (see examples) like ``webhook``, ``api_token``, 
-``webhook_secret`` and others are filled out here
-- This API only allows bot owners (not even staff) to use it, otherwise it will 400!
-
-Staff members *must* instead use Lynx."#,
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
                         auth_types: vec![models::RouteAuthType::User],
                     },
 
                     models::Route {
-                        title: "Random Bot",
-                        method: "GET",
-                        path: "/random-bot",
-                        path_params: "",
-                        query_params: "",
+                        title: "Logout Everywhere",
+                        method: "DELETE",
+                        path: "/users/{id}/sessions",
+                        description: r#"
+Revokes every session of a user, including the one used to make the request. Unlike 
+[New User Token](#new-user-token), the user token and scoped API tokens keep working.
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Get User API Tokens",
+                        method: "GET",
+                        path: "/users/{id}/tokens",
+                        description: r#"
+Lists the scoped API tokens of a user. Scoped tokens start with ``fls_`` and can only be used 
+for the endpoints their [scopes](./enums#tokenscope) allow. Your user token (``api_token``) 
+is still a full access token
+
+The token itself is never returned here, only when the token is created"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &vec![models::ApiToken::default()]),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Create User API Token",
+                        method: "POST",
+                        path: "/users/{id}/tokens",
+                        description: r#"
+Creates a scoped API token for a user. Only ``bot:edit``, ``votes:write`` and ``votes:read`` can be 
+given to user tokens. ``expires_at`` is optional. A user can have up to 25 scoped tokens.
+
+**Make sure to save the token, it is only shown once.** This needs a full access token"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::CreateApiToken {
+                            name: "Vote button".to_string(),
+                            scopes: vec![models::TokenScope::VotesWrite],
+                            expires_at: None,
+                        }),
+                        response_body: &body(RESP_BODY, &models::NewApiToken {
+                            id: "token id".to_string(),
+                            token: "fls_...".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Delete User API Token",
+                        method: "DELETE",
+                        path: "/users/{user_id}/tokens/{token_id}",
+                        description: "Revokes a scoped API token of a user. This needs a full access token",
+                        path_params: &body(PATH_PARAMS, &models::UserTokenPath {
+                            user_id: 0,
+                            token_id: "token id".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Get Bot API Tokens",
+                        method: "GET",
+                        path: "/users/{user_id}/bots/{bot_id}/tokens",
+                        description: r#"
+Lists the scoped API tokens of a bot. The user must be a owner of the bot. The bot token 
+(``api_token``) is still a full access token"#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &vec![models::ApiToken::default()]),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Create Bot API Token",
+                        method: "POST",
+                        path: "/users/{user_id}/bots/{bot_id}/tokens",
+                        description: r#"
+Creates a scoped API token for a bot. Only ``stats:write``, ``commands:write`` and ``votes:read`` 
+can be given to bot tokens. The user must be a owner of the bot.
+
+**Make sure to save the token, it is only shown once.**"#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::CreateApiToken::default()),
+                        response_body: &body(RESP_BODY, &models::NewApiToken {
+                            id: "token id".to_string(),
+                            token: "fls_...".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Delete Bot API Token",
+                        method: "DELETE",
+                        path: "/users/{user_id}/bots/{bot_id}/tokens/{token_id}",
+                        description: "Revokes a scoped API token of a bot. The user must be a owner of the bot",
+                        path_params: &body(PATH_PARAMS, &models::UserBotTokenPath {
+                            user_id: 0,
+                            bot_id: 0,
+                            token_id: "token id".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                ]
+            },
+
+            models::RouteList {
+                file_name: "bot-actions.md",
+                routes: vec![
+                    models::Route {
+                        title: "Post Stats",
+                        method: "POST",
+                        path: "/bots/{id}/stats",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
+                            id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::BotStats {
+                            guild_count: 3939,
+                            shard_count: Some(48484),
+                            shards: Some(vec![149, 22020]),
+                            user_count: Some(39393),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse::default()),
+                        description: r#"
+Post stats to the list
+
+Example:
+```py
+import requests
+
+# On dpy, guild_count is usually the below
+guild_count = len(client.guilds)
+
+# If you are using sharding
+shard_count = len(client.shards)
+shards = client.shards.keys()
+
+# Optional: User count (this is not accurate for larger bots)
+user_count = len(client.users) 
+
+def post_stats(bot_id: int, guild_count: int):
+    res = requests.post(f"{api_url}/bots/{bot_id}/stats", json={"guild_count": guild_count})
+    json = res.json()
+    if res.status != 200:
+        # Handle an error in the api
+        ...
+    return json
+```"#,
+                        auth_types: vec![models::RouteAuthType::Bot]
+                    },
+
+                    models::Route {
+                        title: "Get Bot",
+                        method: "GET",
+                        path: "/bots/{id}",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath::default()),
+                        query_params: "",
+                        description: r#"
+Fetches bot information given a bot ID. If not found, 404 will be returned. 
+
+This endpoint handles both bot IDs and client IDs
+
+- ``long_description/css`` is sanitized with ammonia by default, use `long_description_raw` if you want the unsanitized version
+- All responses are cached for a short period of time. There is *no* way to opt out at this time
+- Some fields have been renamed or removed from API v2 (such as ``promos`` which may be readded at a later date)
+
+This API returns some empty fields such as ``webhook``, ``webhook_secret``, ``api_token`` and more. 
+This is to allow reuse of the Bot struct in Get Bot Settings which *does* contain this sensitive data. 
+
+**Set the Frostpaw header if you are a custom client. Send Frostpaw-Invite header on invites**"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::Bot::default()),
+                        auth_types: vec![],
+                    },
+
+                    models::Route {
+                        title: "Get Bot Settings",
+                        method: "GET",
+                        path: "/users/{user_id}/bots/{bot_id}/settings",
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::BotSettings {
+                            bot: models::Bot::default(),
+                            context: models::BotSettingsContext { tags, features },
+                        }),
+                        description: r#"
+Returns the bot settings.
+
+The ``bot`` here is equivalent to a Get Bot response with the following
+differences:
+
+- Sensitive fields (see examples) like ``webhook``, ``webhook_secret`` 
+and others are filled out here. ``api_token`` is always null as bot tokens are only 
+stored hashed, use [New Bot Token](./security#new-bot-token) to get a new one
+- This API only allows bot owners (not even staff) to use it, otherwise it will 400!
+
+Staff members *must* instead use Lynx."#,
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Random Bot",
+                        method: "GET",
+                        path: "/random-bot",
+                        path_params: "",
+                        query_params: "",
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::IndexBot::default()),
                         description: r#"
@@ -1009,7 +1576,7 @@ def random_server():
                             bot_id: 0,
                         }),
                         query_params: &body(QUERY_PARAMS, &models::VoteBotQuery { test: true }),
-                        description: "This endpoint creates a vote for a bot which can only be done *once* every 8 hours.",
+                        description: "This endpoint creates a vote for a bot which can only be done *once* every 8 hours. Suspicious votes are held for staff review and only counted once confirmed (``context`` will say so).",
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::APIResponse {
                             done: false,
@@ -1030,7 +1597,8 @@ def random_server():
                         query_params: &body(QUERY_PARAMS, &models::VoteBotQuery { test: true }),
                         description: r#"
 This endpoint creates a vote for a server which can only be done *once* every 8 hours
-and is independent from a bot vote.
+and is independent from a bot vote. Suspicious votes are held for staff review and only counted once 
+confirmed (``context`` will say so).
                     "#,
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::APIResponse {
@@ -1049,7 +1617,10 @@ and is independent from a bot vote.
                             user_id: 0,
                             bot_id: 0,
                         }),
-                        query_params: "",
+                        query_params: &body(QUERY_PARAMS, &models::CursorQuery {
+                            cursor: None,
+                            limit: Some(100),
+                        }),
                         description: r#"
 Endpoint to check amount of votes a user has.
 
@@ -1063,6 +1634,8 @@ Endpoint to check amount of votes a user has.
 because the last thing people want to scrape are Fates List user votes anyways. **You should not rely on
 this however, it is prone to change *anytime* in the future and may return bogus results for privacy purposes**.
 - ``vts`` has been renamed to ``timestamps``
+- ``timestamps`` is returned newest first and is paginated (``limit`` defaults to 100). Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to walk through it
+- weight | How many votes a vote made right now counts as (during weekends and events). This is also sent as ``multiplier`` in vote webhooks
 
 **This endpoint will return bogus data if "Hide votes to other users" is enabled**"#,
                         request_body: "",
@@ -1072,6 +1645,9 @@ this however, it is prone to change *anytime* in the future and may return bogus
                             expiry: 101,
                             timestamps: vec![chrono::DateTime::<chrono::Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(0, 0), chrono::Utc)],
                             vote_right_now: false,
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                            weight: 2,
                         }),
                         auth_types: vec![]
                     },
@@ -1084,7 +1660,10 @@ this however, it is prone to change *anytime* in the future and may return bogus
                             user_id: 0,
                             server_id: 0,
                         }),
-                        query_params: "",
+                        query_params: &body(QUERY_PARAMS, &models::CursorQuery {
+                            cursor: None,
+                            limit: Some(100),
+                        }),
                         description: r#"
 Endpoint to check amount of votes a user has.
 
@@ -1098,6 +1677,8 @@ Endpoint to check amount of votes a user has.
 because the last thing people want to scrape are Fates List user votes anyways. **You should not rely on
 this however, it is prone to change *anytime* in the future and may return bogus results for privacy purposes**.
 - ``vts`` has been renamed to ``timestamps``
+- ``timestamps`` is returned newest first and is paginated (``limit`` defaults to 100). Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to walk through it
+- weight | How many votes a vote made right now counts as (during weekends and events). This is also sent as ``multiplier`` in vote webhooks
 
 **This endpoint will return bogus data if "Hide votes to other users" is enabled**"#,
                         request_body: "",
@@ -1107,80 +1688,387 @@ this however, it is prone to change *anytime* in the future and may return bogus
                             expiry: 101,
                             timestamps: vec![chrono::DateTime::<chrono::Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(0, 0), chrono::Utc)],
                             vote_right_now: false,
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                            weight: 2,
                         }),
                         auth_types: vec![]
-                    }
-                ]
-            },
-
-            models::RouteList {
-                file_name: "appeals.md",
-                routes: vec![
+                    },
 
                     models::Route {
-                        title: "Create Bot Appeal",
-                        method: "POST",
-                        path: "/users/{user_id}/bots/{bot_id}/appeal",
-                        description: r#"
-Creates a appeal for a bot.
-
-``request_type`` is a [AppealType](./enums#appealtype)
-                "#,
-                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
-                            user_id: 0,
-                            bot_id: 0,
+                        title: "Get Flagged Votes",
+                        method: "GET",
+                        path: "/users/{id}/staff/votes",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
+                            id: 0,
                         }),
-                        query_params: "",
-                        request_body: &body(REQ_BODY, &models::Appeal {
-                            request_type: models::AppealType::Appeal,
-                            appeal: "This bot deserves to be unbanned because...".to_string(),
+                        query_params: &body(QUERY_PARAMS, &models::FlaggedVoteQuery {
+                            state: Some(models::VoteRiskState::Held),
+                            target_id: None,
+                            cursor: Some("next_cursor or prev_cursor from a previous response".to_string()),
+                            limit: Some(100),
                         }),
-                        response_body: &body(RESP_BODY, &models::APIResponse {
-                            done: true,
-                            reason: None,
-                            context: None,
+                        description: r#"
+Returns votes that were given a risk score, newest first. **Needs the ``vote.review`` [Capability](./enums#capability)**
+
+Every vote is scored using the request it was made from (its network and a fingerprint of the browser)
+and the age of the account. Votes with a high enough score are held (and not counted) until staff
+review them.
+
+- ``state`` is a [VoteRiskState](./enums#voteriskstate) and defaults to ``Held``
+- ``target_id`` only returns votes for that bot or server
+- ``limit`` defaults to 100. Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to paginate
+- ``reasons`` is why the vote was scored as it was (one of ``NewAccount``, ``NetworkBurst``, ``VoteRing`` 
+or ``FingerprintBurst``)
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::FlaggedVoteList {
+                            votes: vec![models::FlaggedVote::default()],
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
                         }),
-                        auth_types: vec![models::RouteAuthType::User],
+                        auth_types: vec![models::RouteAuthType::User]
                     },
 
                     models::Route {
-                        title: "Create Server Appeal",
-                        method: "POST",
-                        path: "/users/{user_id}/servers/{server_id}/appeal",
+                        title: "Review Flagged Vote",
+                        method: "PATCH",
+                        path: "/users/{user_id}/staff/votes/{vote_id}",
+                        path_params: &body(PATH_PARAMS, &models::FlaggedVotePath::default()),
+                        query_params: "",
                         description: r#"
-Creates a appeal for a server.
+Confirms or voids a flagged vote. **Needs the ``vote.review`` [Capability](./enums#capability)**
 
-**Currently only `report` is supported by this endpoint**
-
-``request_type`` is a [AppealType](./enums#appealtype)
-                "#,
-                        path_params: &body(PATH_PARAMS, &models::GetUserServerPath {
-                            user_id: 0,
-                            server_id: 0,
-                        }),
-                        query_params: "",
-                        request_body: &body(REQ_BODY, &models::Appeal {
-                            request_type: models::AppealType::Appeal,
-                            appeal: "This server deserves to be unbanned because...".to_string(),
-                        }),
-                        response_body: &body(RESP_BODY, &models::APIResponse {
-                            done: true,
-                            reason: None,
-                            context: None,
+- Confirming a held vote counts it (sending the vote event and webhook as usual)
+- Voiding a counted vote removes it from the votes of the bot or server
+- A vote can only be reviewed once
+"#,
+                        request_body: &body(REQ_BODY, &models::VoteDecision {
+                            confirm: true,
                         }),
-                        auth_types: vec![models::RouteAuthType::User],
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
                     }
                 ]
             },
 
             models::RouteList {
-                file_name: "packs.md",
+                file_name: "staff.md",
                 routes: vec![
+
                     models::Route {
-                        title: "Add Pack",
-                        method: "POST",
-                        path: "/users/{id}/packs",
-                        description: r#"
+                        title: "Get Queue",
+                        method: "GET",
+                        path: "/users/{id}/staff/queue",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
+                            id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::QueueQuery {
+                            target_type: Some(models::TargetType::Bot),
+                            state: Some(models::State::Pending),
+                            cursor: None,
+                            limit: Some(25),
+                        }),
+                        description: r#"
+Returns the bots or servers in the queue, oldest first. **Needs the ``queue.view`` [Capability](./enums#capability)**
+
+- ``target_type`` is a [TargetType](./enums#targettype) and defaults to ``Bot``
+- ``state`` is a [State](./enums#state) and defaults to ``Pending``. Use ``UnderReview`` to see claimed bots
+- ``verifier`` is the staff member who claimed (or last approved/denied) the bot or server
+- ``limit`` defaults to 25. Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to walk through the queue
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::QueueList {
+                            queue: vec![models::QueueEntry::default()],
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Staff Action",
+                        method: "PATCH",
+                        path: "/users/{user_id}/staff/queue/{target_id}",
+                        path_params: &body(PATH_PARAMS, &models::StaffTargetPath::default()),
+                        query_params: "",
+                        description: r#"
+Moderates a bot or server in the queue. **Needs the ``queue.view`` [Capability](./enums#capability)** along
+with the capability of the action
+
+``action`` is a [UserBotAction](./enums#userbotaction), only the following are supported:
+
+| Action | From | To | Capability |
+| :--- | :--- | :--- | :--- |
+| Claim | Pending | UnderReview | queue.claim |
+| Unclaim | UnderReview | Pending | queue.claim |
+| Approve | UnderReview | Approved | queue.approve |
+| Deny | UnderReview | Denied | queue.approve |
+| Ban | Any but Banned | Banned | bot.ban |
+| Certify | Approved | Certified | bot.certify |
+| Requeue | Denied or Banned | Pending | queue.approve |
+
+- ``reason`` is required when denying or banning
+- Doing a action on a bot or server in any other state returns a ``409 Conflict``
+- Every action is logged, sent as a event (``BotClaim``, ``BotUnclaim``, ``BotApprove``, ``BotDeny``, ``BotBan``, 
+``BotCertify`` or ``BotRequeue``, servers use the same events) and posted to the bot logs channel
+"#,
+                        request_body: &body(REQ_BODY, &models::StaffAction {
+                            target_type: models::TargetType::Bot,
+                            action: models::UserBotAction::Deny,
+                            reason: Some("Bot is offline".to_string()),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Get Review Queue",
+                        method: "GET",
+                        path: "/users/{id}/staff/reviews",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
+                            id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::ReviewQueueQuery {
+                            hidden: Some(false),
+                            cursor: None,
+                            limit: Some(25),
+                        }),
+                        description: r#"
+Returns reviews (and replies) that were flagged or have open reports, oldest first. **Needs the ``review.moderate``
+[Capability](./enums#capability)**
+
+- Reviews flagged by the spam filter are hidden until a staff member restores them, ``filter_reason`` says why
+they were flagged
+- ``hidden`` only returns hidden (``true``) or visible (``false``) reviews, omit it to get both
+- ``reports`` only has open reports, ``reason`` is a [ReviewReportReason](./enums#reviewreportreason)
+- ``limit`` defaults to 25. Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to walk through the queue
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::ReviewQueueList {
+                            reviews: vec![models::ReviewQueueEntry::default()],
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Moderate Review",
+                        method: "PATCH",
+                        path: "/users/{user_id}/staff/reviews/{rid}",
+                        path_params: &body(PATH_PARAMS, &models::StaffReviewPath {
+                            user_id: 0,
+                            rid: uuid::Uuid::new_v4(),
+                        }),
+                        query_params: "",
+                        description: r#"
+Hides, restores or deletes a review. **Needs the ``review.moderate`` [Capability](./enums#capability)**, deleting
+also needs ``review.delete_any``
+
+``action`` is a [ReviewModAction](./enums#reviewmodaction):
+
+- ``Hide`` hides the review from everyone but its author and staff
+- ``Restore`` unhides and unflags the review
+- ``Delete`` deletes the review along with its replies and reports
+
+Hiding and restoring resolve all open reports of the review, taking it out of the queue. Every action is
+added to the audit log of the bot or server (with ``reason`` if set)
+"#,
+                        request_body: &body(REQ_BODY, &models::ReviewModeration {
+                            action: models::ReviewModAction::Hide,
+                            reason: Some("Harassment".to_string()),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User]
+                    }
+                ]
+            },
+
+            models::RouteList {
+                file_name: "audit-logs.md",
+                routes: vec![
+
+                    models::Route {
+                        title: "Get Bot Audit Logs",
+                        method: "GET",
+                        path: "/users/{user_id}/bots/{bot_id}/audit-logs",
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::AuditLogQuery {
+                            action: Some(models::AuditAction::EditBot),
+                            actor: None,
+                            from: Some(0),
+                            to: None,
+                            cursor: None,
+                            limit: Some(50),
+                            format: None,
+                        }),
+                        description: r#"
+Returns who did what to a bot, newest first. Only owners of the bot (and staff with the ``audit.view``
+[Capability](./enums#capability)) can see this
+
+- ``action`` is a [AuditAction](./enums#auditaction), only entries with this action are returned
+- ``actor`` only returns what this user did. Command changes and token resets done using the bots own token have the bot as the actor
+- ``from`` (inclusive) and ``to`` (exclusive) are unix timestamps
+- ``limit`` defaults to 50. Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to walk through the log
+- Set ``format`` to ``json`` or ``csv`` to download every matching entry (up to 10000) instead, ``cursor`` and ``limit`` are 
+ignored when exporting
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::AuditLogList {
+                            logs: vec![models::AuditLog::default()],
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Get Server Audit Logs",
+                        method: "GET",
+                        path: "/users/{user_id}/servers/{server_id}/audit-logs",
+                        path_params: &body(PATH_PARAMS, &models::GetUserServerPath {
+                            user_id: 0,
+                            server_id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::AuditLogQuery {
+                            action: Some(models::AuditAction::EditServer),
+                            actor: None,
+                            from: None,
+                            to: None,
+                            cursor: None,
+                            limit: Some(50),
+                            format: Some(models::ExportFormat::Csv),
+                        }),
+                        description: r#"
+Returns who did what to a server, newest first. Only the owner of the server (and staff with the ``audit.view``
+[Capability](./enums#capability)) can see this
+
+This includes edits made using the server bot (as ``EditServer`` with ``field: value`` as the context). See 
+[Get Bot Audit Logs](#get-bot-audit-logs) for the query parameters
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::AuditLogList {
+                            logs: vec![models::AuditLog {
+                                target_type: models::AuditTargetType::Server,
+                                action: models::AuditAction::EditServer,
+                                context: Some("description: My server".to_string()),
+                                ..models::AuditLog::default()
+                            }],
+                            next_cursor: None,
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    },
+
+                    models::Route {
+                        title: "Get User Audit Logs",
+                        method: "GET",
+                        path: "/users/{id}/audit-logs",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath {
+                            id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::AuditLogQuery {
+                            action: Some(models::AuditAction::AddPack),
+                            actor: None,
+                            from: None,
+                            to: None,
+                            cursor: None,
+                            limit: Some(50),
+                            format: None,
+                        }),
+                        description: r#"
+Returns what was done to your account (token resets, API tokens and pack changes), newest first.
+
+See [Get Bot Audit Logs](#get-bot-audit-logs) for the query parameters
+"#,
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::AuditLogList {
+                            logs: vec![models::AuditLog {
+                                target_type: models::AuditTargetType::User,
+                                action: models::AuditAction::AddPack,
+                                context: Some("My pack".to_string()),
+                                ..models::AuditLog::default()
+                            }],
+                            next_cursor: None,
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User]
+                    }
+                ]
+            },
+
+            models::RouteList {
+                file_name: "appeals.md",
+                routes: vec![
+
+                    models::Route {
+                        title: "Create Bot Appeal",
+                        method: "POST",
+                        path: "/users/{user_id}/bots/{bot_id}/appeal",
+                        description: r#"
+Creates a appeal for a bot.
+
+``request_type`` is a [AppealType](./enums#appealtype)
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::Appeal {
+                            request_type: models::AppealType::Appeal,
+                            appeal: "This bot deserves to be unbanned because...".to_string(),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Create Server Appeal",
+                        method: "POST",
+                        path: "/users/{user_id}/servers/{server_id}/appeal",
+                        description: r#"
+Creates a appeal for a server.
+
+**Currently only `report` is supported by this endpoint**
+
+``request_type`` is a [AppealType](./enums#appealtype)
+                "#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserServerPath {
+                            user_id: 0,
+                            server_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::Appeal {
+                            request_type: models::AppealType::Appeal,
+                            appeal: "This server deserves to be unbanned because...".to_string(),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    }
+                ]
+            },
+
+            models::RouteList {
+                file_name: "packs.md",
+                routes: vec![
+                    models::Route {
+                        title: "Add Pack",
+                        method: "POST",
+                        path: "/users/{id}/packs",
+                        description: r#"
 Creates a bot pack. 
 
 - Set ``id`` to empty string, 
@@ -1279,11 +2167,17 @@ May be used by our partners to get user information."#,
                         description: r#"
 Gets the permissions of a user from Baypaw (our microservices handling user fetching and permissions)
 
-Internally is used by client for extra owner rendering etc."#,
+Internally is used by client for extra owner rendering etc.
+
+``capabilities`` are the [Capabilities](./enums#capability) the users perm level grants them. Returns a
+``408 Request Timeout`` if baypaw is down"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
                         query_params: "",
                         request_body: "",
-                        response_body: &body(RESP_BODY, &bristlefrost::models::StaffRole::default()),
+                        response_body: &body(RESP_BODY, &models::StaffPerms {
+                            role: bristlefrost::models::StaffRole::default(),
+                            capabilities: vec![models::Capability::QueueView, models::Capability::QueueClaim, models::Capability::QueueApprove],
+                        }),
                         auth_types: vec![],
                     },
 
@@ -1366,15 +2260,31 @@ path.
 ``user_id`` is optional for this endpoint but specifying it will provide ``user_reviews`` if
 the user has made a review. This will tell you the users review for the entity.
 
+Hidden reviews (and replies) are not returned or counted in ``stats``. The users own review is
+still returned as ``user_review`` even if it is hidden.
+
 ``per_page`` (amount of root/non-reply reviews per page) is currently set to 9. 
 This may change in the future and is given by ``per_page`` key.
 
-``from`` contains the index/count of the first review of the page."#,
+``from`` contains the index/count of the first review of the page.
+
+Reviews are returned newest first. ``next_cursor`` and ``prev_cursor`` can be passed back as 
+``cursor`` to page through reviews reliably, in which case ``page`` is ignored.
+
+Only the first few levels of replies (2 by default) and the first few replies of each review 
+(5 by default) are returned. ``more_replies`` is set on reviews with replies that were not returned,
+use [Get Replies](#get-replies) (with ``replies_cursor`` if set) to load them. ``reply_order`` is a 
+[ReplyOrder](./enums#replyorder) and defaults to ``oldest``
+
+The official response of the owner to a review is returned as ``response`` (with ``owner_response``
+set) and is not included in ``replies``"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
                         query_params: &body(QUERY_PARAMS, &models::ReviewQuery {
                             page: Some(1),
                             user_id: Some(0),
                             target_type: models::TargetType::Bot,
+                            cursor: None,
+                            reply_order: Some(models::ReplyOrder::Oldest),
                         }),
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::ParsedReview {
@@ -1382,6 +2292,8 @@ This may change in the future and is given by ``per_page`` key.
                             user_review: Some(models::Review::default()),
                             per_page: 9,
                             from: 0,
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
                             stats: models::ReviewStats {
                                 total: 78,
                                 average_stars: bigdecimal::BigDecimal::from_f32(8.8).unwrap(),
@@ -1410,12 +2322,25 @@ The ``parent_id`` is optional and is used to create a reply to a review.
 ``review`` is a [Review](https://fates-lynx.select-list.xyz/docs/endpoints/enums#review)
 
 ``user_id`` is *required* for this endpoint and must be the user making the review. It must
-also match the user token sent in the ``Authorization`` header"#,
+also match the user token sent in the ``Authorization`` header
+
+To respond to a review as the owner of the bot or server, set ``owner_response`` with ``parent_id`` 
+set to the review. A review can only have one owner response and only top level reviews can be 
+responded to. Replies can be nested 5 deep by default (``ThreadTooDeep``)
+
+The owner of the bot or server gets a ``ReviewAdd`` event (unless the review was flagged)
+
+Reviews go through a spam filter. Reviews that look like spam are rejected with ``ReviewRejected``,
+suspicious ones are flagged and hidden (only their author and staff can see them) until staff look
+at them. ``flagged`` and ``hidden`` in the request body are ignored. Users can post or edit 5 reviews 
+an hour by default"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
                         query_params: &body(QUERY_PARAMS, &models::ReviewQuery {
                             page: None,
                             user_id: Some(0),
                             target_type: models::TargetType::Bot,
+                            cursor: None,
+                            reply_order: None,
                         }),
                         request_body: &body(REQ_BODY, &models::Review {
                             parent_id: Some(uuid::Uuid::new_v4()),
@@ -1451,6 +2376,8 @@ The id of the review must be specified as ``id`` in the request body which accep
 object. The ``user_id`` specified must *own*/have created the review being editted. Staff should
 edit reviews using Lynx when required.
 
+Edits go through the same spam filter (and rate limit) as new reviews. A flagged edit hides the review
+
 ``user_id`` is *required* for this endpoint and must be the user making the review. It must
 also match the user token sent in the ``Authorization`` header"#,
                         path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
@@ -1458,6 +2385,8 @@ also match the user token sent in the ``Authorization`` header"#,
                             page: None,
                             user_id: Some(0),
                             target_type: models::TargetType::Bot,
+                            cursor: None,
+                            reply_order: None,
                         }),
                         request_body: &body(REQ_BODY, &models::Review {
                             id: Some(uuid::Uuid::new_v4()),
@@ -1480,8 +2409,9 @@ Deletes a review
 
 ``rid`` must be a valid uuid.
 
-``user_id`` is *required* for this endpoint and must be the user making the review. It must
-also match the user token sent in the ``Authorization`` header. ``page`` is currently ignored
+``user_id`` is *required* for this endpoint and must be the user making the review (or staff with the
+``review.delete_any`` [Capability](./enums#capability)). It must also match the user token sent in the 
+``Authorization`` header. ``page`` is currently ignored
 
 A reviewable entity is currently only a bot or a server. Profile reviews are a possibility
 in the future.
@@ -1497,6 +2427,8 @@ set this anyways so you might as well set it correctly."#,
                             page: None,
                             user_id: Some(0),
                             target_type: models::TargetType::Bot,
+                            cursor: None,
+                            reply_order: None,
                         }),
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::APIResponse {
@@ -1542,6 +2474,72 @@ in the future.
                             context: None,
                         }),
                         auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Get Replies",
+                        method: "GET",
+                        path: "/reviews/{rid}/replies",
+                        description: r#"
+Gets the replies of a review (or reply), see ``more_replies`` in [Get Reviews](#get-reviews)
+
+``rid`` must be a valid uuid.
+
+- ``order`` is a [ReplyOrder](./enums#replyorder) and defaults to ``oldest``
+- Replies are returned with the same amount of levels of their own replies as ``Get Reviews``
+- ``limit`` defaults to 5. Pass ``next_cursor`` (or ``replies_cursor`` of the review) as ``cursor`` 
+to get the next page
+- The owner response of a review is never included, it is in ``response`` of the review"#,
+                        path_params: &body(PATH_PARAMS, &models::ReviewDeletePath {
+                            rid: uuid::Uuid::new_v4().to_hyphenated().to_string(),
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::ReplyQuery {
+                            order: Some(models::ReplyOrder::Oldest),
+                            cursor: None,
+                            limit: Some(5),
+                        }),
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::ReplyList {
+                            replies: vec![models::Review::default()],
+                            next_cursor: Some("opaque cursor".to_string()),
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![],
+                    },
+
+                    models::Route {
+                        title: "Report Review",
+                        method: "POST",
+                        path: "/reviews/{rid}/reports",
+                        description: r#"
+Reports a review to staff
+
+``rid`` must be a valid uuid.
+
+``user_id`` is *required* for this endpoint and must be the user reporting the review. It must
+also match the user token sent in the ``Authorization`` header. Like voting, ``user_id`` is in the 
+request body
+
+``reason`` is a [ReviewReportReason](./enums#reviewreportreason). ``context`` (at most 1000 characters) 
+is required when the reason is ``Other``
+
+A user can only report a review once and cannot report their own review. Reports are rate limited 
+per user (10 an hour by default)"#,
+                        path_params: &body(PATH_PARAMS, &models::ReviewDeletePath {
+                            rid: uuid::Uuid::new_v4().to_hyphenated().to_string(),
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::ReviewReport {
+                            user_id: "user id here".to_string(),
+                            reason: models::ReviewReportReason::Spam,
+                            context: Some("Same review was posted on 20 bots".to_string()),
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
                     }
                 ]
             },
@@ -1557,10 +2555,15 @@ in the future.
 Returns the bot list stats. This currently returns the full list of all bots
 as a vector/list of IndexBot structs.
 
-As a client, it is your responsibility, to parse this. Pagination may be added
-if the list grows and then requires it."#,
+As a client, it is your responsibility, to parse this. 
+
+If ``cursor`` or ``limit`` (default 100) is set, ``bots`` is paginated and ``next_cursor`` 
+and ``prev_cursor`` can be passed back as ``cursor``"#,
                         path_params: "",
-                        query_params: "",
+                        query_params: &body(QUERY_PARAMS, &models::CursorQuery {
+                            cursor: None,
+                            limit: Some(100),
+                        }),
                         request_body: "",
                         response_body: &body(RESP_BODY, &models::ListStats {
                             bots: index_bots,
@@ -1679,33 +2682,366 @@ UUIDs in the case of ids. Bad names/ids will be ignored"#,
                             context: None,
                         }),
                         auth_types: vec![models::RouteAuthType::User],
-                    }
-                ]
-            }
-        ]
-    );
-}
+                    },
 
+                    models::Route {
+                        title: "Get Vote Reminders",
+                        method: "GET",
+                        path: "/users/{id}/reminders",
+                        description: r#"
+Returns the vote reminders of a user.
 
+``remind_at`` is when the reminder will next be sent. This is set when the user votes (to when they can 
+vote again) and is ``null`` after the reminder is sent until the user votes again. If the reminder could not be 
+sent, ``remind_at`` is pushed back and it is retried a few times before being given up on"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &vec![models::VoteReminder::default()]),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
 
+                    models::Route {
+                        title: "Create Vote Reminder",
+                        method: "POST",
+                        path: "/users/{id}/reminders",
+                        description: r#"
+Opts in to vote reminders for a bot or server. If a reminder already exists, its method is changed.
 
+``method`` is a [ReminderMethod](./enums#remindermethod). Push notifications are sent to every device 
+subscribed using ``/notifications/{id}/sub``. A user can have up 
+to 50 reminders"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::CreateVoteReminder {
+                            target_id: 0,
+                            target_type: models::TargetType::Bot,
+                            method: models::ReminderMethod::Both,
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
 
+                    models::Route {
+                        title: "Delete Vote Reminder",
+                        method: "DELETE",
+                        path: "/users/{user_id}/reminders/{reminder_id}",
+                        description: "Cancels a vote reminder",
+                        path_params: &body(PATH_PARAMS, &models::UserReminderPath::default()),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse::ok()),
+                        auth_types: vec![models::RouteAuthType::User],
+                    }
+                ]
+            },
 
+            models::RouteList {
+                file_name: "webhooks.md",
+                routes: vec![
+                    models::Route {
+                        title: "Get Bot Webhook Deliveries",
+                        method: "GET",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks",
+                        description: &(r#"
+Returns the webhook delivery log of a bot, newest first.
 
-fn enum_doc<T: Debug + Serialize>(typ: T) -> String {
-    format!("| **{:?}** | {} |\n", typ, json!(typ))
-}
+Webhooks are queued and retried with exponential backoff (honouring ``Retry-After``)
+until they succeed or are moved to the dead letter queue after 8 attempts.
+``status`` is a [WebhookDeliveryStatus](./enums#webhookdeliverystatus)
 
-fn new_enum(data: models::EnumDesc) -> String {
-    let mut keys = String::new();
+``limit`` defaults to 25. Pass ``next_cursor`` or ``prev_cursor`` back as ``cursor`` to paginate
 
-    for name in data.alt_names {
-        writeln!(&mut keys, "- ``{}``\n", name).unwrap();
-    }
+### Verifying webhooks
 
-    format!("
-    
-### {name}
+Each request has the following headers:
+
+- ``X-Webhook-Timestamp``: The unix timestamp (in seconds) the request was sent at
+- ``X-Webhook-Event-Id``: The ``eid`` of the event, this is the same across retries
+- ``X-Webhook-Signature-V2``: A comma seperated list of ``v2=<signature>``. Each signature is 
+the hex encoded HMAC-SHA512 of ``{timestamp}.{eid}.{body}`` keyed with your webhook secret 
+(one is created if unset, webhooks without a secret used to be signed with your API token). While rotating your secret, a second signature made with your old secret is also sent
+- ``X-Webhook-Signature``: *Deprecated*, HMAC-SHA512 of only the body. This can be replayed and should not be used. 
+While rotating your secret, this (and the ``Authorization`` header) keeps using your old secret until it is cleared
+
+To verify a request, compute the signature yourself and compare it (in constant time) against each 
+``v2`` signature. Reject requests where the timestamp is more than 5 minutes away from your current time
+and requests with a ``X-Webhook-Event-Id`` you have already handled.
+
+Test vectors:
+
+"#.to_string() + &webhook_test_vectors()),
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: &body(QUERY_PARAMS, &models::CursorQuery {
+                            cursor: None,
+                            limit: Some(25),
+                        }),
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::WebhookDeliveryList {
+                            deliveries: vec![models::WebhookDelivery::default()],
+                            next_cursor: None,
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Redeliver Bot Webhook",
+                        method: "POST",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks/{eid}/redeliver",
+                        description: r#"
+Queues a previously sent event (by its ``eid``) to be sent again to the bots current webhook URL.
+
+This works for both delivered and dead lettered events"#,
+                        path_params: &body(PATH_PARAMS, &models::RedeliverBotWebhookPath {
+                            user_id: 0,
+                            bot_id: 0,
+                            eid: "event id".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Get Server Webhook Deliveries",
+                        method: "GET",
+                        path: "/servers/{id}/webhooks",
+                        description: r#"
+Returns the webhook delivery log of a server, newest first.
+
+See [Get Bot Webhook Deliveries](#get-bot-webhook-deliveries) for more information"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: &body(QUERY_PARAMS, &models::CursorQuery {
+                            cursor: None,
+                            limit: Some(25),
+                        }),
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::WebhookDeliveryList {
+                            deliveries: vec![models::WebhookDelivery::default()],
+                            next_cursor: None,
+                            prev_cursor: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    },
+
+                    models::Route {
+                        title: "Redeliver Server Webhook",
+                        method: "POST",
+                        path: "/servers/{id}/webhooks/{eid}/redeliver",
+                        description: "Queues a previously sent event (by its ``eid``) to be sent again to the servers current webhook URL.",
+                        path_params: &body(PATH_PARAMS, &models::RedeliverServerWebhookPath {
+                            id: 0,
+                            eid: "event id".to_string(),
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    },
+
+                    models::Route {
+                        title: "Rotate Bot Webhook Secret",
+                        method: "POST",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks/secret/rotate",
+                        description: r#"
+Sets a new webhook secret (a random one is generated if ``secret`` is not set) and returns it.
+
+The old secret becomes the secondary secret and webhooks are signed with both until 
+[Clear Bot Secondary Webhook Secret](#clear-bot-secondary-webhook-secret) is called. This lets 
+you update your receiver to the new secret without dropping any webhooks.
+
+Custom secrets must be between 16 and 512 characters and may not contain commas"#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::RotateWebhookSecret {
+                            secret: None,
+                        }),
+                        response_body: &body(RESP_BODY, &models::WebhookSecret {
+                            secret: "new webhook secret".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Clear Bot Secondary Webhook Secret",
+                        method: "DELETE",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks/secret/secondary",
+                        description: "Stops signing webhooks with the old secret after a rotation",
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Rotate Server Webhook Secret",
+                        method: "POST",
+                        path: "/servers/{id}/webhooks/secret/rotate",
+                        description: r#"
+Sets a new webhook secret for a server and returns it.
+
+See [Rotate Bot Webhook Secret](#rotate-bot-webhook-secret) for more information"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::RotateWebhookSecret {
+                            secret: None,
+                        }),
+                        response_body: &body(RESP_BODY, &models::WebhookSecret {
+                            secret: "new webhook secret".to_string(),
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    },
+
+                    models::Route {
+                        title: "Clear Server Secondary Webhook Secret",
+                        method: "DELETE",
+                        path: "/servers/{id}/webhooks/secret/secondary",
+                        description: "Stops signing webhooks with the old secret after a rotation",
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    },
+
+                    models::Route {
+                        title: "Get Bot Webhook Events",
+                        method: "GET",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks/events",
+                        description: r#"
+Returns the events (a list of [EventName](./enums#eventname)) the bots webhook is subscribed to.
+
+By default, only ``BotVote`` is sent"#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::WebhookEvents {
+                            events: vec![models::EventName::BotVote, models::EventName::ReviewAdd],
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Set Bot Webhook Events",
+                        method: "PUT",
+                        path: "/users/{user_id}/bots/{bot_id}/webhooks/events",
+                        description: r#"
+Sets the events (a list of [EventName](./enums#eventname)) the bots webhook is subscribed to.
+
+Vote events keep using the vote webhook payload. All other events are sent as the same
+event object that is sent over the websocket, signed the same way as vote webhooks.
+
+Discord integration webhooks only support ``BotVote``"#,
+                        path_params: &body(PATH_PARAMS, &models::GetUserBotPath {
+                            user_id: 0,
+                            bot_id: 0,
+                        }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::WebhookEvents {
+                            events: vec![models::EventName::BotVote, models::EventName::ReviewAdd],
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::User],
+                    },
+
+                    models::Route {
+                        title: "Get Server Webhook Events",
+                        method: "GET",
+                        path: "/servers/{id}/webhooks/events",
+                        description: r#"
+Returns the events (a list of [EventName](./enums#eventname)) the servers webhook is subscribed to.
+
+By default, only ``ServerVote`` is sent"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: "",
+                        response_body: &body(RESP_BODY, &models::WebhookEvents {
+                            events: vec![models::EventName::ServerVote, models::EventName::ReviewAdd],
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    },
+
+                    models::Route {
+                        title: "Set Server Webhook Events",
+                        method: "PUT",
+                        path: "/servers/{id}/webhooks/events",
+                        description: r#"
+Sets the events (a list of [EventName](./enums#eventname)) the servers webhook is subscribed to.
+
+See [Set Bot Webhook Events](#set-bot-webhook-events) for more information"#,
+                        path_params: &body(PATH_PARAMS, &models::FetchBotPath { id: 0 }),
+                        query_params: "",
+                        request_body: &body(REQ_BODY, &models::WebhookEvents {
+                            events: vec![models::EventName::ServerVote, models::EventName::ReviewAdd],
+                        }),
+                        response_body: &body(RESP_BODY, &models::APIResponse {
+                            done: true,
+                            reason: None,
+                            context: None,
+                        }),
+                        auth_types: vec![models::RouteAuthType::Server],
+                    }
+                ]
+            }
+        ]
+    );
+}
+
+
+
+
+
+
+
+fn enum_doc<T: Debug + Serialize>(typ: T) -> String {
+    format!("| **{:?}** | {} |\n", typ, json!(typ))
+}
+
+fn new_enum(data: models::EnumDesc) -> String {
+    let mut keys = String::new();
+
+    for name in data.alt_names {
+        writeln!(&mut keys, "- ``{}``\n", name).unwrap();
+    }
+
+    format!("
+    
+### {name}
 
 {desc}
 
@@ -1860,6 +3196,20 @@ It is semi-automatically generated
         },
     });
 
+    // SearchSort
+    docs += &new_enum(models::EnumDesc {
+        name: "SearchSort",
+        alt_names: vec!["sort (query parameter)"],
+        description: "The order to return search results in",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::SearchSort::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
     // PageStyle
     docs += &new_enum(models::EnumDesc {
         name: "PageStyle",
@@ -1916,6 +3266,76 @@ It is semi-automatically generated
         },
     });
 
+    // AuditAction
+    docs += &new_enum(models::EnumDesc {
+        name: "AuditAction",
+        alt_names: vec!["action"],
+        description: "What was done to a bot, server or user in a audit log. The first 13 are the same as UserBotAction",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::AuditAction::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // AuditTargetType
+    docs += &new_enum(models::EnumDesc {
+        name: "AuditTargetType",
+        alt_names: vec!["target_type"],
+        description: "What a audit log is for",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::AuditTargetType::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // ReplyOrder
+    docs += &new_enum(models::EnumDesc {
+        name: "ReplyOrder",
+        alt_names: vec!["reply_order", "order"],
+        description: "The order to return replies to reviews in",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::ReplyOrder::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // ReviewReportReason
+    docs += &new_enum(models::EnumDesc {
+        name: "ReviewReportReason",
+        alt_names: vec!["reason"],
+        description: "Why a review was reported",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::ReviewReportReason::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // ReviewModAction
+    docs += &new_enum(models::EnumDesc {
+        name: "ReviewModAction",
+        alt_names: vec!["action"],
+        description: "What staff can do to a reported or flagged review",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::ReviewModAction::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
     // AppealType
     docs += &new_enum(models::EnumDesc {
         name: "AppealType",
@@ -1944,6 +3364,131 @@ It is semi-automatically generated
         },
     });
 
+    // WebhookDeliveryStatus
+    docs += &new_enum(models::EnumDesc {
+        name: "WebhookDeliveryStatus",
+        alt_names: vec!["status"],
+        description: "The status of a webhook delivery",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::WebhookDeliveryStatus::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // TokenScope
+    docs += &new_enum(models::EnumDesc {
+        name: "TokenScope",
+        alt_names: vec!["scopes"],
+        description: r#"A permission of a scoped API token. Scoped tokens can be used on endpoints needing
+one of their scopes, all other endpoints need a full access token"#,
+        gen: || {
+            let mut types = String::new();
+            for typ in models::TokenScope::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    docs += &new_enum(models::EnumDesc {
+        name: "SessionType",
+        alt_names: vec!["session_type"],
+        description: "The type of a session",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::SessionType::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    docs += &new_enum(models::EnumDesc {
+        name: "VoteRiskState",
+        alt_names: vec!["state"],
+        description: "The state of a scored vote",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::VoteRiskState::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    docs += &new_enum(models::EnumDesc {
+        name: "ReminderMethod",
+        alt_names: vec!["method"],
+        description: "How a vote reminder is sent",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::ReminderMethod::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    docs += &new_enum(models::EnumDesc {
+        name: "Capability",
+        alt_names: vec!["capabilities"],
+        description: r#"Something only staff can do. Each capability is granted from a baypaw perm level, by default 
+``queue.*`` needs a perm of 2, ``vote.review``, ``review.delete_any``, ``review.moderate`` and ``audit.view`` need 3, ``bot.ban`` needs 4, 
+``bot.certify`` needs 5 and ``experiment.grant`` needs 6"#,
+        gen: || {
+            let mut types = String::new();
+            for typ in models::Capability::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    docs += &new_enum(models::EnumDesc {
+        name: "ClientVerificationState",
+        alt_names: vec!["verification_state"],
+        description: "The verification state of a Frostpaw client",
+        gen: || {
+            let mut types = String::new();
+            for typ in models::ClientVerificationState::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
+    // GatewayOp
+    docs += &new_enum(models::EnumDesc {
+        name: "GatewayOp",
+        alt_names: vec!["op"],
+        description: r#"The op code of a frame sent over the JSON websocket gateway (``/ws/{id}?mode={target_type}``).
+Frames are of the form ``{"op": op, "d": data, "s": sequence number, "t": event name}``, ``s`` and ``t`` only being set on Dispatch.
+View events are not sequenced (``s`` is not set) and are not replayed on Resume.
+
+On connect, the server sends a Hello with ``v`` and ``heartbeat_interval`` (in milliseconds). The client must send a Heartbeat
+every ``heartbeat_interval`` or it will be disconnected. To start receiving events, send a Identify with ``{"token": "..."}``, or
+a Resume with ``{"token": "...", "seq": last sequence number}`` to also receive every event missed since ``seq``. A Ready is sent
+once events are being received. The legacy text protocol can be used by passing ``legacy=true``
+
+Users owning many bots/servers can instead connect to ``/ws/users/{user_id}`` and Identify with a user token to receive the events of
+every bot/server they own over one connection (or only the ``targets`` given in the Identify, a list of ``{"id": "...", "target_type": target_type}``).
+Events include their target in ``ctx``. Ready lists the ``targets`` being received and is sent again whenever owned bots/servers change. 
+Resume is not supported on the user gateway
+
+Identify and Resume also take a optional ``filter`` to only receive some events: ``{"events": [list of EventName], "user": "user id", "exclude_test": true}``.
+All set conditions must match. Using the legacy protocol, the filter can be given as JSON after ``SUB`` or ``RESUME <seq>``"#,
+        gen: || {
+            let mut types = String::new();
+            for typ in models::GatewayOp::iter() {
+                types += &enum_doc(typ);
+            }
+            types
+        },
+    });
+
     docs += "To see errors, please see https://github.com/Fates-List/api-v3/blob/main/src/models.rs and search for all ``APIError`` trait implementations";
 
     let path = match std::env::var_os("HOME") {
@@ -1959,3 +3504,30 @@ It is semi-automatically generated
 
     file.write_all(docs.as_bytes()).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::WEBHOOK_TEST_VECTORS;
+    use crate::converters;
+
+    #[test]
+    fn webhook_test_vectors_verify() {
+        for (secret, timestamp, eid, body, signature) in WEBHOOK_TEST_VECTORS {
+            let signature = "v2=".to_string() + signature;
+
+            assert_eq!(
+                converters::verify_webhook(secret, &timestamp.to_string(), eid, body, &signature, timestamp),
+                Ok(()),
+                "test vector for {} does not verify",
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn webhook_test_vectors_match_signer() {
+        for (secret, timestamp, eid, body, signature) in WEBHOOK_TEST_VECTORS {
+            assert_eq!(converters::sign_webhook_v2(&[secret], timestamp, eid, body), "v2=".to_string() + signature);
+        }
+    }
+}
@@ -7,7 +7,6 @@ use actix_web::{get, post, web, HttpRequest, HttpResponse};
 use log::{debug, error};
 use std::collections::HashMap;
 use std::time::Duration;
-use std::sync::Arc;
 use uuid::Uuid;
 use ring::hmac;
 
@@ -76,25 +75,16 @@ async fn refresh_access_token(req: HttpRequest, client_id: web::Path<String>, in
     }
 
     // Invalidate all other tokens
-    for (key, value) in data.database.client_data.iter() {
-        if value.user_id == refresh_data.user_id && value.client_id == refresh_data.client.id {
-            data.database.client_data.invalidate(&key).await;
-        }
+    data.database.revoke_legacy_client_tokens(refresh_data.user_id, &client.id).await;
+
+    match data.database.create_oauth2_token(&client.id, refresh_data.user_id, None, &converters::session_origin(&req)).await {
+        Ok(token) => HttpResponse::Ok().json(models::APIResponse {
+            done: true,
+            reason: None,
+            context: Some(token.access_token),
+        }),
+        Err(err) => HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(err))),
     }
-
-    let access_token = "Frostpaw.".to_string() + &converters::create_token(64);
-
-    data.database.client_data.insert(access_token.clone(), Arc::new(models::FrostpawLogin {
-        client_id: client.id,
-        user_id: refresh_data.user_id,
-        token: data.database.get_user_token(refresh_data.user_id).await,
-    })).await;
-
-    HttpResponse::Ok().json(models::APIResponse {
-        done: true,
-        reason: None,
-        context: Some(access_token),
-    })
 }
 
 /// Creates a oauth2 login
@@ -128,7 +118,9 @@ async fn do_oauth2(req: HttpRequest, info: web::Json<models::OauthDoQuery>) -> H
 
     debug!("Got redirect URI: {}", redirect_uri);
 
-    let login = login_user(data, code, redirect_uri).await;
+    let origin = converters::session_origin(&req);
+
+    let login = login_user(data, code, redirect_uri, &origin).await;
 
     match login {
         Err(err) => {
@@ -138,14 +130,6 @@ async fn do_oauth2(req: HttpRequest, info: web::Json<models::OauthDoQuery>) -> H
         Ok(mut user) => {
             // Check for a frostpaw login
             if info.frostpaw {
-    		    if !data.config.discord.allowed_oauth2.contains(&(redirect_url_domain.to_string())) {
-        		    return HttpResponse::BadRequest().json(models::APIResponse {
-            			done: false,
-            			reason: Some("Origin header is not in valid format, perhaps your client isn't setting the header properly?".to_string()),
-            			context: None,
-        		    });
-    		    }
-
                 // Check claw with blood
                 if info.frostpaw_blood.is_none() || info.frostpaw_claw.is_none() || info.frostpaw_claw_unseathe_time.is_none() {
                     return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::InvalidFields));
@@ -172,6 +156,14 @@ async fn do_oauth2(req: HttpRequest, info: web::Json<models::OauthDoQuery>) -> H
                 }
                 let client = client.unwrap();
 
+                if !client.redirect_domains.contains(&(redirect_url_domain.to_string())) {
+                    return HttpResponse::BadRequest().json(models::APIResponse {
+                        done: false,
+                        reason: Some("Origin header is not a redirect domain of this client, perhaps your client isn't setting the header properly?".to_string()),
+                        context: None,
+                    });
+                }
+
                 // Now check HMAC
 		let key = hmac::Key::new(hmac::HMAC_SHA512, client.secret.as_bytes());
 	
@@ -186,16 +178,14 @@ async fn do_oauth2(req: HttpRequest, info: web::Json<models::OauthDoQuery>) -> H
                 }
 
                 // OK, now that we are reasonably confident about client, we can create the frostpaw login
-                let access_token = "Frostpaw.".to_string() + &converters::create_token(64);
-                
-                data.database.client_data.insert(access_token.clone(), Arc::new(models::FrostpawLogin {
-                    client_id: client.id,
-                    user_id: user.user.id.parse().unwrap(),
-                    token: user.token,
-                })).await;
-
-                // Put new access token and refresh token in user struct
-                user.token = access_token.clone();
+                let access_token = match data.database.create_oauth2_token(&client.id, user.user.id.parse().unwrap(), None, &origin).await {
+                    Ok(token) => token.access_token,
+                    Err(err) => return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(err))),
+                };
+
+                // Put new access token and refresh token in user struct, the client gets no login token
+                data.database.revoke_login_token(user.user.id.parse().unwrap(), &user.token).await;
+                user.token = access_token;
                 user.refresh_token = Some(data.database.add_refresh_token(&blood, user.user.id.parse().unwrap()).await);
 
                 return HttpResponse::Ok().json(user);
@@ -211,6 +201,7 @@ async fn login_user(
     data: &models::AppState,
     code: String,
     redirect_url: String,
+    origin: &models::SessionOrigin,
 ) -> Result<models::OauthUserLogin, models::OauthError> {
     let mut params = HashMap::new();
     params.insert("client_id", data.config.secrets.client_id.clone());
@@ -265,7 +256,7 @@ async fn login_user(
 
     let data = data
         .database
-        .create_user_oauth(json)
+        .create_user_oauth(json, origin)
         .await
         .map_err(models::OauthError::SQLError)?;
 
@@ -13,23 +13,32 @@ use log::{debug, error, info};
 use std::sync::Arc;
 
 mod appeal;
+mod audit;
+mod auth;
 mod botactions;
 mod serveractions;
 mod commands;
 mod converters;
 mod core;
 mod database;
+mod devportal;
 mod docs;
 mod login;
 mod models;
+mod oauth;
 mod packs;
+mod perms;
+mod ratelimit;
+mod reminders;
 mod reviews;
 mod security;
+mod staff;
 mod stats;
 mod user;
 mod ws;
 mod votes;
 mod notifs;
+mod webhooks;
 
 use crate::models::APIResponse;
 
@@ -66,7 +75,8 @@ async fn main() -> std::io::Result<()> {
         */
         Arc::new(discord_main),
         Arc::new(discord_server),
-        app_config.discord.clone()
+        app_config.discord.clone(),
+        app_config.staff_perms.clone()
     )
     .await;
 
@@ -83,6 +93,9 @@ async fn main() -> std::io::Result<()> {
         requests: client,
     });
 
+    actix_rt::spawn(webhooks::delivery_worker(app_state.database.clone()));
+    actix_rt::spawn(reminders::reminder_worker(app_state.database.clone()));
+
     docs::document_routes();
     docs::document_enums();
 
@@ -110,6 +123,13 @@ async fn main() -> std::io::Result<()> {
                 http::header::HeaderName::from_bytes(b"Frostpaw-Invite").unwrap(),
                 http::header::HeaderName::from_bytes(b"Method").unwrap(),
             ])
+            .expose_headers(vec![
+                http::header::RETRY_AFTER,
+                http::header::HeaderName::from_bytes(b"X-RateLimit-Limit").unwrap(),
+                http::header::HeaderName::from_bytes(b"X-RateLimit-Remaining").unwrap(),
+                http::header::HeaderName::from_bytes(b"X-RateLimit-Reset").unwrap(),
+                http::header::HeaderName::from_bytes(b"X-RateLimit-Bucket").unwrap(),
+            ])
             .supports_credentials()
             .max_age(3600);
         App::new()
@@ -121,6 +141,7 @@ async fn main() -> std::io::Result<()> {
             )
             .app_data(web::QueryConfig::default().error_handler(|err, _req| actix_handle_err(err)))
             .app_data(web::PathConfig::default().error_handler(|err, _req| actix_handle_err(err)))
+            .wrap(ratelimit::Ratelimiter)
             .wrap(cors)
             .wrap(middleware::Compress::default())
             .wrap(Logger::default())
@@ -169,18 +190,48 @@ async fn main() -> std::io::Result<()> {
             .service(votes::create_server_vote)
             .service(votes::get_bot_votes)
             .service(votes::get_server_votes)
+            .service(votes::get_flagged_votes)
+            .service(votes::review_flagged_vote)
+            .service(staff::get_queue)
+            .service(staff::staff_action)
+            .service(staff::get_review_queue)
+            .service(staff::moderate_review)
 
             // Login
             .service(login::get_oauth2)
             .service(login::do_oauth2)
             .service(login::get_frostpaw_client)
             .service(login::refresh_access_token)
+
+            // OAuth2
+            .service(oauth::oauth2_authorize_info)
+            .service(oauth::oauth2_authorize)
+            .service(oauth::oauth2_token)
+            .service(oauth::oauth2_introspect)
+            .service(oauth::oauth2_revoke)
+
+            // Dev Portal
+            .service(devportal::get_user_clients)
+            .service(devportal::create_client)
+            .service(devportal::edit_client)
+            .service(devportal::delete_client)
+            .service(devportal::rotate_client_secret)
+            .service(devportal::request_client_verification)
             
             // Security
             .service(security::new_bot_token)
             .service(security::new_user_token)
             .service(security::new_server_token)
+            .service(security::get_user_api_tokens)
+            .service(security::create_user_api_token)
+            .service(security::delete_user_api_token)
+            .service(security::get_bot_api_tokens)
+            .service(security::create_bot_api_token)
+            .service(security::delete_bot_api_token)
             .service(security::revoke_frostpaw_client_auth)
+            .service(security::get_user_sessions)
+            .service(security::revoke_user_session)
+            .service(security::logout_everywhere)
             
             // Bot Actions
             .service(botactions::add_bot)
@@ -202,6 +253,9 @@ async fn main() -> std::io::Result<()> {
             // Appeal
             .service(appeal::appeal_bot)
             .service(appeal::appeal_server)
+            .service(audit::get_bot_audit_logs)
+            .service(audit::get_server_audit_logs)
+            .service(audit::get_user_audit_logs)
 
             // Packs
             .service(packs::add_pack)
@@ -221,6 +275,8 @@ async fn main() -> std::io::Result<()> {
             .service(reviews::edit_review)
             .service(reviews::delete_review)
             .service(reviews::vote_review)
+            .service(reviews::get_replies)
+            .service(reviews::report_review)
 
             // Stats
             .service(stats::get_bot_list_stats)
@@ -232,11 +288,29 @@ async fn main() -> std::io::Result<()> {
             // WS
             .service(ws::preview_description)
             .service(ws::bot_ws)
+            .service(ws::user_ws)
 
             // Notifications
             .service(notifs::get_notif_info)
             .service(notifs::subscribe)
             .service(notifs::test_notifs)
+            .service(notifs::get_vote_reminders)
+            .service(notifs::create_vote_reminder)
+            .service(notifs::delete_vote_reminder)
+
+            // Webhooks
+            .service(webhooks::get_bot_webhook_deliveries)
+            .service(webhooks::redeliver_bot_webhook)
+            .service(webhooks::get_server_webhook_deliveries)
+            .service(webhooks::redeliver_server_webhook)
+            .service(webhooks::get_bot_webhook_events)
+            .service(webhooks::set_bot_webhook_events)
+            .service(webhooks::get_server_webhook_events)
+            .service(webhooks::set_server_webhook_events)
+            .service(webhooks::rotate_bot_webhook_secret)
+            .service(webhooks::clear_bot_secondary_webhook_secret)
+            .service(webhooks::rotate_server_webhook_secret)
+            .service(webhooks::clear_server_secondary_webhook_secret)
     })
     .workers(8)
     .bind("localhost:3987")?
@@ -2,7 +2,7 @@ use crate::database;
 use actix_web::HttpResponse;
 use log::{debug, error};
 use num_enum::TryFromPrimitive;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use indexmap::{map::IndexMap, indexmap};
 use serde_repr::{Serialize_repr, Deserialize_repr};
 use serenity::model::id::{ChannelId, RoleId, GuildId};
@@ -12,6 +12,8 @@ use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 use std::collections::HashMap;
+use chrono::Datelike;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 // Re-export common models
@@ -213,6 +215,9 @@ pub struct Index {
     pub certified: Vec<IndexBot>,
     pub tags: Vec<Tag>,
     pub features: Vec<Feature>,
+    /// Cursors for ``top_voted``
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 impl Index {
@@ -224,6 +229,8 @@ impl Index {
             new: Vec::new(),
             tags: Vec::new(),
             features: Vec::new(),
+            next_cursor: None,
+            prev_cursor: None,
         }
     }
 }
@@ -283,11 +290,34 @@ pub struct Search {
     pub profiles: Vec<SearchProfile>,
     pub packs: Vec<BotPack>,
     pub tags: SearchTags,
+    pub totals: SearchTotals,
+    pub facets: SearchFacets,
+    /// Only set by Search Tags (and walks ``bots``)
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+/// Amount of bots matching a search per tag and per feature
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SearchFacets {
+    pub tags: HashMap<String, i64>,
+    pub features: HashMap<String, i64>,
+}
+
+/// Total amount of hits for a search, regardless of pagination
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SearchTotals {
+    pub bots: i64,
+    pub servers: i64,
+    pub profiles: i64,
+    pub packs: i64,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
 pub struct IndexQuery {
     pub target_type: TargetType,
+    /// Cursor to walk ``top_voted`` with
+    pub cursor: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -310,6 +340,128 @@ pub struct ImportQuery {
 #[derive(Deserialize, Serialize, Clone)]
 pub struct WsModeStruct {
     pub mode: TargetType,
+    /// Use the legacy text protocol (``AUTH``, ``SUB``, ``ARCHIVE`` etc.) instead of JSON frames
+    pub legacy: Option<bool>,
+    /// Gateway version, only ``1`` is supported right now
+    pub v: Option<i32>,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum GatewayOp {
+    #[default]
+    Dispatch = 0, // Server: A event, ``s`` is its sequence number and ``t`` its EventName
+    Heartbeat = 1, // Client: Must be sent every ``heartbeat_interval``
+    Identify = 2, // Client: Authenticates and starts receiving events
+    Ready = 3, // Server: Sent once events are being received after a Identify or Resume
+    Resume = 6, // Client: Authenticates, replays missed events and starts receiving events
+    InvalidSession = 9, // Server: Sent before closing due to bad auth or a bad frame
+    Hello = 10, // Server: Sent on connect
+    HeartbeatAck = 11, // Server: Sent in response to a Heartbeat
+}
+
+/// A frame sent over the JSON gateway
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayFrame {
+    pub op: GatewayOp,
+    #[serde(default)]
+    pub d: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub t: Option<EventName>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayHello {
+    pub v: i32,
+    /// In milliseconds
+    pub heartbeat_interval: u64,
+}
+
+/// Query parameters for the user gateway (``/ws/users/{id}``)
+#[derive(Deserialize, Serialize, Clone)]
+pub struct UserWsQuery {
+    /// Gateway version, only ``1`` is supported right now
+    pub v: Option<i32>,
+}
+
+/// A bot or server a user gateway session is subscribed to
+#[derive(Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct GatewayTarget {
+    pub id: String,
+    pub target_type: TargetType,
+}
+
+/// Server side filtering of gateway events, all set conditions must match for a event to be sent
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayFilter {
+    /// Only send these events
+    #[serde(default)]
+    pub events: Option<Vec<EventName>>,
+    /// Only send events caused by this user (such as votes by them)
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Don't send test events (such as test votes)
+    #[serde(default)]
+    pub exclude_test: bool,
+}
+
+impl GatewayFilter {
+    pub fn matches(&self, event: &Event<serde_json::Value>) -> bool {
+        if let Some(events) = &self.events {
+            if !events.contains(&event.m.e) {
+                return false;
+            }
+        }
+
+        if let Some(user) = &self.user {
+            if event.ctx.user.as_ref() != Some(user) {
+                return false;
+            }
+        }
+
+        if self.exclude_test && event.props.get("test").and_then(|test| test.as_bool()).unwrap_or(false) {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayIdentify {
+    pub token: String,
+    #[serde(default)]
+    pub filter: Option<GatewayFilter>,
+    /// User gateway only. Subscribe to only these bots/servers instead of all of them. 
+    /// Bots/servers the user does not own are ignored
+    #[serde(default)]
+    pub targets: Option<Vec<GatewayTarget>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayResume {
+    pub token: String,
+    #[serde(default)]
+    pub filter: Option<GatewayFilter>,
+    /// The last sequence number received
+    pub seq: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayReady {
+    pub resumed: bool,
+    /// User gateway only. The bots/servers events are being received for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<Vec<GatewayTarget>>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct GatewayInvalidSession {
+    pub reason: String,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -360,6 +512,48 @@ pub struct FrostpawClient {
     pub secret: String,
     pub owner: User,
     pub verified: bool,
+    /// Domains the client may redirect users to after login
+    pub redirect_domains: Vec<String>,
+    pub verification_state: ClientVerificationState,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum ClientVerificationState {
+    #[default]
+    Unverified = 0, // Never submitted for verification
+    Pending = 1, // Submitted for verification, waiting on staff
+    Verified = 2, // Verified by staff
+    Denied = 3, // Denied by staff, can be resubmitted
+}
+
+/// Creates or edits a Frostpaw client in the developer portal
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct EditFrostpawClient {
+    pub name: String,
+    pub domain: String,
+    pub privacy_policy: String,
+    pub redirect_domains: Vec<String>,
+}
+
+/// The ID and secret of a Frostpaw client. Only sent when the client is created or its secret rotated
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct FrostpawClientSecret {
+    pub id: String,
+    pub secret: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ClientVerificationRequest {
+    pub reason: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct UserClientPath {
+    pub user_id: i64,
+    pub client_id: String,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -411,11 +605,389 @@ pub struct NotificationSubData {
     pub data: String
 }
 
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum ReminderMethod {
+    #[default]
+    Push = 0, // A push notification to every device the user has subscribed
+    Dm = 1, // A Discord DM from the main bot
+    Both = 2, // Both a push notification and a Discord DM
+}
+
+/// A vote reminder for a bot or server
+#[derive(Deserialize, Serialize, Clone)]
+pub struct VoteReminder {
+    pub id: uuid::Uuid,
+    pub target_id: String,
+    pub target_type: TargetType,
+    pub method: ReminderMethod,
+    /// When the reminder will next be sent, ``None`` until the user votes again
+    pub remind_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_sent_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for VoteReminder {
+    fn default() -> Self {
+        VoteReminder {
+            id: uuid::Uuid::nil(),
+            target_id: "0".to_string(),
+            target_type: TargetType::Bot,
+            method: ReminderMethod::Both,
+            remind_at: Some(chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            )),
+            last_sent_at: None,
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
-pub struct FrostpawLogin {
+pub struct CreateVoteReminder {
+    pub target_id: i64,
+    pub target_type: TargetType,
+    pub method: ReminderMethod,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct UserReminderPath {
+    pub user_id: i64,
+    pub reminder_id: uuid::Uuid,
+}
+
+/// A reminder claimed by the reminder worker, never sent to clients
+pub struct PendingVoteReminder {
+    pub id: uuid::Uuid,
+    pub user_id: i64,
+    pub target_id: i64,
+    pub target_type: TargetType,
+    pub method: ReminderMethod,
+    /// The ``remind_at`` the reminder was claimed with, the user may vote again (and reschedule it) while it is being sent
+    pub remind_at: chrono::DateTime<chrono::Utc>,
+    pub attempts: i32,
+}
+
+/// Where a session was created from, see ``converters::session_origin``
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SessionOrigin {
+    pub origin: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum SessionType {
+    #[default]
+    Login = 0, // A login on the site
+    Client = 1, // A Frostpaw client using OAuth2 (or its access token from the older claw login)
+    ClientConnection = 2, // A refresh token of the older claw login
+}
+
+/// A active session of a user
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Session {
+    pub id: String,
+    pub session_type: SessionType,
+    /// The client for ``Client`` and ``ClientConnection`` sessions
+    pub client: Option<FrostpawClient>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The network the session was created from
+    pub origin: Option<String>,
+    pub user_agent: Option<String>,
+    /// Whether this is the session being used to make the request
+    pub current: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            id: "session id".to_string(),
+            session_type: SessionType::Login,
+            client: None,
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+            last_used_at: None,
+            expires_at: None,
+            origin: Some("203.0.113.0/24".to_string()),
+            user_agent: Some("Mozilla/5.0".to_string()),
+            current: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct UserSessionPath {
+    pub user_id: i64,
+    pub session_id: String,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum VoteRiskState {
+    #[default]
+    Counted = 0, // The vote was counted
+    Held = 1, // The vote looked suspicious and is waiting on staff
+    Confirmed = 2, // Staff confirmed the vote, it has been counted
+    Voided = 3, // Staff voided the vote, it is not counted
+}
+
+/// Metadata recorded with a vote, used to score it
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct VoteMeta {
+    /// The network the vote was made from
+    pub origin: Option<String>,
+    /// Hash of request headers that stay the same for a browser
+    pub fingerprint: Option<String>,
+}
+
+/// A vote along with its risk score
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FlaggedVote {
+    pub id: uuid::Uuid,
+    pub user: User,
+    pub target_id: String,
+    pub target_type: TargetType,
+    pub origin: Option<String>,
+    pub account_created_at: chrono::DateTime<chrono::Utc>,
+    pub score: i32,
+    /// Why the vote was scored as it was
+    pub reasons: Vec<String>,
+    pub state: VoteRiskState,
+    pub reviewed_by: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for FlaggedVote {
+    fn default() -> Self {
+        FlaggedVote {
+            id: uuid::Uuid::nil(),
+            user: User::default(),
+            target_id: "0".to_string(),
+            target_type: TargetType::Bot,
+            origin: Some("203.0.113.0/24".to_string()),
+            account_created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+            score: 70,
+            reasons: vec!["NewAccount".to_string(), "FingerprintBurst".to_string()],
+            state: VoteRiskState::Held,
+            reviewed_by: None,
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct FlaggedVoteQuery {
+    /// Defaults to ``Held``
+    pub state: Option<VoteRiskState>,
+    pub target_id: Option<i64>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct FlaggedVoteList {
+    pub votes: Vec<FlaggedVote>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct FlaggedVotePath {
+    pub user_id: i64,
+    pub vote_id: uuid::Uuid,
+}
+
+/// Staff decision on a held vote
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct VoteDecision {
+    /// ``true`` to count the vote, ``false`` to void it
+    pub confirm: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct QueueQuery {
+    /// Defaults to ``Bot``
+    pub target_type: Option<TargetType>,
+    /// Defaults to ``Pending``
+    pub state: Option<State>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// A bot or server in the staff queue
+#[derive(Deserialize, Serialize, Clone)]
+pub struct QueueEntry {
+    pub user: User,
+    pub target_type: TargetType,
+    pub state: State,
+    /// The staff member who claimed this bot or server
+    pub verifier: Option<String>,
+    pub description: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for QueueEntry {
+    fn default() -> Self {
+        QueueEntry {
+            user: User::default(),
+            target_type: TargetType::Bot,
+            state: State::Pending,
+            verifier: None,
+            description: "My bot".to_string(),
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct QueueList {
+    pub queue: Vec<QueueEntry>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct StaffTargetPath {
+    pub user_id: i64,
+    pub target_id: i64,
+}
+
+/// A staff action on a bot or server in the queue
+#[derive(Deserialize, Serialize, Clone)]
+pub struct StaffAction {
+    pub target_type: TargetType,
+    pub action: UserBotAction,
+    /// Required when denying or banning
+    pub reason: Option<String>,
+}
+
+/// Query parameters of the OAuth2 authorize endpoints (RFC 6749 section 4.1.1 and RFC 7636)
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2AuthorizeQuery {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    /// Space separated scopes the client wants
+    #[serde(default)]
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+/// What a user is consenting to, shown on the consent page
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2AuthorizeInfo {
+    pub client: FrostpawClient,
+    pub scopes: Vec<TokenScope>,
+    pub redirect_uri: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2Consent {
+    pub approve: bool,
+}
+
+/// Where to send the user after they approve or deny a client
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2Redirect {
+    pub redirect_uri: String,
+}
+
+/// A authorization code waiting to be exchanged for a token
+pub struct Oauth2Code {
     pub client_id: String,
     pub user_id: i64,
-    pub token: String, // User token
+    pub redirect_uri: String,
+    pub scopes: Vec<TokenScope>,
+    pub code_challenge: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Form body of the token endpoint. Which fields are needed depends on ``grant_type``
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2TokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Oauth2TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: Option<String>,
+    /// Space separated scopes of the token
+    pub scope: String,
+}
+
+impl Default for Oauth2TokenResponse {
+    fn default() -> Self {
+        Oauth2TokenResponse {
+            access_token: "Frostpaw.access token here".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 3600,
+            refresh_token: Some("refresh token here".to_string()),
+            scope: "votes:write votes:read".to_string(),
+        }
+    }
+}
+
+/// Form body of the introspection (RFC 7662) and revocation (RFC 7009) endpoints
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2TokenQuery {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// A introspected token. Everything but ``active`` is left out for inactive tokens
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Oauth2Introspection {
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The user ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -455,6 +1027,104 @@ pub struct OauthAccessTokenResponse {
 #[derive(Deserialize, Serialize, Clone, Default)]
 pub struct SearchTagQuery {
     pub q: String,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for any endpoint supporting cursor based pagination
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct CursorQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// An opaque keyset pagination cursor. 
+///
+/// Clients only ever see the encoded form (``next_cursor`` and ``prev_cursor``) and should
+/// pass it back as is
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct Cursor {
+    /// Sort key of the item this cursor points to
+    pub key: i64,
+    /// Tie breaker (the ID) of the item this cursor points to
+    pub id: String,
+    /// Whether this cursor walks backwards (is a ``prev_cursor``)
+    pub rev: bool,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        base64::encode_config(serde_json::to_string(self).unwrap_or_default(), base64::URL_SAFE_NO_PAD)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Cursor> {
+        let decoded = base64::decode_config(cursor, base64::URL_SAFE_NO_PAD).ok()?;
+        serde_json::from_slice(&decoded).ok()
+    }
+
+    /// Decodes a cursor from a query parameter. ``None`` means start from the first page
+    pub fn from_query(cursor: &Option<String>) -> Result<Option<Cursor>, GenericError> {
+        match cursor {
+            Some(cursor) => Cursor::decode(cursor).map(Some).ok_or(GenericError::InvalidCursor),
+            None => Ok(None),
+        }
+    }
+
+    /// Clamps a client provided page size
+    pub fn limit(limit: Option<i64>, default: i64) -> i64 {
+        limit.unwrap_or(default).clamp(1, 100)
+    }
+
+    /// The ID of this cursor as a bigint (bot/server/user IDs)
+    pub fn id_i64(&self) -> i64 {
+        self.id.parse().unwrap_or_default()
+    }
+
+    /// Turns rows fetched (with ``limit + 1`` rows) in the direction of ``cursor`` into a page. 
+    /// 
+    /// Rows must be ordered by ``key`` descending, or ascending when walking backwards
+    pub fn paginate<T>(
+        mut rows: Vec<T>,
+        limit: i64,
+        cursor: Option<&Cursor>,
+        key: impl Fn(&T) -> (i64, String),
+    ) -> CursorPage<T> {
+        let rev = cursor.map(|c| c.rev).unwrap_or(false);
+        let has_more = rows.len() as i64 > limit;
+
+        rows.truncate(limit as usize);
+
+        if rev {
+            rows.reverse();
+        }
+
+        // Walking backwards means there is always a next page (the one we came from)
+        let (more_next, more_prev) = if rev {
+            (cursor.is_some(), has_more)
+        } else {
+            (has_more, cursor.is_some())
+        };
+
+        let make_cursor = |item: Option<&T>, rev: bool| {
+            item.map(|item| {
+                let (key, id) = key(item);
+                Cursor { key, id, rev }.encode()
+            })
+        };
+
+        CursorPage {
+            next_cursor: if more_next { make_cursor(rows.last(), false) } else { None },
+            prev_cursor: if more_prev { make_cursor(rows.first(), true) } else { None },
+            items: rows,
+        }
+    }
+}
+
+/// A single page of results returned by ``Cursor::paginate``
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -462,6 +1132,86 @@ pub struct SearchQuery {
     pub q: String,
     pub gc_from: i64,
     pub gc_to: i64,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// Comma seperated list of tags, all of which must be present
+    pub tags: Option<String>,
+    /// Comma seperated list of feature IDs, all of which must be present
+    pub features: Option<String>,
+    pub library: Option<String>,
+    pub certified_only: Option<bool>,
+    /// ``true`` to only show NSFW entities, ``false`` to exclude them. Omit to include both
+    pub nsfw: Option<bool>,
+    pub min_stars: Option<f64>,
+    pub sort: Option<SearchSort>,
+}
+
+impl SearchQuery {
+    /// The last page that can be requested, anything further should be narrowed down using filters
+    pub const MAX_PAGE: i64 = 1000;
+
+    /// Returns the (limit, offset) to use for this search
+    pub fn limits(&self) -> Result<(i64, i64), GenericError> {
+        let per_page = self.per_page.unwrap_or(6).clamp(1, 50);
+        let page = self.page.unwrap_or(1).max(1);
+
+        if page > SearchQuery::MAX_PAGE {
+            return Err(GenericError::InvalidPage);
+        }
+
+        Ok((per_page, (page - 1) * per_page))
+    }
+
+    fn split_list(list: &Option<String>) -> Option<Vec<String>> {
+        let list: Vec<String> = list
+            .as_ref()?
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect();
+
+        if list.is_empty() {
+            None
+        } else {
+            Some(list)
+        }
+    }
+
+    pub fn tag_list(&self) -> Option<Vec<String>> {
+        SearchQuery::split_list(&self.tags)
+    }
+
+    pub fn feature_list(&self) -> Option<Vec<String>> {
+        SearchQuery::split_list(&self.features)
+    }
+}
+
+#[derive(
+    Eq, Serialize, Deserialize, PartialEq, Clone, Copy, Default, Debug, EnumIter
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Votes,
+    GuildCount,
+    CreatedAt,
+    TotalVotes,
+    ReviewAverage,
+}
+
+impl SearchSort {
+    /// The name used for this sort order in SQL queries
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Votes => "votes",
+            SearchSort::GuildCount => "guild_count",
+            SearchSort::CreatedAt => "created_at",
+            SearchSort::TotalVotes => "total_votes",
+            SearchSort::ReviewAverage => "review_average",
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -505,6 +1255,9 @@ pub struct ListStats {
     pub bots: Vec<IndexBot>,
     pub servers: Vec<IndexBot>,
     pub uptime: f64,
+    /// Cursors for ``bots``
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -575,17 +1328,561 @@ pub struct DiscordData {
     pub support_server: String,
     pub site_url: String,
     pub fates_api_url: String,
-    pub allowed_oauth2: Vec<String> // What oauth2 url's are allowed?
+}
+
+/// A rate limit quota, ``requests`` requests every ``per`` seconds
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub struct RatelimitQuota {
+    pub requests: u32,
+    pub per: u64,
+}
+
+impl RatelimitQuota {
+    const fn new(requests: u32, per: u64) -> Self {
+        RatelimitQuota { requests, per }
+    }
+}
+
+/// The quotas of a route group for each ratelimit tier
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RatelimitRule {
+    pub ip: RatelimitQuota,
+    pub user: RatelimitQuota,
+    pub bot: RatelimitQuota,
+    pub certified_bot: RatelimitQuota,
+    pub staff: RatelimitQuota,
+}
+
+impl RatelimitRule {
+    pub fn quota(&self, tier: RatelimitTier) -> RatelimitQuota {
+        match tier {
+            RatelimitTier::Ip => self.ip,
+            RatelimitTier::User => self.user,
+            RatelimitTier::Bot => self.bot,
+            RatelimitTier::CertifiedBot => self.certified_bot,
+            RatelimitTier::Staff => self.staff,
+        }
+    }
+}
+
+/// Who a request is rate limited as. Requests without a valid token are limited by IP
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RatelimitTier {
+    Ip,
+    User,
+    Bot,
+    CertifiedBot,
+    Staff,
+}
+
+/// The baypaw perm level at which a user counts as staff
+pub const STAFF_PERM: f32 = 2.0;
+
+/// Something only staff can do. Each capability is granted from a baypaw perm level, see ``StaffPermConfig``
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum Capability {
+    #[serde(rename = "queue.view")]
+    QueueView,
+    /// Claiming and unclaiming
+    #[serde(rename = "queue.claim")]
+    QueueClaim,
+    /// Approving, denying and requeueing
+    #[serde(rename = "queue.approve")]
+    QueueApprove,
+    #[serde(rename = "bot.certify")]
+    BotCertify,
+    #[serde(rename = "bot.ban")]
+    BotBan,
+    #[serde(rename = "vote.review")]
+    VoteReview,
+    /// Viewing the audit logs of any bot or server
+    #[serde(rename = "audit.view")]
+    AuditView,
+    #[serde(rename = "review.delete_any")]
+    ReviewDeleteAny,
+    /// Seeing reported and flagged reviews, hiding and restoring them
+    #[serde(rename = "review.moderate")]
+    ReviewModerate,
+    #[serde(rename = "experiment.grant")]
+    ExperimentGrant,
+}
+
+impl Capability {
+    /// The perm level needed for this capability if ``staff_perms.json`` does not say otherwise
+    pub fn default_perm(&self) -> f32 {
+        match self {
+            // Bot reviewers
+            Capability::QueueView | Capability::QueueClaim | Capability::QueueApprove => STAFF_PERM,
+            // Moderators
+            Capability::VoteReview
+            | Capability::ReviewDeleteAny
+            | Capability::ReviewModerate
+            | Capability::AuditView => 3.0,
+            // Admins
+            Capability::BotBan => 4.0,
+            Capability::BotCertify => 5.0,
+            // Developers
+            Capability::ExperimentGrant => 6.0,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|name| name.as_str().map(|name| name.to_string()))
+            .unwrap_or_default()
+    }
+}
+
+/// Staff permissions, from ``staff_perms.json`` in the config data dir
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct StaffPermConfig {
+    /// Overrides of the perm level needed for a capability
+    pub capabilities: HashMap<Capability, f32>,
+    /// Staff roles to use instead of asking baypaw (keyed by user ID) in tests. Users missing from it
+    /// are not staff. This can never be set from the config file
+    #[cfg(test)]
+    #[serde(skip)]
+    pub fake_baypaw: Option<HashMap<String, bristlefrost::models::StaffRole>>,
+}
+
+impl StaffPermConfig {
+    pub fn min_perm(&self, capability: Capability) -> f32 {
+        self.capabilities
+            .get(&capability)
+            .copied()
+            .unwrap_or_else(|| capability.default_perm())
+    }
+
+    /// All capabilities granted at a perm level
+    pub fn granted(&self, perm: f32) -> Vec<Capability> {
+        Capability::iter()
+            .filter(|capability| perm >= self.min_perm(*capability))
+            .collect()
+    }
+}
+
+/// The baypaw staff role of a user along with what it allows them to do
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct StaffPerms {
+    #[serde(flatten)]
+    pub role: bristlefrost::models::StaffRole,
+    pub capabilities: Vec<Capability>,
+}
+
+/// Groups of routes sharing a rate limit, see ``ratelimit``
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteGroup {
+    Global,
+    Votes,
+    Reviews,
+    ReviewVotes,
+    Search,
+    Stats,
+    Gateway,
+    Login,
+}
+
+impl RouteGroup {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RouteGroup::Global => "global",
+            RouteGroup::Votes => "votes",
+            RouteGroup::Reviews => "reviews",
+            RouteGroup::ReviewVotes => "review_votes",
+            RouteGroup::Search => "search",
+            RouteGroup::Stats => "stats",
+            RouteGroup::Gateway => "gateway",
+            RouteGroup::Login => "login",
+        }
+    }
+}
+
+/// Rate limits of each route group, from ``ratelimits.json`` in the config data dir. Groups
+/// missing from the file use the defaults below
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct RatelimitConfig {
+    pub enabled: bool,
+    pub global: RatelimitRule,
+    pub votes: RatelimitRule,
+    pub reviews: RatelimitRule,
+    pub review_votes: RatelimitRule,
+    pub search: RatelimitRule,
+    pub stats: RatelimitRule,
+    pub gateway: RatelimitRule,
+    pub login: RatelimitRule,
+    /// Reverse proxies allowed to set ``X-Forwarded-For``. Requests from anywhere else are
+    /// limited by their peer address
+    pub trusted_proxies: Vec<std::net::IpAddr>,
+}
+
+impl RatelimitConfig {
+    pub fn rule(&self, group: RouteGroup) -> &RatelimitRule {
+        match group {
+            RouteGroup::Global => &self.global,
+            RouteGroup::Votes => &self.votes,
+            RouteGroup::Reviews => &self.reviews,
+            RouteGroup::ReviewVotes => &self.review_votes,
+            RouteGroup::Search => &self.search,
+            RouteGroup::Stats => &self.stats,
+            RouteGroup::Gateway => &self.gateway,
+            RouteGroup::Login => &self.login,
+        }
+    }
+}
+
+impl Default for RatelimitConfig {
+    fn default() -> Self {
+        let q = RatelimitQuota::new;
+
+        RatelimitConfig {
+            enabled: true,
+            global: RatelimitRule {
+                ip: q(120, 60),
+                user: q(240, 60),
+                bot: q(240, 60),
+                certified_bot: q(600, 60),
+                staff: q(1200, 60),
+            },
+            votes: RatelimitRule {
+                ip: q(5, 60),
+                user: q(10, 60),
+                bot: q(10, 60),
+                certified_bot: q(10, 60),
+                staff: q(60, 60),
+            },
+            reviews: RatelimitRule {
+                ip: q(5, 60),
+                user: q(10, 60),
+                bot: q(10, 60),
+                certified_bot: q(10, 60),
+                staff: q(60, 60),
+            },
+            review_votes: RatelimitRule {
+                ip: q(15, 60),
+                user: q(30, 60),
+                bot: q(30, 60),
+                certified_bot: q(30, 60),
+                staff: q(120, 60),
+            },
+            search: RatelimitRule {
+                ip: q(30, 60),
+                user: q(60, 60),
+                bot: q(60, 60),
+                certified_bot: q(120, 60),
+                staff: q(300, 60),
+            },
+            stats: RatelimitRule {
+                ip: q(5, 60),
+                user: q(5, 60),
+                bot: q(5, 60),
+                certified_bot: q(15, 60),
+                staff: q(60, 60),
+            },
+            gateway: RatelimitRule {
+                ip: q(10, 60),
+                user: q(10, 60),
+                bot: q(10, 60),
+                certified_bot: q(30, 60),
+                staff: q(60, 60),
+            },
+            login: RatelimitRule {
+                ip: q(20, 60),
+                user: q(20, 60),
+                bot: q(20, 60),
+                certified_bot: q(20, 60),
+                staff: q(60, 60),
+            },
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+/// Rules used to score votes, from ``vote_risk.json`` in the config data dir. Votes with a score of at
+/// least ``hold_score`` are held for staff review instead of being counted
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct VoteRiskConfig {
+    pub enabled: bool,
+    pub hold_score: i32,
+    /// Accounts younger than this (in days, from the Discord snowflake) are new
+    pub new_account_days: i64,
+    pub new_account_score: i32,
+    /// The window (in seconds) used to look for bursts of votes
+    pub burst_window: i64,
+    /// More than this many votes from one network in the window is a burst
+    pub network_burst_votes: i64,
+    pub network_burst_score: i32,
+    /// More than this many votes from one fingerprint (by different users) in the window is a burst
+    pub fingerprint_burst_votes: i64,
+    pub fingerprint_burst_score: i32,
+    /// The window (in seconds) used to look for vote rings
+    pub ring_window: i64,
+    /// More than this many other users voting for the same bot/server from one network in the window is a ring
+    pub ring_votes: i64,
+    pub ring_score: i32,
+}
+
+impl Default for VoteRiskConfig {
+    fn default() -> Self {
+        VoteRiskConfig {
+            enabled: true,
+            hold_score: 60,
+            new_account_days: 7,
+            new_account_score: 30,
+            burst_window: 10 * 60,
+            network_burst_votes: 10,
+            network_burst_score: 30,
+            fingerprint_burst_votes: 3,
+            fingerprint_burst_score: 40,
+            ring_window: 60 * 60,
+            ring_votes: 3,
+            ring_score: 40,
+        }
+    }
+}
+
+/// A time limited vote multiplier such as a holiday event
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct VoteEvent {
+    pub name: String,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    pub multiplier: i64,
+}
+
+/// The highest multiplier a bot/server in ``state`` can get
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct VoteStateCap {
+    pub state: State,
+    pub max_multiplier: i64,
+}
+
+/// List-wide vote rules, from ``vote_rules.json`` in the config data dir
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct VoteRules {
+    /// How long (in seconds) a user must wait between votes
+    pub cooldown: i64,
+    /// The multiplier on saturdays and sundays (UTC)
+    pub weekend_multiplier: i64,
+    pub events: Vec<VoteEvent>,
+    pub state_caps: Vec<VoteStateCap>,
+}
+
+impl VoteRules {
+    /// The multiplier of a vote made at ``now`` for a bot/server in ``state``. When more than one
+    /// multiplier applies the highest is used (they do not stack), this is then capped using ``state_caps``
+    pub fn multiplier(&self, state: State, now: chrono::DateTime<chrono::Utc>) -> i64 {
+        let mut multiplier = 1;
+
+        if matches!(now.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun) {
+            multiplier = multiplier.max(self.weekend_multiplier);
+        }
+
+        for event in &self.events {
+            if event.starts_at <= now && now < event.ends_at {
+                multiplier = multiplier.max(event.multiplier);
+            }
+        }
+
+        if let Some(cap) = self.state_caps.iter().find(|cap| cap.state == state) {
+            multiplier = multiplier.min(cap.max_multiplier);
+        }
+
+        multiplier.max(0)
+    }
+}
+
+impl Default for VoteRules {
+    fn default() -> Self {
+        VoteRules {
+            cooldown: 8 * 60 * 60,
+            weekend_multiplier: 1,
+            events: Vec::new(),
+            state_caps: Vec::new(),
+        }
+    }
+}
+
+/// The spam/abuse filter run on new and edited reviews, from ``review_filter.json`` in the config data dir.
+/// Reviews scoring at least ``reject_score`` are rejected, those scoring at least ``flag_score`` are flagged
+/// and hidden until staff look at them
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReviewFilterConfig {
+    pub enabled: bool,
+    pub flag_score: i32,
+    pub reject_score: i32,
+    /// Words (matched case insensitively) that should not be in a review
+    pub blocked_words: Vec<String>,
+    pub blocked_word_score: i32,
+    /// More links than this is suspicious
+    pub max_links: usize,
+    pub link_score: i32,
+    /// Reviews with at least ``caps_min_letters`` letters where more than this fraction of them are uppercase
+    pub caps_ratio: f32,
+    pub caps_min_letters: usize,
+    pub caps_score: i32,
+    /// A character repeated at least this many times in a row
+    pub repeat_run: usize,
+    pub repeat_score: i32,
+    /// The user already posted the same text on another review
+    pub duplicate_score: i32,
+    /// How often a user can post or edit reviews (and replies)
+    pub post_quota: RatelimitQuota,
+    /// How often a user can report reviews
+    pub report_quota: RatelimitQuota,
+}
+
+impl ReviewFilterConfig {
+    /// Scores the text of a review, the duplicate check needs the database and is done in ``Database::filter_review``
+    pub fn score(&self, text: &str) -> ReviewFilterResult {
+        let mut result = ReviewFilterResult::default();
+
+        if !self.enabled {
+            return result;
+        }
+
+        let lower = text.to_lowercase();
+
+        for word in &self.blocked_words {
+            if !word.is_empty() && lower.contains(&word.to_lowercase()) {
+                result.add(self.blocked_word_score, "blocked word");
+                break;
+            }
+        }
+
+        if lower.matches("http://").count() + lower.matches("https://").count() > self.max_links {
+            result.add(self.link_score, "too many links");
+        }
+
+        let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+        let upper = text.chars().filter(|c| c.is_uppercase()).count();
+
+        if letters >= self.caps_min_letters && upper as f32 > letters as f32 * self.caps_ratio {
+            result.add(self.caps_score, "too many capital letters");
+        }
+
+        let mut run = 0;
+        let mut last = None;
+
+        for c in text.chars() {
+            if Some(c) == last && !c.is_whitespace() {
+                run += 1;
+            } else {
+                run = 1;
+                last = Some(c);
+            }
+
+            if run >= self.repeat_run {
+                result.add(self.repeat_score, "repeated characters");
+                break;
+            }
+        }
+
+        result
+    }
+}
+
+impl Default for ReviewFilterConfig {
+    fn default() -> Self {
+        ReviewFilterConfig {
+            enabled: true,
+            flag_score: 50,
+            reject_score: 100,
+            blocked_words: Vec::new(),
+            blocked_word_score: 100,
+            max_links: 2,
+            link_score: 50,
+            caps_ratio: 0.7,
+            caps_min_letters: 20,
+            caps_score: 30,
+            repeat_run: 10,
+            repeat_score: 30,
+            duplicate_score: 50,
+            post_quota: RatelimitQuota::new(5, 60 * 60),
+            report_quota: RatelimitQuota::new(10, 60 * 60),
+        }
+    }
+}
+
+/// How suspicious a review looks and why
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ReviewFilterResult {
+    pub score: i32,
+    pub reasons: Vec<String>,
+}
+
+impl ReviewFilterResult {
+    pub fn add(&mut self, score: i32, reason: &str) {
+        self.score += score;
+        self.reasons.push(reason.to_string());
+    }
+}
+
+/// How review threads are loaded, from ``review_threads.json`` in the config data dir
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReviewThreadConfig {
+    /// How deep replies can be nested, a reply to a top level review is 1 deep
+    pub max_depth: i32,
+    /// How many levels of replies are loaded with a review (at least 1), deeper replies are loaded
+    /// using ``GET /reviews/{rid}/replies``
+    pub inline_depth: i32,
+    /// How many replies of each review are loaded with it
+    pub inline_replies: i64,
+}
+
+impl Default for ReviewThreadConfig {
+    fn default() -> Self {
+        ReviewThreadConfig {
+            max_depth: 5,
+            inline_depth: 2,
+            inline_replies: 5,
+        }
+    }
+}
+
+/// The result of counting a request against a quota
+#[derive(Clone, Copy, Debug)]
+pub struct RatelimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Unix time (in seconds) the current window ends
+    pub reset: u64,
+    /// Seconds to wait before retrying, ``0`` if the request is allowed
+    pub retry_after: u64,
 }
 
 pub struct AppConfig {
     pub secrets: Secrets,
     pub partners: Partners,
     pub discord: DiscordData,
+    pub ratelimits: RatelimitConfig,
+    pub vote_risk: VoteRiskConfig,
+    pub vote_rules: VoteRules,
+    pub staff_perms: StaffPermConfig,
+    pub review_filter: ReviewFilterConfig,
+    pub review_threads: ReviewThreadConfig,
     pub discord_http: serenity::http::Http,
     pub discord_http_server: serenity::http::Http,
 }
 
+/// Loads ``name`` from the data dir if it exists, otherwise returns the default config
+fn load_optional_config<T: DeserializeOwned + Default>(data_dir: &str, name: &str) -> T {
+    match File::open(data_dir.to_owned() + name) {
+        Ok(mut file) => {
+            let mut data = String::new();
+            file.read_to_string(&mut data).unwrap();
+            serde_json::from_str(&data).unwrap_or_else(|err| panic!("{} is invalid: {}", name, err))
+        }
+        Err(_) => T::default(),
+    }
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         let path = match env::var_os("HOME") {
@@ -618,12 +1915,20 @@ impl Default for AppConfig {
 
         // open discord.json, handle config
         let mut file =
-            File::open(data_dir + "discord.json").expect("No discord.json file found");
+            File::open(data_dir.to_owned() + "discord.json").expect("No discord.json file found");
         let mut discord = String::new();
         file.read_to_string(&mut discord).unwrap();
 
         let discord: DiscordData = serde_json::from_str(&discord).expect("Discord data is invalid");
 
+        // Optional configs, the defaults are used if the file does not exist
+        let ratelimits: RatelimitConfig = load_optional_config(&data_dir, "ratelimits.json");
+        let vote_risk: VoteRiskConfig = load_optional_config(&data_dir, "vote_risk.json");
+        let vote_rules: VoteRules = load_optional_config(&data_dir, "vote_rules.json");
+        let staff_perms: StaffPermConfig = load_optional_config(&data_dir, "staff_perms.json");
+        let review_filter: ReviewFilterConfig = load_optional_config(&data_dir, "review_filter.json");
+        let review_threads: ReviewThreadConfig = load_optional_config(&data_dir, "review_threads.json");
+
         let token_main = secrets.token_main.clone();
         let token_squirrelflight = secrets.token_squirrelflight.clone();
 
@@ -631,6 +1936,12 @@ impl Default for AppConfig {
             secrets,
             partners,
             discord,
+            ratelimits,
+            vote_risk,
+            vote_rules,
+            staff_perms,
+            review_filter,
+            review_threads,
             discord_http: serenity::http::Http::new(&token_main),
             discord_http_server: serenity::http::Http::new(&token_squirrelflight),
         }
@@ -778,6 +2089,74 @@ pub struct BotOwner {
     pub main: bool,
 }
 
+/// A entry in the audit log of a bot, server or user
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AuditLog {
+    pub id: uuid::Uuid,
+    pub target_id: String,
+    pub target_type: AuditTargetType,
+    /// The user, bot or server (when using its own token) that did this
+    pub actor_id: String,
+    pub action: AuditAction,
+    pub context: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        AuditLog {
+            id: uuid::Uuid::nil(),
+            target_id: "0".to_string(),
+            target_type: AuditTargetType::Bot,
+            actor_id: "0".to_string(),
+            action: AuditAction::EditBot,
+            context: None,
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+        }
+    }
+}
+
+/// A audit log entry to record
+pub struct NewAuditLog {
+    pub target_id: i64,
+    pub target_type: AuditTargetType,
+    pub actor_id: i64,
+    pub action: AuditAction,
+    pub context: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct AuditLogQuery {
+    pub action: Option<AuditAction>,
+    /// Only show what this user (or bot/server) did
+    pub actor: Option<i64>,
+    /// Unix timestamp (inclusive)
+    pub from: Option<i64>,
+    /// Unix timestamp (exclusive)
+    pub to: Option<i64>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Download every matching entry (up to 10000) instead of a page
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct AuditLogList {
+    pub logs: Vec<AuditLog>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct ActionLog {
     pub user_id: String,
@@ -981,7 +2360,7 @@ impl Default for Bot {
             webhook_type: None,
             webhook_hmac_only: None,
             webhook_secret: Some("This (along with ``webhook_type``, ``api_token`` and ``webhook_hmac_only``) will be redacted for Get Bot endpoint".to_string()),
-            api_token: Some("This will be redacted for Get Bot endpoint".to_string()),
+            api_token: Some("This is always null, bot tokens are only shown by New Bot Token".to_string()),
         }
     }
 }
@@ -1024,6 +2403,70 @@ pub enum EventName {
     ServerInvite = 72,
 }
 
+impl EventName {
+    /// Bit used for this event in a webhook event subscription mask (``webhook_events``)
+    ///
+    /// These are stable and must never be reused, new events get the next free bit
+    pub fn webhook_bit(self) -> i64 {
+        let bit = match self {
+            EventName::BotVote => 0,
+            EventName::BotEdit => 1,
+            EventName::BotDelete => 2,
+            EventName::BotClaim => 3,
+            EventName::BotApprove => 4,
+            EventName::BotDeny => 5,
+            EventName::BotBan => 6,
+            EventName::BotUnban => 7,
+            EventName::BotRequeue => 8,
+            EventName::BotCertify => 9,
+            EventName::BotUncertify => 10,
+            EventName::BotTransfer => 11,
+            EventName::BotUnverify => 12,
+            EventName::BotView => 13,
+            EventName::BotInvite => 14,
+            EventName::BotUnclaim => 15,
+            EventName::BotVoteReset => 16,
+            EventName::BotLock => 17,
+            EventName::BotUnlock => 18,
+            EventName::ReviewVote => 19,
+            EventName::ReviewAdd => 20,
+            EventName::ReviewEdit => 21,
+            EventName::ReviewDelete => 22,
+            EventName::ResourceAdd => 23,
+            EventName::ResourceDelete => 24,
+            EventName::CommandAdd => 25,
+            EventName::CommandDelete => 26,
+            EventName::ServerView => 27,
+            EventName::ServerVote => 28,
+            EventName::ServerInvite => 29,
+        };
+
+        1 << bit
+    }
+
+    /// Whether the event gets a sequence number and is kept for RESUME. Views are sent far too 
+    /// often to be worth replaying
+    pub fn sequenced(self) -> bool {
+        !matches!(self, EventName::BotView | EventName::ServerView)
+    }
+
+    pub fn to_mask(events: &[EventName]) -> i64 {
+        events.iter().fold(0, |mask, event| mask | event.webhook_bit())
+    }
+
+    pub fn from_mask(mask: i64) -> Vec<EventName> {
+        EventName::iter()
+            .filter(|event| mask & event.webhook_bit() != 0)
+            .collect()
+    }
+}
+
+/// Events a bot or server webhook is subscribed to
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct WebhookEvents {
+    pub events: Vec<EventName>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GuildInviteBaypawData {
     pub url: String,
@@ -1034,7 +2477,29 @@ pub struct GuildInviteBaypawData {
     Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
 )]
 #[repr(i32)]
-pub enum UserBotAction {
+pub enum UserBotAction {
+    #[default]
+    Approve = 0,
+    Deny = 1,
+    Certify = 2,
+    Ban = 3,
+    Claim = 4,
+    Unclaim = 5,
+    TransferOwnership = 6,
+    EditBot = 7,
+    DeleteBot = 8,
+    Unban = 9,
+    Uncertify = 10,
+    Unverify = 11,
+    Requeue = 12,
+}
+
+/// What was done to a bot, server or user, see ``audit_logs``. The first 13 are the same as ``UserBotAction``
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum AuditAction {
     #[default]
     Approve = 0,
     Deny = 1,
@@ -1049,6 +2514,29 @@ pub enum UserBotAction {
     Uncertify = 10,
     Unverify = 11,
     Requeue = 12,
+    EditServer = 13, // Done using the server bot
+    NewToken = 14,
+    CreateApiToken = 15,
+    DeleteApiToken = 16,
+    AddPack = 17,
+    EditPack = 18,
+    DeletePack = 19,
+    AddCommands = 20,
+    DeleteCommands = 21,
+    HideReview = 22,
+    RestoreReview = 23,
+    DeleteReview = 24,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum AuditTargetType {
+    #[default]
+    Bot = 0,
+    Server = 1,
+    User = 2,
 }
 
 #[derive(
@@ -1076,6 +2564,24 @@ pub struct BotViewProp {
 pub struct BotVoteProp {
     pub test: bool,
     pub votes: i64,
+    /// How many votes this vote counted as, see ``VoteRules``
+    pub multiplier: i64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ReviewAddProp {
+    pub id: uuid::Uuid,
+    pub parent_id: Option<uuid::Uuid>,
+    pub star_rating: bigdecimal::BigDecimal,
+    pub review_text: String,
+    pub owner_response: bool,
+}
+
+/// Sent with the claim, unclaim, approve, deny, ban, certify and requeue events
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ModActionProp {
+    pub action: UserBotAction,
+    pub reason: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -1090,6 +2596,10 @@ pub struct EventContext {
 pub struct EventMeta {
     pub e: EventName,
     pub eid: String,
+    /// Per bot/server sequence number, this is set by ``Database::ws_event``. Always 0 for events 
+    /// which are not sequenced (``EventName::sequenced``)
+    #[serde(default)]
+    pub seq: i64,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -1107,6 +2617,238 @@ pub struct VoteWebhookEvent {
     pub votes: i64,
     pub eid: String,
     pub test: bool,
+    /// How many votes this vote counted as (during weekends and events), reward users accordingly
+    pub multiplier: i64,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum WebhookDeliveryStatus {
+    #[default]
+    Pending = 0, // Queued or waiting for a retry
+    Delivered = 1,
+    DeadLetter = 2, // Gave up after too many failed attempts
+}
+
+/// A queued or sent webhook delivery
+#[derive(Deserialize, Serialize, Clone)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub eid: String,
+    pub url: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for WebhookDelivery {
+    fn default() -> Self {
+        WebhookDelivery {
+            id: "delivery id".to_string(),
+            eid: "event id".to_string(),
+            url: "https://example.com/webhook".to_string(),
+            status: WebhookDeliveryStatus::Delivered,
+            attempts: 1,
+            last_status_code: Some(200),
+            last_error: None,
+            next_attempt_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+            delivered_at: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct WebhookDeliveryList {
+    pub deliveries: Vec<WebhookDelivery>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+/// A delivery claimed by the webhook worker, never sent to clients
+pub struct PendingWebhookDelivery {
+    pub id: uuid::Uuid,
+    pub target_id: i64,
+    pub target_type: TargetType,
+    pub eid: String,
+    pub url: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+}
+
+/// Where (and for which events) events of a target are sent, cached as this is needed for every event.
+/// ``url`` is ``None`` if the target has no webhook or only supports votes (Discord integrations)
+#[derive(Clone)]
+pub struct WebhookTarget {
+    pub url: Option<String>,
+    pub events: i64,
+}
+
+/// Secrets used to sign a targets webhooks, never sent to clients
+pub struct WebhookSecrets {
+    pub secret: String,
+    pub secondary: Option<String>,
+    pub hmac_only: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct RotateWebhookSecret {
+    /// A random secret is generated if this is not set
+    pub secret: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct WebhookSecret {
+    pub secret: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct RedeliverBotWebhookPath {
+    pub user_id: i64,
+    pub bot_id: i64,
+    pub eid: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct RedeliverServerWebhookPath {
+    pub id: i64,
+    pub eid: String,
+}
+
+/// Who a scoped API token belongs to
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter,
+)]
+#[repr(i32)]
+pub enum TokenType {
+    #[default]
+    User = 0,
+    Bot = 1,
+}
+
+/// A permission that can be given to a scoped API token
+#[derive(Eq, Serialize, Deserialize, PartialEq, Clone, Copy, Debug, EnumIter)]
+pub enum TokenScope {
+    /// Edit bots the user owns (user tokens)
+    #[serde(rename = "bot:edit")]
+    BotEdit,
+    /// Vote for bots and servers (user tokens)
+    #[serde(rename = "votes:write")]
+    VotesWrite,
+    /// Post stats (bot tokens)
+    #[serde(rename = "stats:write")]
+    StatsWrite,
+    /// Add and delete commands (bot tokens)
+    #[serde(rename = "commands:write")]
+    CommandsWrite,
+    /// Connect to the websocket gateway to receive votes and other events
+    #[serde(rename = "votes:read")]
+    VotesRead,
+}
+
+impl TokenScope {
+    /// The name of the scope as stored in the database
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenScope::BotEdit => "bot:edit",
+            TokenScope::VotesWrite => "votes:write",
+            TokenScope::StatsWrite => "stats:write",
+            TokenScope::CommandsWrite => "commands:write",
+            TokenScope::VotesRead => "votes:read",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        TokenScope::iter().find(|scope| scope.as_str() == name)
+    }
+
+    /// Whether a token of ``token_type`` can be given this scope
+    pub fn valid_for(self, token_type: TokenType) -> bool {
+        match self {
+            TokenScope::BotEdit | TokenScope::VotesWrite => token_type == TokenType::User,
+            TokenScope::StatsWrite | TokenScope::CommandsWrite => token_type == TokenType::Bot,
+            TokenScope::VotesRead => true,
+        }
+    }
+}
+
+/// A scoped API token. The token itself is only returned once, on creation
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for ApiToken {
+    fn default() -> Self {
+        ApiToken {
+            id: "token id".to_string(),
+            name: "Stats poster".to_string(),
+            scopes: vec![TokenScope::StatsWrite],
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+            expires_at: None,
+            last_used_at: Some(chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct CreateApiToken {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for CreateApiToken {
+    fn default() -> Self {
+        CreateApiToken {
+            name: "Stats poster".to_string(),
+            scopes: vec![TokenScope::StatsWrite],
+            expires_at: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct NewApiToken {
+    pub id: String,
+    /// Make sure to save this, it cannot be seen again
+    pub token: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct UserTokenPath {
+    pub user_id: i64,
+    pub token_id: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct UserBotTokenPath {
+    pub user_id: i64,
+    pub bot_id: i64,
+    pub token_id: String,
 }
 
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -1122,6 +2864,11 @@ pub struct UserVoted {
     pub vote_right_now: bool,
     pub expiry: u64,
     pub timestamps: Vec<chrono::DateTime<chrono::Utc>>,
+    /// Cursors for ``timestamps``
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    /// How many votes a vote made right now would count as
+    pub weight: i64,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -1204,9 +2951,24 @@ pub struct Review {
     pub review_text: String,
     pub votes: ParsedReviewVotes,
     pub flagged: bool,
+    /// Hidden reviews are only shown to their author and staff
+    #[serde(default)]
+    pub hidden: bool,
+    /// Set on the official response of the owner to a top level review
+    #[serde(default)]
+    pub owner_response: bool,
     pub user: User,
     pub epoch: Vec<i64>,
+    /// The owner response to this review, it is not included in ``replies``
+    #[serde(default)]
+    pub response: Option<Box<Review>>,
     pub replies: Vec<Review>,
+    /// Whether this review has replies that are not in ``replies``
+    #[serde(default)]
+    pub more_replies: bool,
+    /// Cursor to load the rest of ``replies`` with, if some were loaded
+    #[serde(default)]
+    pub replies_cursor: Option<String>,
     pub parent_id: Option<uuid::Uuid>,
 }
 
@@ -1229,6 +2991,8 @@ pub struct ParsedReview {
     pub from: i64,
     pub stats: ReviewStats,
     pub user_review: Option<Review>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -1236,6 +3000,151 @@ pub struct ReviewQuery {
     pub target_type: TargetType,
     pub page: Option<i64>,
     pub user_id: Option<i64>,
+    /// Takes priority over ``page`` if set
+    pub cursor: Option<String>,
+    /// Defaults to ``oldest``
+    pub reply_order: Option<ReplyOrder>,
+}
+
+#[derive(
+    Eq, Serialize, Deserialize, PartialEq, Clone, Copy, Default, Debug, EnumIter
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyOrder {
+    #[default]
+    Oldest,
+    Newest,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReplyQuery {
+    /// Defaults to ``oldest``
+    pub order: Option<ReplyOrder>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReplyList {
+    pub replies: Vec<Review>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+/// Where a review is in its thread, see ``Database::get_review_thread``
+pub struct ReviewThread {
+    pub target_id: i64,
+    pub target_type: TargetType,
+    /// How many reviews are above this one, ``0`` for top level reviews
+    pub depth: i32,
+    pub has_owner_response: bool,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum ReviewReportReason {
+    #[default]
+    Spam = 0,
+    Abuse = 1, // Harassment, hate speech or slurs
+    OffTopic = 2, // Not about the bot or server
+    Misleading = 3, // Fake or paid reviews
+    Other = 4,
+}
+
+/// A report of a review
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReviewReport {
+    pub user_id: String,
+    pub reason: ReviewReportReason,
+    /// Required when the reason is ``Other``
+    pub context: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ReviewReportEntry {
+    pub user_id: String,
+    pub reason: ReviewReportReason,
+    pub context: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for ReviewReportEntry {
+    fn default() -> Self {
+        ReviewReportEntry {
+            user_id: "0".to_string(),
+            reason: ReviewReportReason::Spam,
+            context: None,
+            created_at: chrono::DateTime::<chrono::Utc>::from_utc(
+                chrono::NaiveDateTime::from_timestamp(0, 0),
+                chrono::Utc,
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReviewQueueQuery {
+    /// Only return hidden (or only visible) reviews
+    pub hidden: Option<bool>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// A reported or flagged review in the staff queue
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ReviewQueueEntry {
+    pub review: Review,
+    pub target_id: String,
+    pub target_type: TargetType,
+    /// Why the spam filter flagged this review, if it did
+    pub filter_reason: Option<String>,
+    /// Reports that have not been resolved yet
+    pub reports: Vec<ReviewReportEntry>,
+}
+
+impl Default for ReviewQueueEntry {
+    fn default() -> Self {
+        ReviewQueueEntry {
+            review: Review::default(),
+            target_id: "0".to_string(),
+            target_type: TargetType::Bot,
+            filter_reason: Some("too many links".to_string()),
+            reports: vec![ReviewReportEntry::default()],
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReviewQueueList {
+    pub reviews: Vec<ReviewQueueEntry>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct StaffReviewPath {
+    pub user_id: i64,
+    pub rid: uuid::Uuid,
+}
+
+#[derive(
+    Eq, TryFromPrimitive, Serialize_repr, Deserialize_repr, PartialEq, Clone, Copy, Debug, Default, EnumIter
+)]
+#[repr(i32)]
+pub enum ReviewModAction {
+    #[default]
+    Hide = 0,
+    Restore = 1, // Unhides and unflags the review
+    Delete = 2,
+}
+
+/// A staff action on a reported or flagged review
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct ReviewModeration {
+    pub action: ReviewModAction,
+    pub reason: Option<String>,
 }
 
 // Error Handling
@@ -1265,55 +3174,143 @@ pub enum ProfileRolesUpdate {
     DiscordError(#[serde(skip)] serenity::Error),
 }
 
-impl APIError for ProfileRolesUpdate {
+impl APIError for ProfileRolesUpdate {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::DiscordError(f) => Some(f.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum GenericError {
+    Forbidden, // Added
+    NotFound, // Added
+    InvalidFields, // Added
+    SQLError(#[serde(skip)] sqlx::Error),
+    APIBan(#[serde(skip)] String), // Added
+    InvalidCursor,
+    InvalidPage,
+}
+
+impl APIError for GenericError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::APIBan(s) => Some("The flag corresponding to your ban is: ".to_string() + s),
+            _ => None
+        }
+    }
+}
+
+/// Who a request is authenticated as, see ``auth``
+#[derive(Clone, Debug, PartialEq)]
+pub enum Principal {
+    User(i64),
+    Bot(i64),
+    Server(i64),
+    /// A custom client (using a ``Frostpaw.`` access token) acting for a user
+    FrostpawClient { user_id: i64, client_id: String },
+}
+
+/// A resolved ``Authorization`` token, see ``auth``
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedToken {
+    pub principal: Principal,
+    /// The scopes of a scoped API token, ``None`` for full access (``api_token``) tokens
+    pub scopes: Option<Vec<TokenScope>>,
+}
+
+impl ResolvedToken {
+    pub fn full(principal: Principal) -> Self {
+        ResolvedToken {
+            principal,
+            scopes: None,
+        }
+    }
+
+    /// Whether the token can be used for ``scope``
+    pub fn allows(&self, scope: TokenScope) -> bool {
+        match self.scopes {
+            Some(ref scopes) => scopes.contains(&scope),
+            None => true,
+        }
+    }
+}
+
+/// Authentication errors. These are always sent as a ``Forbidden`` with the variant as context
+#[derive(Serialize, Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    WrongTokenType,
+    WrongTarget,
+    /// A scoped API token was used without the scope needed
+    MissingScope,
+}
+
+impl APIError for AuthError {
     fn name(&self) -> String {
-        serde_json::to_string(self).unwrap_or_default()
+        // Kept as Forbidden for older clients
+        "Forbidden".to_string()
     }
 
     fn context(&self) -> Option<String> {
-        match self {
-            Self::SQLError(s) => Some(s.to_string()),
-            Self::DiscordError(f) => Some(f.to_string()),
-            _ => None,
-        }
+        serde_json::to_string(self).ok().map(|s| s.replace('"', ""))
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
     }
 }
 
 #[derive(Serialize, Debug)]
-pub enum GenericError {
-    Forbidden, // Added
-    NotFound, // Added
-    InvalidFields, // Added
-    SQLError(#[serde(skip)] sqlx::Error),
-    APIBan(#[serde(skip)] String), // Added
+pub enum NotifSubError {
+    TooManySubscriptions, // Added
 }
 
-impl APIError for GenericError {
+impl APIError for NotifSubError {
     fn name(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
     }
 
     fn context(&self) -> Option<String> {
-        match self {
-            Self::SQLError(s) => Some(s.to_string()),
-            Self::APIBan(s) => Some("The flag corresponding to your ban is: ".to_string() + s),
-            _ => None
-        }
+        None
     }
 }
 
 #[derive(Serialize, Debug)]
-pub enum NotifSubError {
-    TooManySubscriptions, // Added
+pub enum VoteReminderError {
+    SQLError(#[serde(skip)] sqlx::Error),
+    NotFound,
+    TargetNotFound,
+    TooManyReminders,
 }
 
-impl APIError for NotifSubError {
+impl APIError for VoteReminderError {
     fn name(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
     }
 
     fn context(&self) -> Option<String> {
-        None
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::NotFound => Some("This reminder could not be found".to_string()),
+            Self::TargetNotFound => Some("This bot or server could not be found".to_string()),
+            Self::TooManyReminders => Some("You can only have 50 vote reminders".to_string()),
+        }
     }
 }
 
@@ -1369,6 +3366,81 @@ impl APIError for OauthError {
     }
 }
 
+/// OAuth2 errors. Unlike other errors, these are sent as ``{"error": name, "error_description": context}``
+/// as clients expect RFC 6749 errors
+#[derive(Debug)]
+pub enum Oauth2Error {
+    InvalidRequest(String),
+    InvalidClient,
+    InvalidGrant(String),
+    UnauthorizedClient(String),
+    UnsupportedResponseType,
+    UnsupportedGrantType,
+    InvalidScope(String),
+    AccessDenied,
+    ServerError(sqlx::Error),
+}
+
+impl APIError for Oauth2Error {
+    fn name(&self) -> String {
+        match self {
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant(_) => "invalid_grant",
+            Self::UnauthorizedClient(_) => "unauthorized_client",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::InvalidScope(_) => "invalid_scope",
+            Self::AccessDenied => "access_denied",
+            Self::ServerError(_) => "server_error",
+        }
+        .to_string()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::InvalidRequest(s) | Self::InvalidGrant(s) | Self::UnauthorizedClient(s) | Self::InvalidScope(s) => Some(s.to_string()),
+            Self::ServerError(s) => Some(s.to_string()),
+            Self::InvalidClient => Some("Client authentication failed".to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Oauth2Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error())
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum FrostpawClientError {
+    SQLError(#[serde(skip)] sqlx::Error),
+    NameError,
+    InvalidDomain(#[serde(skip)] String),
+    InvalidPrivacyPolicy,
+    TooManyDomains,
+    TooManyClients,
+    NotFound,
+    AlreadyVerified,
+    VerificationPending,
+    VerificationReasonError,
+}
+
+impl APIError for FrostpawClientError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::InvalidDomain(s) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone)]
 pub enum BotActionMode {
     Add,
@@ -1421,6 +3493,11 @@ pub enum ReviewAddError {
     ParentReviewInvalid,
     ReviewAlreadyExists,
     ReviewAlreadyVoted(#[serde(skip)] String),
+    ReviewRejected(#[serde(skip)] Vec<String>),
+    ThreadTooDeep(#[serde(skip)] i32),
+    NotOwner,
+    OwnerResponseNotTopLevel,
+    OwnerResponseExists,
 }
 
 impl APIError for ReviewAddError {
@@ -1431,11 +3508,43 @@ impl APIError for ReviewAddError {
     fn context(&self) -> Option<String> {
         match self {
             Self::ReviewAlreadyVoted(button) => Some(format!("Click the {button} button to {button} it.", button = button)),
+            Self::ReviewRejected(reasons) => Some(format!("Your review looks like spam ({})", reasons.join(", "))),
+            Self::ThreadTooDeep(max_depth) => Some(format!("Replies can only be nested {} deep", max_depth)),
+            Self::NotOwner => Some("Only owners can respond to reviews".to_string()),
+            Self::OwnerResponseNotTopLevel => Some("Owners can only respond to top level reviews".to_string()),
+            Self::OwnerResponseExists => Some("This review already has a owner response, edit it instead".to_string()),
             _ => None
         }
     }
 }
 
+#[derive(Serialize, Debug)]
+pub enum ReviewReportError {
+    SQLError(#[serde(skip)] sqlx::Error),
+    NotFound,
+    OwnReview,
+    AlreadyReported,
+    ContextRequired,
+    ContextTooLong,
+}
+
+impl APIError for ReviewReportError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::NotFound => Some("This review could not be found".to_string()),
+            Self::OwnReview => Some("You cannot report your own review".to_string()),
+            Self::AlreadyReported => Some("You have already reported this review".to_string()),
+            Self::ContextRequired => Some("Please tell us what is wrong with this review".to_string()),
+            Self::ContextTooLong => Some("The context of a report can be at most 1000 characters".to_string()),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub enum CheckBotError {
     AlreadyExists, // Added
@@ -1568,6 +3677,80 @@ impl APIError for VoteBotError {
     }
 }
 
+#[derive(Serialize, Debug)]
+pub enum VoteReviewError {
+    SQLError(#[serde(skip)] sqlx::Error),
+    NotFound,
+    AlreadyReviewed,
+    VoteError(#[serde(skip)] VoteBotError),
+}
+
+impl APIError for VoteReviewError {
+    fn name(&self) -> String {
+        match self {
+            Self::VoteError(e) => e.name(),
+            _ => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::NotFound => Some("This vote could not be found".to_string()),
+            Self::AlreadyReviewed => Some("This vote has already been reviewed".to_string()),
+            Self::VoteError(e) => e.context(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum StaffActionError {
+    SQLError(#[serde(skip)] sqlx::Error),
+    NotFound,
+    InvalidState(#[serde(skip)] State),
+    ReasonRequired,
+    UnsupportedAction,
+}
+
+impl APIError for StaffActionError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::NotFound => Some("This bot or server could not be found".to_string()),
+            Self::InvalidState(state) => Some(format!("This action cannot be done on a {:?} bot or server", state)),
+            Self::ReasonRequired => Some("A reason is required for this action".to_string()),
+            Self::UnsupportedAction => {
+                Some("Only claim, unclaim, approve, deny, ban, certify and requeue are supported".to_string())
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum StaffPermError {
+    MissingCapability(#[serde(skip)] Capability),
+    BaypawError(#[serde(skip)] reqwest::Error),
+}
+
+impl APIError for StaffPermError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::MissingCapability(capability) => {
+                Some(format!("You need the {} capability to do this", capability.name()))
+            }
+            Self::BaypawError(e) => Some(e.to_string()),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub enum StatsError {
     BadStats(#[serde(skip)] String), // TODO
@@ -1593,6 +3776,71 @@ impl APIError for StatsError {
     }
 }
 
+#[derive(Serialize, Debug)]
+pub enum WebhookError {
+    SQLError(#[serde(skip)] sqlx::Error), // Added
+    NoWebhook, // Added
+    UnknownEvent, // Added
+    InvalidSecret, // Added
+}
+
+impl APIError for WebhookError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub enum ApiTokenError {
+    SQLError(#[serde(skip)] sqlx::Error), // Added
+    NameError, // Added
+    NoScopes, // Added
+    InvalidScope, // Added
+    ExpiryInPast, // Added
+    TooManyTokens, // Added
+    NotFound, // Added
+}
+
+impl APIError for ApiTokenError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        match self {
+            Self::SQLError(s) => Some(s.to_string()),
+            Self::NameError => Some("Token names must be between 1 and 64 characters".to_string()),
+            Self::InvalidScope => Some("One or more scopes cannot be used with this token type".to_string()),
+            Self::TooManyTokens => Some("You can only have 25 API tokens at a time".to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Errors returned by ``converters::verify_webhook``
+#[derive(Serialize, Debug, PartialEq)]
+pub enum WebhookSignatureError {
+    MalformedTimestamp,
+    Expired,
+    InvalidSignature,
+}
+
+impl APIError for WebhookSignatureError {
+    fn name(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn context(&self) -> Option<String> {
+        None
+    }
+}
 
 #[derive(Clone, PartialEq)]
 pub enum RouteAuthType {
@@ -1625,3 +3873,279 @@ pub struct RouteList<'a> {
     pub file_name: &'a str,
     pub routes: Vec<Route<'a>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(key: i64, id: &str, rev: bool) -> Cursor {
+        Cursor {
+            key,
+            id: id.to_string(),
+            rev,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        for c in [cursor(0, "", false), cursor(-42, "563808552288780322", true), cursor(i64::MAX, "a/b+c=", false)] {
+            let encoded = c.encode();
+
+            // Cursors are sent in query strings
+            assert!(encoded.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_'));
+            assert_eq!(Cursor::decode(&encoded), Some(c));
+        }
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert_eq!(Cursor::decode("not a cursor"), None);
+        assert_eq!(Cursor::decode(&base64::encode_config("{}", base64::URL_SAFE_NO_PAD)), None);
+        assert!(matches!(Cursor::from_query(&None), Ok(None)));
+        assert!(Cursor::from_query(&Some("%%%".to_string())).is_err());
+    }
+
+    #[test]
+    fn cursor_limit_is_clamped() {
+        assert_eq!(Cursor::limit(None, 25), 25);
+        assert_eq!(Cursor::limit(Some(0), 25), 1);
+        assert_eq!(Cursor::limit(Some(-5), 25), 1);
+        assert_eq!(Cursor::limit(Some(1000), 25), 100);
+    }
+
+    #[test]
+    fn paginate_forwards() {
+        let key = |row: &i64| (*row, row.to_string());
+
+        // limit + 1 rows means there is a next page
+        let page = Cursor::paginate(vec![5, 4, 3], 2, None, key);
+        assert_eq!(page.items, vec![5, 4]);
+        assert_eq!(page.prev_cursor, None);
+        assert_eq!(Cursor::decode(&page.next_cursor.unwrap()), Some(cursor(4, "4", false)));
+
+        let next = cursor(4, "4", false);
+        let page = Cursor::paginate(vec![3], 2, Some(&next), key);
+        assert_eq!(page.items, vec![3]);
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(Cursor::decode(&page.prev_cursor.unwrap()), Some(cursor(3, "3", true)));
+    }
+
+    #[test]
+    fn paginate_backwards() {
+        let key = |row: &i64| (*row, row.to_string());
+
+        // Walking backwards fetches rows in ascending order
+        let prev = cursor(3, "3", true);
+        let page = Cursor::paginate(vec![4, 5, 6], 2, Some(&prev), key);
+        assert_eq!(page.items, vec![5, 4]);
+        assert_eq!(Cursor::decode(&page.next_cursor.unwrap()), Some(cursor(4, "4", false)));
+        assert_eq!(Cursor::decode(&page.prev_cursor.unwrap()), Some(cursor(5, "5", true)));
+
+        let page = Cursor::paginate(vec![4, 5], 2, Some(&prev), key);
+        assert_eq!(page.items, vec![5, 4]);
+        assert_eq!(page.prev_cursor, None);
+    }
+
+    #[test]
+    fn search_limits() {
+        let search = |page: Option<i64>, per_page: Option<i64>| SearchQuery {
+            page,
+            per_page,
+            ..Default::default()
+        };
+
+        assert_eq!(search(None, None).limits().ok(), Some((6, 0)));
+        assert_eq!(search(Some(-3), Some(1000)).limits().ok(), Some((50, 0)));
+        assert_eq!(search(Some(SearchQuery::MAX_PAGE), Some(50)).limits().ok(), Some((50, 49950)));
+
+        // Used to overflow
+        assert!(matches!(search(Some(SearchQuery::MAX_PAGE + 1), None).limits(), Err(GenericError::InvalidPage)));
+        assert!(matches!(search(Some(i64::MAX), Some(50)).limits(), Err(GenericError::InvalidPage)));
+    }
+
+    #[test]
+    fn optional_config_falls_back_to_default() {
+        let dir = env::temp_dir().into_os_string().into_string().unwrap() + "/";
+        std::fs::write(dir.clone() + "optional_config_test.json", r#"{"a": 1}"#).unwrap();
+
+        let config: HashMap<String, i64> = load_optional_config(&dir, "optional_config_test.json");
+        assert_eq!(config.get("a"), Some(&1));
+
+        let config: HashMap<String, i64> = load_optional_config(&dir, "optional_config_missing.json");
+        assert!(config.is_empty());
+    }
+
+    #[test]
+    fn only_views_are_unsequenced() {
+        let unsequenced: Vec<EventName> = EventName::iter().filter(|event| !event.sequenced()).collect();
+        assert_eq!(unsequenced, vec![EventName::BotView, EventName::ServerView]);
+    }
+
+    fn utc(day: u32, hour: u32) -> chrono::DateTime<chrono::Utc> {
+        use chrono::TimeZone;
+
+        // July 2022, the 2nd and 3rd are a weekend
+        chrono::Utc.ymd(2022, 7, day).and_hms(hour, 0, 0)
+    }
+
+    fn vote_event(multiplier: i64, starts_at: chrono::DateTime<chrono::Utc>, ends_at: chrono::DateTime<chrono::Utc>) -> VoteEvent {
+        VoteEvent {
+            name: format!("x{}", multiplier),
+            starts_at,
+            ends_at,
+            multiplier,
+        }
+    }
+
+    #[test]
+    fn vote_multiplier_defaults_to_one() {
+        let rules = VoteRules::default();
+
+        assert_eq!(rules.multiplier(State::Approved, utc(4, 12)), 1);
+        assert_eq!(rules.multiplier(State::Approved, utc(2, 12)), 1);
+    }
+
+    #[test]
+    fn vote_multiplier_on_weekends() {
+        let rules = VoteRules {
+            weekend_multiplier: 2,
+            ..VoteRules::default()
+        };
+
+        assert_eq!(rules.multiplier(State::Approved, utc(1, 23)), 1);
+        assert_eq!(rules.multiplier(State::Approved, utc(2, 0)), 2);
+        assert_eq!(rules.multiplier(State::Approved, utc(3, 23)), 2);
+        assert_eq!(rules.multiplier(State::Approved, utc(4, 0)), 1);
+    }
+
+    #[test]
+    fn vote_multiplier_uses_the_highest_active_event() {
+        let rules = VoteRules {
+            weekend_multiplier: 2,
+            events: vec![vote_event(3, utc(3, 0), utc(5, 0)), vote_event(5, utc(4, 0), utc(4, 12))],
+            ..VoteRules::default()
+        };
+
+        // Multipliers do not stack
+        assert_eq!(rules.multiplier(State::Approved, utc(2, 12)), 2);
+        assert_eq!(rules.multiplier(State::Approved, utc(3, 12)), 3);
+        assert_eq!(rules.multiplier(State::Approved, utc(4, 0)), 5);
+        assert_eq!(rules.multiplier(State::Approved, utc(4, 12)), 3);
+
+        // Events end at ends_at
+        assert_eq!(rules.multiplier(State::Approved, utc(5, 0)), 1);
+    }
+
+    #[test]
+    fn vote_multiplier_is_capped_by_state() {
+        let rules = VoteRules {
+            weekend_multiplier: 4,
+            state_caps: vec![
+                VoteStateCap {
+                    state: State::Certified,
+                    max_multiplier: 2,
+                },
+                VoteStateCap {
+                    state: State::Banned,
+                    max_multiplier: -1,
+                },
+            ],
+            ..VoteRules::default()
+        };
+
+        assert_eq!(rules.multiplier(State::Approved, utc(2, 12)), 4);
+        assert_eq!(rules.multiplier(State::Certified, utc(2, 12)), 2);
+        assert_eq!(rules.multiplier(State::Certified, utc(4, 12)), 1);
+
+        // A vote never counts negatively
+        assert_eq!(rules.multiplier(State::Banned, utc(2, 12)), 0);
+    }
+
+    fn review_filter() -> ReviewFilterConfig {
+        ReviewFilterConfig {
+            blocked_words: vec!["".to_string(), "scam".to_string(), "free nitro".to_string()],
+            ..ReviewFilterConfig::default()
+        }
+    }
+
+    #[test]
+    fn review_filter_passes_normal_reviews() {
+        let result = review_filter().score("Great bot, the music commands work well and the support server is helpful");
+
+        assert_eq!(result.score, 0);
+        assert!(result.reasons.is_empty());
+    }
+
+    #[test]
+    fn review_filter_can_be_disabled() {
+        let filter = ReviewFilterConfig {
+            enabled: false,
+            ..review_filter()
+        };
+
+        assert_eq!(filter.score("FREE NITRO SCAM!!!!!!!!!!!!").score, 0);
+    }
+
+    #[test]
+    fn review_filter_blocked_words() {
+        let filter = review_filter();
+
+        let result = filter.score("Get your Free Nitro here");
+        assert_eq!(result.score, filter.blocked_word_score);
+        assert_eq!(result.reasons, vec!["blocked word"]);
+
+        // Only counted once, however many blocked words there are
+        assert_eq!(filter.score("free nitro scam").score, filter.blocked_word_score);
+    }
+
+    #[test]
+    fn review_filter_links() {
+        let filter = review_filter();
+
+        assert_eq!(filter.score("see http://a.example and https://b.example").score, 0);
+
+        let result = filter.score("http://a.example HTTPS://b.example https://c.example");
+        assert_eq!(result.score, filter.link_score);
+        assert_eq!(result.reasons, vec!["too many links"]);
+    }
+
+    #[test]
+    fn review_filter_capital_letters() {
+        let filter = review_filter();
+
+        // Short reviews are allowed to shout
+        assert_eq!(filter.score("BEST BOT EVER").score, 0);
+        assert_eq!(filter.score("This Is The Best Bot I Have Ever Used On Discord").score, 0);
+
+        let result = filter.score("THIS IS THE BEST BOT I HAVE EVER USED");
+        assert_eq!(result.score, filter.caps_score);
+        assert_eq!(result.reasons, vec!["too many capital letters"]);
+    }
+
+    #[test]
+    fn review_filter_repeated_characters() {
+        let filter = review_filter();
+
+        assert_eq!(filter.score("sooooooooo good").score, 0);
+        assert_eq!(filter.score(&format!("good{}bot", " ".repeat(20))).score, 0);
+
+        let result = filter.score("soooooooooo good");
+        assert_eq!(result.score, filter.repeat_score);
+        assert_eq!(result.reasons, vec!["repeated characters"]);
+    }
+
+    #[test]
+    fn review_filter_scores_add_up() {
+        let filter = review_filter();
+        let result = filter.score("SCAM SCAM SCAM HTTP://A.EXAMPLE HTTP://B.EXAMPLE HTTP://C.EXAMPLE !!!!!!!!!!!!");
+
+        assert_eq!(
+            result.score,
+            filter.blocked_word_score + filter.link_score + filter.caps_score + filter.repeat_score
+        );
+        assert_eq!(
+            result.reasons,
+            vec!["blocked word", "too many links", "too many capital letters", "repeated characters"]
+        );
+    }
+}
@@ -1,6 +1,6 @@
+use crate::auth;
 use crate::models;
-use actix_web::{http, get, post, web, HttpRequest, HttpResponse};
-use actix_web::http::header::HeaderValue;
+use actix_web::{http, delete, get, post, web, HttpRequest, HttpResponse, ResponseError};
 
 #[get("/notifications/info")]
 async fn get_notif_info(req: HttpRequest) -> HttpResponse {
@@ -14,46 +14,85 @@ async fn get_notif_info(req: HttpRequest) -> HttpResponse {
 pub async fn subscribe(
     req: HttpRequest, 
     id: web::Path<models::FetchBotPath>,
-    notif: web::Json<models::NotificationSub>
+    notif: web::Json<models::NotificationSub>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(id.id, auth).await {
-        let notif_res = data.database.subscribe_notifs(id.id, notif.into_inner()).await;
-
-        if notif_res.is_ok() {
-            return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-        } else if let Err(e) = notif_res {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&e));
-        }
-    }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    match data.database.subscribe_notifs(id.id, notif.into_inner()).await {
+        Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
+        Err(e) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&e)),
+    }
 }
 
 #[get("/notifications/{id}/test")]
 pub async fn test_notifs(
     req: HttpRequest, 
     id: web::Path<models::FetchBotPath>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    data.database.test_notifs(id.id).await;
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok())
+}
+
+/// Lists the vote reminders of a user
+#[get("/users/{id}/reminders")]
+pub async fn get_vote_reminders(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    HttpResponse::build(http::StatusCode::OK).json(data.database.get_vote_reminders(id.id).await)
+}
+
+/// Opts in to vote reminders for a bot or server
+#[post("/users/{id}/reminders")]
+pub async fn create_vote_reminder(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    reminder: web::Json<models::CreateVoteReminder>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    match data.database.create_vote_reminder(id.id, &reminder).await {
+        Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
+        Err(models::VoteReminderError::TargetNotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::VoteReminderError::TargetNotFound)),
+        Err(e) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&e)),
+    }
+}
+
+/// Cancels a vote reminder
+#[delete("/users/{user_id}/reminders/{reminder_id}")]
+pub async fn delete_vote_reminder(
+    req: HttpRequest,
+    info: web::Path<models::UserReminderPath>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(id.id, auth).await {
-        data.database.test_notifs(id.id).await;
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-    }
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
-}
\ No newline at end of file
+    if let Err(err) = auth.ensure(info.user_id) {
+        return err.error_response();
+    }
+
+    match data.database.delete_vote_reminder(info.user_id, info.reminder_id).await {
+        Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
+        Err(models::VoteReminderError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::VoteReminderError::NotFound)),
+        Err(e) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&e)),
+    }
+}
@@ -0,0 +1,329 @@
+// OAuth2 authorization server for Frostpaw clients (RFC 6749 authorization code flow with
+// PKCE as per RFC 7636, token introspection as per RFC 7662 and revocation as per RFC 7009)
+use crate::auth;
+use crate::converters;
+use crate::models;
+use crate::models::APIError;
+use actix_web::{get, http, post, web, HttpRequest, HttpResponse, ResponseError};
+use log::error;
+use ring::constant_time;
+use serde_json::json;
+
+impl ResponseError for models::Oauth2Error {
+    fn status_code(&self) -> http::StatusCode {
+        match self {
+            models::Oauth2Error::InvalidClient => http::StatusCode::UNAUTHORIZED,
+            models::Oauth2Error::ServerError(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
+            _ => http::StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(json!({
+            "error": self.name(),
+            "error_description": self.context(),
+        }))
+    }
+}
+
+/// Checks that ``redirect_uri`` is on one of the redirect domains of the client. Only ``https`` is
+/// allowed except for ``localhost`` so clients can be tested locally
+fn check_redirect_uri(client: &models::FrostpawClient, redirect_uri: &str) -> Result<reqwest::Url, models::Oauth2Error> {
+    let url = reqwest::Url::parse(redirect_uri)
+        .map_err(|_| models::Oauth2Error::InvalidRequest("redirect_uri is not a valid URL".to_string()))?;
+
+    let host = url.host_str().unwrap_or_default();
+    let authority = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+
+    let allowed = client
+        .redirect_domains
+        .iter()
+        .any(|domain| domain == host || *domain == authority);
+
+    let secure = url.scheme() == "https" || (url.scheme() == "http" && (host == "localhost" || host == "127.0.0.1"));
+
+    if !secure || url.fragment().is_some() || !allowed {
+        return Err(models::Oauth2Error::InvalidRequest(
+            "redirect_uri is not on a redirect domain of this client".to_string(),
+        ));
+    }
+
+    Ok(url)
+}
+
+/// Validates a authorization request, returning the client, the scopes asked for and where to redirect to
+async fn check_authorize(
+    data: &models::AppState,
+    query: &models::Oauth2AuthorizeQuery,
+) -> Result<(models::FrostpawClient, Vec<models::TokenScope>, reqwest::Url), models::Oauth2Error> {
+    if query.response_type != "code" {
+        return Err(models::Oauth2Error::UnsupportedResponseType);
+    }
+
+    let client = data
+        .database
+        .get_frostpaw_client(&query.client_id)
+        .await
+        .ok_or_else(|| models::Oauth2Error::InvalidRequest("Unknown client_id".to_string()))?;
+
+    let redirect_uri = check_redirect_uri(&client, &query.redirect_uri)?;
+
+    // PKCE is required for all clients
+    match (&query.code_challenge, query.code_challenge_method.as_deref()) {
+        (Some(challenge), Some("S256")) if challenge.len() == 43 => {}
+        (None, _) => {
+            return Err(models::Oauth2Error::InvalidRequest("code_challenge is required".to_string()));
+        }
+        (Some(_), Some("S256")) => {
+            return Err(models::Oauth2Error::InvalidRequest("code_challenge is not a S256 challenge".to_string()));
+        }
+        _ => {
+            return Err(models::Oauth2Error::InvalidRequest(
+                "Only the S256 code_challenge_method is supported".to_string(),
+            ));
+        }
+    }
+
+    let mut scopes = Vec::new();
+
+    for name in query.scope.split_whitespace() {
+        match models::TokenScope::from_name(name) {
+            Some(scope) if scope.valid_for(models::TokenType::User) => {
+                if !scopes.contains(&scope) {
+                    scopes.push(scope);
+                }
+            }
+            _ => return Err(models::Oauth2Error::InvalidScope(format!("Unknown scope {}", name))),
+        }
+    }
+
+    Ok((client, scopes, redirect_uri))
+}
+
+/// Authenticates a client using HTTP Basic auth or ``client_id``/``client_secret`` in the form body.
+/// Clients with a secret must always send it. If ``allow_public`` is true, public clients (clients without
+/// a secret, which rely on PKCE instead) are also allowed
+async fn check_client(
+    req: &HttpRequest,
+    data: &models::AppState,
+    client_id: &Option<String>,
+    client_secret: &Option<String>,
+    allow_public: bool,
+) -> Result<models::FrostpawClient, models::Oauth2Error> {
+    let basic = req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Basic "))
+        .and_then(|creds| base64::decode(creds.trim()).ok())
+        .and_then(|creds| String::from_utf8(creds).ok())
+        .and_then(|creds| {
+            creds
+                .split_once(':')
+                .map(|(id, secret)| (id.to_string(), secret.to_string()))
+        });
+
+    let (client_id, client_secret) = match basic {
+        Some((id, secret)) => (Some(id), Some(secret)),
+        None => (client_id.clone(), client_secret.clone()),
+    };
+
+    let client = match client_id {
+        Some(client_id) => data.database.get_frostpaw_client(&client_id).await,
+        None => None,
+    }
+    .ok_or(models::Oauth2Error::InvalidClient)?;
+
+    if client.secret.is_empty() {
+        if !allow_public {
+            return Err(models::Oauth2Error::InvalidClient);
+        }
+
+        return Ok(client);
+    }
+
+    match client_secret {
+        Some(secret) if constant_time::verify_slices_are_equal(secret.as_bytes(), client.secret.as_bytes()).is_ok() => {
+            Ok(client)
+        }
+        _ => Err(models::Oauth2Error::InvalidClient),
+    }
+}
+
+/// Returns the client and scopes of a authorization request for the consent page
+#[get("/frostpaw/oauth2/authorize")]
+async fn oauth2_authorize_info(req: HttpRequest, query: web::Query<models::Oauth2AuthorizeQuery>) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    match check_authorize(data, &query).await {
+        Ok((client, scopes, redirect_uri)) => HttpResponse::Ok().json(models::Oauth2AuthorizeInfo {
+            client,
+            scopes,
+            redirect_uri: redirect_uri.to_string(),
+        }),
+        Err(err) => err.error_response(),
+    }
+}
+
+/// Approves or denies a authorization request, returning where the user should be redirected to
+#[post("/frostpaw/oauth2/authorize")]
+async fn oauth2_authorize(
+    req: HttpRequest,
+    query: web::Query<models::Oauth2AuthorizeQuery>,
+    consent: web::Json<models::Oauth2Consent>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    // Clients cannot authorize other clients
+    if auth.client_id.is_some() {
+        return models::AuthError::WrongTokenType.error_response();
+    }
+
+    if let Err(err) = auth.ensure(auth.user_id) {
+        return err.error_response();
+    }
+
+    let (client, scopes, mut redirect_uri) = match check_authorize(data, &query).await {
+        Ok(authorize) => authorize,
+        Err(err) => return err.error_response(),
+    };
+
+    if consent.approve {
+        let code = data
+            .database
+            .create_oauth2_code(
+                &client.id,
+                auth.user_id,
+                &query.redirect_uri,
+                &scopes,
+                query.code_challenge.as_deref().unwrap_or_default(),
+            )
+            .await;
+
+        match code {
+            Ok(code) => {
+                redirect_uri.query_pairs_mut().append_pair("code", &code);
+            }
+            Err(err) => {
+                error!("Failed to create authorization code: {}", err);
+                return models::Oauth2Error::ServerError(err).error_response();
+            }
+        }
+    } else {
+        redirect_uri
+            .query_pairs_mut()
+            .append_pair("error", &models::Oauth2Error::AccessDenied.name());
+    }
+
+    if let Some(ref state) = query.state {
+        redirect_uri.query_pairs_mut().append_pair("state", state);
+    }
+
+    HttpResponse::Ok().json(models::Oauth2Redirect {
+        redirect_uri: redirect_uri.to_string(),
+    })
+}
+
+/// Exchanges a authorization code for a token
+async fn exchange_code(
+    data: &models::AppState,
+    client: &models::FrostpawClient,
+    form: &models::Oauth2TokenRequest,
+    origin: &models::SessionOrigin,
+) -> Result<models::Oauth2TokenResponse, models::Oauth2Error> {
+    let (code, redirect_uri, code_verifier) = match (&form.code, &form.redirect_uri, &form.code_verifier) {
+        (Some(code), Some(redirect_uri), Some(code_verifier)) => (code, redirect_uri, code_verifier),
+        _ => {
+            return Err(models::Oauth2Error::InvalidRequest(
+                "code, redirect_uri and code_verifier are required".to_string(),
+            ))
+        }
+    };
+
+    let code = data
+        .database
+        .consume_oauth2_code(code)
+        .await
+        .ok_or_else(|| models::Oauth2Error::InvalidGrant("Invalid authorization code".to_string()))?;
+
+    if code.expires_at < chrono::Utc::now() {
+        return Err(models::Oauth2Error::InvalidGrant("Authorization code has expired".to_string()));
+    }
+
+    if code.client_id != client.id || &code.redirect_uri != redirect_uri {
+        return Err(models::Oauth2Error::InvalidGrant(
+            "Authorization code was not issued to this client or redirect_uri".to_string(),
+        ));
+    }
+
+    if !converters::verify_pkce(code_verifier, &code.code_challenge) {
+        return Err(models::Oauth2Error::InvalidGrant("code_verifier does not match the code_challenge".to_string()));
+    }
+
+    data.database
+        .create_oauth2_token(&client.id, code.user_id, Some(&code.scopes), origin)
+        .await
+        .map_err(models::Oauth2Error::ServerError)
+}
+
+/// Issues a token using a authorization code or a refresh token
+#[post("/frostpaw/oauth2/token")]
+async fn oauth2_token(req: HttpRequest, form: web::Form<models::Oauth2TokenRequest>) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let client = match check_client(&req, data, &form.client_id, &form.client_secret, true).await {
+        Ok(client) => client,
+        Err(err) => return err.error_response(),
+    };
+
+    let origin = converters::session_origin(&req);
+
+    let token = match form.grant_type.as_str() {
+        "authorization_code" => exchange_code(data, &client, &form, &origin).await,
+        "refresh_token" => match form.refresh_token {
+            Some(ref refresh_token) => data.database.refresh_oauth2_token(&client.id, refresh_token, &origin).await,
+            None => Err(models::Oauth2Error::InvalidRequest("refresh_token is required".to_string())),
+        },
+        _ => Err(models::Oauth2Error::UnsupportedGrantType),
+    };
+
+    match token {
+        Ok(token) => HttpResponse::Ok()
+            .insert_header((http::header::CACHE_CONTROL, "no-store"))
+            .json(token),
+        Err(err) => err.error_response(),
+    }
+}
+
+/// Returns whether a token is active and what it can do. Clients can only introspect their own tokens
+#[post("/frostpaw/oauth2/introspect")]
+async fn oauth2_introspect(req: HttpRequest, form: web::Form<models::Oauth2TokenQuery>) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let client = match check_client(&req, data, &form.client_id, &form.client_secret, false).await {
+        Ok(client) => client,
+        Err(err) => return err.error_response(),
+    };
+
+    HttpResponse::Ok().json(data.database.introspect_oauth2_token(&client.id, &form.token).await)
+}
+
+/// Revokes a access or refresh token. Unknown tokens are ignored as per RFC 7009
+#[post("/frostpaw/oauth2/revoke")]
+async fn oauth2_revoke(req: HttpRequest, form: web::Form<models::Oauth2TokenQuery>) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let client = match check_client(&req, data, &form.client_id, &form.client_secret, true).await {
+        Ok(client) => client,
+        Err(err) => return err.error_response(),
+    };
+
+    match data.database.revoke_oauth2_token(&client.id, &form.token).await {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(err) => models::Oauth2Error::ServerError(err).error_response(),
+    }
+}
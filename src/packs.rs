@@ -1,8 +1,8 @@
 
 // Endpoints to create, delete and edit packs
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
-use actix_web::{delete, patch, post, web, http, HttpRequest, HttpResponse};
+use actix_web::{delete, patch, post, web, http, HttpRequest, HttpResponse, ResponseError};
 use log::error;
 
 async fn pack_check(
@@ -50,21 +50,14 @@ async fn add_pack(
     req: HttpRequest,
     info: web::Path<models::FetchBotPath>,
     mut pack: web::Json<models::BotPack>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.id;
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Pack Add Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     pack.owner.id = user_id.to_string();
@@ -74,15 +67,27 @@ async fn add_pack(
     let res = pack_check(data, &mut pack).await;
 
     if res.is_err() {
-        HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
     }
 
+    let name = pack.name.clone();
+
     let res = data.database.add_pack(pack).await;
 
     if res.is_err() {
-        HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
     }
 
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: user_id,
+            target_type: models::AuditTargetType::User,
+            actor_id: user_id,
+            action: models::AuditAction::AddPack,
+            context: Some(name),
+        })
+        .await;
+
     HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
@@ -91,21 +96,14 @@ async fn edit_pack(
     req: HttpRequest,
     info: web::Path<models::FetchBotPath>,
     pack: web::Json<models::BotPack>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.id;
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Pack Edit Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     // Make sure we are the owner of this pack
@@ -124,35 +122,38 @@ async fn edit_pack(
     let res = pack_check(data, &mut pack).await;
 
     if res.is_err() {
-        HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
     }
 
+    let pack_id = pack.id.clone();
+
     let res = data.database.edit_pack(pack).await;
 
     if res.is_err() {
-        HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
-
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&res.unwrap_err()));
     }
 
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: user_id,
+            target_type: models::AuditTargetType::User,
+            actor_id: user_id,
+            action: models::AuditAction::EditPack,
+            context: Some(pack_id),
+        })
+        .await;
+
     HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 #[delete("/users/{user_id}/packs/{pack_id}")]
-async fn delete_pack(req: HttpRequest, info: web::Path<models::GetUserPackPath>) -> HttpResponse {
+async fn delete_pack(req: HttpRequest, info: web::Path<models::GetUserPackPath>, auth: auth::UserAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Pack Delete Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     // Make sure we are the owner of this pack
@@ -168,5 +169,15 @@ async fn delete_pack(req: HttpRequest, info: web::Path<models::GetUserPackPath>)
 
     data.database.delete_pack(info.pack_id.clone()).await;
 
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: user_id,
+            target_type: models::AuditTargetType::User,
+            actor_id: user_id,
+            action: models::AuditAction::DeletePack,
+            context: Some(info.pack_id.clone()),
+        })
+        .await;
+
     HttpResponse::Ok().json(models::APIResponse::ok())
 }
@@ -0,0 +1,91 @@
+// Staff permissions. Staff can do what the capabilities (``models::Capability``) of their baypaw perm level allow
+//
+// Routes only staff can use are wrapped in ``Staff`` which checks the capability before the route runs.
+// Routes needing a capability depending on the request (such as the action in a staff action) use ``require``
+use crate::auth;
+use crate::models;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{http, web, Error, HttpResponse, ResponseError};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+
+/// Checks that a user has a capability
+pub async fn require(data: &models::AppState, user_id: i64, capability: models::Capability) -> Result<(), HttpResponse> {
+    if !data.database.has_capability(user_id, capability).await {
+        return Err(HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(
+            &models::StaffPermError::MissingCapability(capability),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Only lets users with this capability through, use as ``#[get("/path", wrap = "perms::Staff(...)")]``
+pub struct Staff(pub models::Capability);
+
+impl<S, B> Transform<S, ServiceRequest> for Staff
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = StaffMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(StaffMiddleware {
+            service: Rc::new(service),
+            capability: self.0,
+        }))
+    }
+}
+
+pub struct StaffMiddleware<S> {
+    service: Rc<S>,
+    capability: models::Capability,
+}
+
+impl<S, B> Service<ServiceRequest> for StaffMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let capability = self.capability;
+
+        Box::pin(async move {
+            let data = req.app_data::<web::Data<models::AppState>>().unwrap().clone();
+
+            // Staff routes can only be used with the full access token of the user, never by custom clients or scoped tokens
+            let user_id = match auth::authenticate(req.request(), auth::TokenKind::User).await {
+                Ok(resolved) => match (resolved.principal, resolved.scopes) {
+                    (models::Principal::User(user_id), None) => user_id,
+                    _ => {
+                        let res = models::AuthError::WrongTokenType.error_response();
+                        return Ok(req.into_response(res).map_into_right_body());
+                    }
+                },
+                Err(err) => {
+                    let res = err.error_response();
+                    return Ok(req.into_response(res).map_into_right_body());
+                }
+            };
+
+            if let Err(res) = require(&data, user_id, capability).await {
+                return Ok(req.into_response(res).map_into_right_body());
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
@@ -0,0 +1,270 @@
+// Rate limiting middleware, counters are kept in redis so limits are shared between workers
+//
+// Every request is counted against the quota of its route group (``models::RouteGroup``) for
+// whoever sent it. Requests with a token that a route has already accepted are limited per
+// user/bot/server (with higher quotas for certified bots and staff), anything else is limited by IP.
+//
+// Tokens are never looked up here, the route resolves them as usual (see ``auth``) and the
+// principal it resolved to is remembered by token hash for the next request
+use crate::converters;
+use crate::models;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+use actix_web::{http, web, Error, HttpMessage, HttpResponse};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::net::IpAddr;
+use std::rc::Rc;
+
+/// Gets the route group of a request
+fn route_group(method: &http::Method, path: &str) -> models::RouteGroup {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (&http::Method::PATCH, ["users", _, "bots" | "servers", _, "votes"]) => models::RouteGroup::Votes,
+        (&http::Method::PATCH, ["reviews", _, "votes"]) => models::RouteGroup::ReviewVotes,
+        (&http::Method::POST | &http::Method::PATCH | &http::Method::DELETE, ["reviews", ..]) => models::RouteGroup::Reviews,
+        (&http::Method::GET, ["search" | "search-tags"]) => models::RouteGroup::Search,
+        (&http::Method::POST, ["bots", _, "stats"]) => models::RouteGroup::Stats,
+        (_, ["ws", ..]) => models::RouteGroup::Gateway,
+        (_, ["oauth2"]) | (_, ["frostpaw", "oauth2", ..]) | (_, ["frostpaw", "clients", _, "refresh"]) => {
+            models::RouteGroup::Login
+        }
+        _ => models::RouteGroup::Global,
+    }
+}
+
+/// Gets the IP to limit a request by. ``X-Forwarded-For`` is only used if the peer is a trusted proxy, in
+/// which case the last address not added by a trusted proxy is the client
+fn client_ip(peer: Option<IpAddr>, forwarded_for: Option<&str>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let mut ip = peer?;
+
+    if !trusted_proxies.contains(&ip) {
+        return Some(ip);
+    }
+
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(hop) => {
+                ip = hop;
+
+                if !trusted_proxies.contains(&hop) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    Some(ip)
+}
+
+fn principal_key(principal: &models::Principal) -> String {
+    match principal {
+        models::Principal::User(user_id) => format!("user:{}", user_id),
+        models::Principal::Bot(bot_id) => format!("bot:{}", bot_id),
+        models::Principal::Server(guild_id) => format!("server:{}", guild_id),
+        models::Principal::FrostpawClient { user_id, client_id } => format!("client:{}:{}", client_id, user_id),
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, group: models::RouteGroup, status: &models::RatelimitStatus) {
+    headers.insert(HeaderName::from_static("x-ratelimit-limit"), HeaderValue::from(status.limit));
+    headers.insert(HeaderName::from_static("x-ratelimit-remaining"), HeaderValue::from(status.remaining));
+    headers.insert(HeaderName::from_static("x-ratelimit-reset"), HeaderValue::from(status.reset));
+    headers.insert(HeaderName::from_static("x-ratelimit-bucket"), HeaderValue::from_static(group.as_str()));
+}
+
+/// Rate limits all routes, see the module docs
+pub struct Ratelimiter;
+
+impl<S, B> Transform<S, ServiceRequest> for Ratelimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RatelimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RatelimitMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RatelimitMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RatelimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let data = req.app_data::<web::Data<models::AppState>>().unwrap().clone();
+
+            if !data.config.ratelimits.enabled || req.method() == http::Method::OPTIONS {
+                return service.call(req).await.map(ServiceResponse::map_into_left_body);
+            }
+
+            let group = route_group(req.method(), req.path());
+
+            let token = req
+                .headers()
+                .get("Authorization")
+                .and_then(|header| header.to_str().ok())
+                .map(|token| token.trim())
+                .filter(|token| !token.is_empty())
+                .map(converters::hash_scoped_token);
+
+            // Unknown (or invalid) tokens are limited by IP, otherwise sending random tokens would get a new quota every time
+            let cached = match token {
+                Some(ref token) => data.database.ratelimit_token_cache.get(token),
+                None => None,
+            };
+
+            let known_token = cached.is_some();
+
+            let (key, tier) = match cached {
+                Some(cached) => cached,
+                None => {
+                    let ip = client_ip(
+                        req.peer_addr().map(|addr| addr.ip()),
+                        req.headers().get("X-Forwarded-For").and_then(|header| header.to_str().ok()),
+                        &data.config.ratelimits.trusted_proxies,
+                    )
+                    .map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                    ("ip:".to_string() + &ip, models::RatelimitTier::Ip)
+                }
+            };
+
+            let quota = data.config.ratelimits.rule(group).quota(tier);
+
+            // If redis is down, let the request through
+            let status = data
+                .database
+                .ratelimit_hit(&format!("{}:{}", group.as_str(), key), quota)
+                .await;
+
+            if let Some(ref status) = status {
+                if status.retry_after > 0 {
+                    let mut res = HttpResponse::build(http::StatusCode::TOO_MANY_REQUESTS)
+                        .insert_header((http::header::RETRY_AFTER, status.retry_after))
+                        .json(models::APIResponse::rl(status.retry_after as i64));
+
+                    set_headers(res.headers_mut(), group, status);
+
+                    return Ok(req.into_response(res).map_into_right_body());
+                }
+            }
+
+            let mut res = service.call(req).await?;
+
+            // The route accepted the token, so limit by its principal from now on
+            if let (Some(token), false) = (token, known_token) {
+                let resolved = res.request().extensions().get::<models::ResolvedToken>().cloned();
+
+                if let Some(resolved) = resolved {
+                    let tier = data.database.get_ratelimit_tier(&resolved.principal).await;
+
+                    data.database
+                        .ratelimit_token_cache
+                        .insert(token, (principal_key(&resolved.principal), tier))
+                        .await;
+                }
+            }
+
+            if let Some(ref status) = status {
+                set_headers(res.headers_mut(), group, status);
+            }
+
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_groups() {
+        let cases = [
+            (http::Method::PATCH, "/users/1/bots/2/votes", models::RouteGroup::Votes),
+            (http::Method::PATCH, "/users/1/servers/2/votes", models::RouteGroup::Votes),
+            (http::Method::GET, "/users/1/bots/2/votes", models::RouteGroup::Global),
+            (http::Method::PATCH, "/reviews/abc/votes", models::RouteGroup::ReviewVotes),
+            (http::Method::POST, "/reviews/1", models::RouteGroup::Reviews),
+            (http::Method::PATCH, "/reviews/1", models::RouteGroup::Reviews),
+            (http::Method::DELETE, "/reviews/abc", models::RouteGroup::Reviews),
+            (http::Method::POST, "/reviews/abc/reports", models::RouteGroup::Reviews),
+            (http::Method::GET, "/reviews/abc/replies", models::RouteGroup::Global),
+            (http::Method::GET, "/search", models::RouteGroup::Search),
+            (http::Method::GET, "/search/", models::RouteGroup::Search),
+            (http::Method::GET, "/search-tags", models::RouteGroup::Search),
+            (http::Method::POST, "/search", models::RouteGroup::Global),
+            (http::Method::POST, "/bots/1/stats", models::RouteGroup::Stats),
+            (http::Method::GET, "/bots/1/stats", models::RouteGroup::Global),
+            (http::Method::GET, "/ws/bot/1", models::RouteGroup::Gateway),
+            (http::Method::GET, "/oauth2", models::RouteGroup::Login),
+            (http::Method::POST, "/frostpaw/oauth2/token", models::RouteGroup::Login),
+            (http::Method::POST, "/frostpaw/clients/abc/refresh", models::RouteGroup::Login),
+            (http::Method::GET, "/frostpaw/clients/abc", models::RouteGroup::Global),
+            (http::Method::GET, "/", models::RouteGroup::Global),
+            (http::Method::GET, "/bots/1", models::RouteGroup::Global),
+        ];
+
+        for (method, path, group) in cases {
+            assert_eq!(route_group(&method, path), group, "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarded_for_from_untrusted_peers() {
+        let peer: IpAddr = "198.51.100.1".parse().unwrap();
+
+        assert_eq!(client_ip(Some(peer), Some("203.0.113.9"), &[]), Some(peer));
+        assert_eq!(client_ip(None, Some("203.0.113.9"), &[]), None);
+    }
+
+    #[test]
+    fn client_ip_walks_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let inner_proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let trusted = [proxy, inner_proxy];
+
+        // The client can put anything at the start of the header, only the address added by our proxy counts
+        assert_eq!(
+            client_ip(Some(proxy), Some("1.1.1.1, 203.0.113.9"), &trusted),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(Some(proxy), Some("1.1.1.1, 203.0.113.9, 10.0.0.2"), &trusted),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(Some(proxy), Some("2001:db8::1"), &trusted),
+            Some("2001:db8::1".parse().unwrap())
+        );
+
+        // Without a usable header, the proxy itself is all we know
+        assert_eq!(client_ip(Some(proxy), None, &trusted), Some(proxy));
+        assert_eq!(client_ip(Some(proxy), Some("garbage"), &trusted), Some(proxy));
+    }
+}
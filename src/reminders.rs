@@ -0,0 +1,53 @@
+// Sends vote reminders once the vote cooldown of a user expires
+use crate::database;
+use crate::models;
+use futures::StreamExt;
+use log::error;
+use std::time::Duration;
+
+/// Reminders are given up on (until the user votes again) after this many failed attempts
+const MAX_ATTEMPTS: i32 = 5;
+
+/// How many reminders are sent at once, mostly to stay clear of the Discord ratelimits on DMs
+const CONCURRENCY: usize = 5;
+
+/// Background task that sends due vote reminders. Spawned once on startup
+pub async fn reminder_worker(database: database::Database) {
+    loop {
+        let reminders = database.claim_vote_reminders(50).await;
+
+        if reminders.is_empty() {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            continue;
+        }
+
+        futures::stream::iter(reminders)
+            .map(|reminder| remind(&database, reminder))
+            .buffer_unordered(CONCURRENCY)
+            .collect::<Vec<()>>()
+            .await;
+    }
+}
+
+/// Time to wait before retrying. This is a minute doubling every attempt
+fn backoff(attempts: i32) -> chrono::Duration {
+    chrono::Duration::minutes(2_i64.pow(attempts.clamp(0, 10) as u32))
+}
+
+async fn remind(database: &database::Database, reminder: models::PendingVoteReminder) {
+    if database.send_vote_reminder(&reminder).await {
+        database.finish_vote_reminder(&reminder, true, None).await;
+        return;
+    }
+
+    let attempts = reminder.attempts + 1;
+
+    if attempts >= MAX_ATTEMPTS {
+        error!("Giving up on vote reminder {} after {} attempts", reminder.id, attempts);
+        database.finish_vote_reminder(&reminder, false, None).await;
+    } else {
+        database
+            .finish_vote_reminder(&reminder, false, Some(chrono::Utc::now() + backoff(reminder.attempts)))
+            .await;
+    }
+}
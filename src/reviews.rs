@@ -1,12 +1,50 @@
 /// Handles reviews
-/// TODO, add websocket events *if desired*
+/// TODO, add websocket events for edits and deletes *if desired*
 
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
-use actix_web::{delete, get, patch, post, web, http, HttpRequest, HttpResponse};
+use actix_web::{delete, get, patch, post, web, http, HttpRequest, HttpResponse, ResponseError};
 use bigdecimal::FromPrimitive;
 use log::error;
 
+/// Counts a post against the review quota of the user and runs the spam filter on it. Returns why
+/// the review was flagged, if it was
+async fn check_review(
+    data: &models::AppState,
+    user_id: i64,
+    review_id: Option<uuid::Uuid>,
+    text: &str,
+) -> Result<Option<String>, HttpResponse> {
+    let rules = &data.config.review_filter;
+
+    // If redis is down, let the review through
+    if let Some(status) = data
+        .database
+        .ratelimit_hit(&format!("review_posts:{}", user_id), rules.post_quota)
+        .await
+    {
+        if status.retry_after > 0 {
+            return Err(HttpResponse::build(http::StatusCode::TOO_MANY_REQUESTS)
+                .insert_header((http::header::RETRY_AFTER, status.retry_after))
+                .json(models::APIResponse::rl(status.retry_after as i64)));
+        }
+    }
+
+    let result = data.database.filter_review(user_id, review_id, text, rules).await;
+
+    if result.score >= rules.reject_score {
+        return Err(HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(
+            &models::ReviewAddError::ReviewRejected(result.reasons),
+        )));
+    }
+
+    if result.score >= rules.flag_score {
+        return Ok(Some(result.reasons.join(", ")));
+    }
+
+    Ok(None)
+}
+
 #[get("/reviews/{id}")]
 async fn get_reviews(
     req: HttpRequest,
@@ -26,16 +64,37 @@ async fn get_reviews(
         return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
     }
 
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
     let per_page = 9;
-    let offset = ((page as i64) - 1) * per_page;
+
+    // Cursors take priority over pages
+    let offset = if cursor.is_some() {
+        0
+    } else {
+        ((page as i64) - 1) * per_page
+    };
 
     let reviews = data
         .database
-        .get_reviews(info.id, query.target_type, per_page, offset)
+        .get_reviews(
+            info.id,
+            query.target_type,
+            per_page,
+            offset,
+            cursor.as_ref(),
+            query.reply_order.unwrap_or_default(),
+            &data.config.review_threads,
+        )
         .await;
 
     let mut parsed_review = models::ParsedReview {
-        reviews,
+        reviews: reviews.items,
+        next_cursor: reviews.next_cursor,
+        prev_cursor: reviews.prev_cursor,
         per_page,
         from: offset,
         stats: data
@@ -48,7 +107,13 @@ async fn get_reviews(
     if let Some(user_id) = query.user_id {
         if let Some(user_review) = data
             .database
-            .get_reviews_for_user(user_id, info.id, query.target_type)
+            .get_reviews_for_user(
+                user_id,
+                info.id,
+                query.target_type,
+                query.reply_order.unwrap_or_default(),
+                &data.config.review_threads,
+            )
             .await
         {
             parsed_review.user_review = Some(user_review);
@@ -65,6 +130,7 @@ async fn add_review(
     info: web::Path<models::FetchBotPath>,
     query: web::Query<models::ReviewQuery>,
     review: web::Json<models::Review>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
@@ -76,36 +142,67 @@ async fn add_review(
 
     let user_id = user_id.unwrap();
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Review Add Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     if review.parent_id.is_none() {
+        if review.owner_response {
+            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::OwnerResponseNotTopLevel));
+        }
+
         let existing = data
             .database
-            .get_reviews_for_user(user_id, info.id, query.target_type)
+            .get_reviews_for_user(
+                user_id,
+                info.id,
+                query.target_type,
+                models::ReplyOrder::Oldest,
+                &data.config.review_threads,
+            )
             .await;
 
         if existing.is_some() {
             return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::ReviewAlreadyExists));
         }
     } else {
-        // Validate parent_id
-        let parent_review = data
+        // Validate parent_id, it must be a review of the same bot/server
+        let parent = data
             .database
-            .get_single_review(review.parent_id.unwrap())
+            .get_review_thread(review.parent_id.unwrap())
             .await;
-        if parent_review.is_none() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::ParentReviewInvalid));
+
+        let parent = match parent {
+            Some(parent) if parent.target_id == info.id && parent.target_type == query.target_type => parent,
+            _ => {
+                return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::ParentReviewInvalid));
+            }
+        };
+
+        let max_depth = data.config.review_threads.max_depth;
+
+        if parent.depth + 1 > max_depth {
+            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::ThreadTooDeep(max_depth)));
+        }
+
+        if review.owner_response {
+            if parent.depth != 0 {
+                return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewAddError::OwnerResponseNotTopLevel));
+            }
+
+            let is_owner = match query.target_type {
+                models::TargetType::Bot => data.database.is_bot_owner(info.id, user_id).await,
+                models::TargetType::Server => data.database.is_server_owner(info.id, user_id).await,
+            };
+
+            if !is_owner {
+                return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::ReviewAddError::NotOwner));
+            }
+
+            if parent.has_owner_response {
+                return HttpResponse::build(http::StatusCode::CONFLICT).json(models::APIResponse::err_small(&models::ReviewAddError::OwnerResponseExists));
+            }
         }
     }
 
@@ -133,15 +230,48 @@ async fn add_review(
         }
     }
 
+    let filter_reason = match check_review(data, user_id, None, &review.review_text).await {
+        Ok(filter_reason) => filter_reason,
+        Err(res) => return res,
+    };
+
+    let flagged = filter_reason.is_some();
+    let review = review.into_inner();
+
     let res = data
         .database
-        .add_review(review.into_inner(), user_id, info.id, query.target_type)
+        .add_review(review.clone(), user_id, info.id, query.target_type, filter_reason)
         .await;
 
     if res.is_err() {
         return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
     }
 
+    // Flagged reviews are hidden so the owner is not told about them
+    if !flagged {
+        let event = models::Event {
+            m: models::EventMeta {
+                e: models::EventName::ReviewAdd,
+                eid: uuid::Uuid::new_v4().to_hyphenated().to_string(),
+                seq: 0,
+            },
+            ctx: models::EventContext {
+                target: info.id.to_string(),
+                target_type: query.target_type,
+                user: Some(user_id.to_string()),
+                ts: chrono::Utc::now().timestamp(),
+            },
+            props: models::ReviewAddProp {
+                id: res.unwrap(),
+                parent_id: review.parent_id,
+                star_rating: review.star_rating,
+                review_text: review.review_text,
+                owner_response: review.owner_response,
+            },
+        };
+        data.database.ws_event(event).await;
+    }
+
     HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
@@ -152,6 +282,7 @@ async fn edit_review(
     _: web::Path<models::FetchBotPath>,
     query: web::Query<models::ReviewQuery>,
     review: web::Json<models::Review>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
@@ -163,17 +294,9 @@ async fn edit_review(
 
     let user_id = user_id.unwrap();
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Review Add Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     if review.star_rating < bigdecimal::BigDecimal::from_i64(0).unwrap()
@@ -208,7 +331,12 @@ async fn edit_review(
         return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
     }
 
-    let res = data.database.edit_review(review.into_inner()).await;
+    let filter_reason = match check_review(data, user_id, Some(review_id), &review.review_text).await {
+        Ok(filter_reason) => filter_reason,
+        Err(res) => return res,
+    };
+
+    let res = data.database.edit_review(review.into_inner(), filter_reason).await;
 
     if res.is_err() {
         return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::SQLError(res.unwrap_err()))); 
@@ -222,6 +350,7 @@ async fn delete_review(
     req: HttpRequest,
     info: web::Path<models::ReviewDeletePath>,
     query: web::Query<models::ReviewQuery>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
@@ -233,17 +362,9 @@ async fn delete_review(
 
     let user_id = user_id.unwrap();
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Review Add Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     let review_id = uuid::Uuid::parse_str(&info.rid);
@@ -263,7 +384,10 @@ async fn delete_review(
 
     let review_orig = review_orig.unwrap();
 
-    if review_orig.user.id != user_id.to_string() {
+    // Staff can delete any review
+    if review_orig.user.id != user_id.to_string()
+        && !data.database.has_capability(user_id, models::Capability::ReviewDeleteAny).await
+    {
         return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
     }
 
@@ -281,6 +405,7 @@ async fn vote_review(
     req: HttpRequest,
     info: web::Path<models::ReviewDeletePath>,
     vote: web::Json<models::ReviewVote>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
@@ -292,17 +417,9 @@ async fn vote_review(
 
     let user_id = user_id.unwrap();
 
-    // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if !data.database.authorize_user(user_id, auth).await {
+    if let Err(err) = auth.ensure(user_id) {
         error!("Review Vote Auth error");
-        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+        return err.error_response();
     }
 
     let review_id = uuid::Uuid::parse_str(&info.rid);
@@ -334,3 +451,113 @@ async fn vote_review(
 
     HttpResponse::Ok().json(models::APIResponse::ok())
 }
+
+#[get("/reviews/{rid}/replies")]
+async fn get_replies(
+    req: HttpRequest,
+    info: web::Path<models::ReviewDeletePath>,
+    query: web::Query<models::ReplyQuery>,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let review_id = uuid::Uuid::parse_str(&info.rid);
+    if review_id.is_err() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+
+    let review_id = review_id.unwrap();
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    if data.database.get_single_review(review_id).await.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+
+    let threads = &data.config.review_threads;
+
+    let replies = data
+        .database
+        .get_review_replies(
+            review_id,
+            query.order.unwrap_or_default(),
+            cursor,
+            models::Cursor::limit(query.limit, threads.inline_replies),
+            threads.inline_depth.max(1),
+        )
+        .await;
+
+    HttpResponse::Ok().json(models::ReplyList {
+        replies: replies.items,
+        next_cursor: replies.next_cursor,
+        prev_cursor: replies.prev_cursor,
+    })
+}
+
+#[post("/reviews/{rid}/reports")]
+async fn report_review(
+    req: HttpRequest,
+    info: web::Path<models::ReviewDeletePath>,
+    report: web::Json<models::ReviewReport>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let user_id = report.user_id.parse::<i64>();
+
+    if user_id.is_err() {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let user_id = user_id.unwrap();
+
+    if let Err(err) = auth.ensure(user_id) {
+        error!("Review Report Auth error");
+        return err.error_response();
+    }
+
+    let review_id = uuid::Uuid::parse_str(&info.rid);
+    if review_id.is_err() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+
+    let review_id = review_id.unwrap();
+
+    let context = report.context.as_deref().map(|context| context.trim()).unwrap_or_default();
+
+    if report.reason == models::ReviewReportReason::Other && context.is_empty() {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewReportError::ContextRequired));
+    }
+
+    if context.chars().count() > 1000 {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::ReviewReportError::ContextTooLong));
+    }
+
+    // If redis is down, let the report through
+    if let Some(status) = data
+        .database
+        .ratelimit_hit(&format!("review_reports:{}", user_id), data.config.review_filter.report_quota)
+        .await
+    {
+        if status.retry_after > 0 {
+            return HttpResponse::build(http::StatusCode::TOO_MANY_REQUESTS)
+                .insert_header((http::header::RETRY_AFTER, status.retry_after))
+                .json(models::APIResponse::rl(status.retry_after as i64));
+        }
+    }
+
+    let res = data.database.report_review(review_id, user_id, &report).await;
+
+    match res {
+        Ok(_) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(models::ReviewReportError::NotFound) => {
+            HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::ReviewReportError::NotFound))
+        }
+        Err(models::ReviewReportError::AlreadyReported) => {
+            HttpResponse::build(http::StatusCode::CONFLICT).json(models::APIResponse::err_small(&models::ReviewReportError::AlreadyReported))
+        }
+        Err(err) => HttpResponse::BadRequest().json(models::APIResponse::err_small(&err)),
+    }
+}
@@ -1,88 +1,380 @@
 /// Endpoints to manage security related features such as token regeneration
 
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
-use actix_web::{delete, http, web, HttpRequest, HttpResponse};
+use actix_web::{delete, get, post, http, web, HttpRequest, HttpResponse, ResponseError};
 use log::error;
 
 /// Issues (regenerates) a new bot token
 #[delete("/bots/{id}/token")]
-async fn new_bot_token(req: HttpRequest, id: web::Path<models::FetchBotPath>) -> HttpResponse {
+async fn new_bot_token(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::BotAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let bot_id = id.id;
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_bot(bot_id, auth).await {
-        data.database.new_bot_token(bot_id).await;
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-    }
-    error!("Token auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    if let Err(err) = auth.ensure(bot_id) {
+        error!("Token auth error");
+        return err.error_response();
+    }
+
+    let token = data.database.new_bot_token(bot_id).await;
+
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: bot_id,
+            target_type: models::AuditTargetType::Bot,
+            actor_id: bot_id,
+            action: models::AuditAction::NewToken,
+            context: None,
+        })
+        .await;
+
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse {
+        done: true,
+        reason: None,
+        context: Some(token),
+    })
 }
 
 /// Issues (regenerates) a new user token
 #[delete("/users/{id}/token")]
-async fn new_user_token(req: HttpRequest, id: web::Path<models::FetchBotPath>) -> HttpResponse {
+async fn new_user_token(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::UserAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = id.id;
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        data.database.new_user_token(user_id).await;
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-    }
-    error!("Token auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    if let Err(err) = auth.ensure_full(user_id) {
+        error!("Token auth error");
+        return err.error_response();
+    }
+
+    let token = data.database.new_user_token(user_id).await;
+
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: user_id,
+            target_type: models::AuditTargetType::User,
+            actor_id: user_id,
+            action: models::AuditAction::NewToken,
+            context: None,
+        })
+        .await;
+
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse {
+        done: true,
+        reason: None,
+        context: Some(token),
+    })
 }
 
 /// Revokes a clients auth
 #[delete("/users/{id}/frostpaw/clients/{client_id}")]
-async fn revoke_frostpaw_client_auth(req: HttpRequest, id: web::Path<models::UserClientAuth>) -> HttpResponse {
+async fn revoke_frostpaw_client_auth(req: HttpRequest, id: web::Path<models::UserClientAuth>, auth: auth::UserAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = id.id;
     let client_id = id.client_id.clone();
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
+
+    if let Err(err) = auth.ensure_full(user_id) {
+        error!("Token auth error");
+        return err.error_response();
+    }
+
+    data.database.revoke_client(user_id, client_id).await;
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok())
+}
+
+
+/// Lists the active sessions (logins and connected clients) of a user
+#[get("/users/{id}/sessions")]
+async fn get_user_sessions(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    // Clients cannot see or manage the sessions of their users
+    if auth.client_id.is_some() {
+        return models::AuthError::WrongTokenType.error_response();
+    }
+
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    let token = req
         .headers()
         .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        data.database.revoke_client(user_id, client_id).await;
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-    }
-    error!("Token auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or_default()
+        .trim();
+
+    let token = token.strip_prefix("Bearer ").unwrap_or(token);
+    let token = token.strip_prefix("User ").unwrap_or(token);
+
+    HttpResponse::Ok().json(data.database.get_sessions(id.id, token).await)
+}
+
+/// Revokes a session of a user
+#[delete("/users/{user_id}/sessions/{session_id}")]
+async fn revoke_user_session(req: HttpRequest, info: web::Path<models::UserSessionPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if auth.client_id.is_some() {
+        return models::AuthError::WrongTokenType.error_response();
+    }
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        return err.error_response();
+    }
+
+    let session_id = match uuid::Uuid::parse_str(&info.session_id) {
+        Ok(session_id) => session_id,
+        Err(_) => return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound)),
+    };
+
+    match data.database.revoke_session(info.user_id, session_id).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(models::GenericError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
 }
 
+/// Logs a user out everywhere by revoking all their sessions
+#[delete("/users/{id}/sessions")]
+async fn logout_everywhere(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if auth.client_id.is_some() {
+        return models::AuthError::WrongTokenType.error_response();
+    }
+
+    if let Err(err) = auth.ensure(id.id) {
+        return err.error_response();
+    }
+
+    data.database.logout_everywhere(id.id).await;
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
+}
 
 /// Issues (regenerates) a new server token
 #[delete("/servers/{id}/token")]
-async fn new_server_token(req: HttpRequest, id: web::Path<models::FetchBotPath>) -> HttpResponse {
+async fn new_server_token(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::ServerAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let server_id = id.id;
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_server(server_id, auth).await {
-        data.database.new_server_token(server_id).await;
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
-    }
-    error!("Token auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    if let Err(err) = auth.ensure(server_id) {
+        error!("Token auth error");
+        return err.error_response();
+    }
+
+    let token = data.database.new_server_token(server_id).await;
+
+    data.database
+        .audit_log(models::NewAuditLog {
+            target_id: server_id,
+            target_type: models::AuditTargetType::Server,
+            actor_id: server_id,
+            action: models::AuditAction::NewToken,
+            context: None,
+        })
+        .await;
+
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse {
+        done: true,
+        reason: None,
+        context: Some(token),
+    })
+}
+
+/// Checks the name, scopes and expiry of a new scoped API token
+fn check_api_token(token_type: models::TokenType, token: &models::CreateApiToken) -> Result<(), models::ApiTokenError> {
+    if token.name.trim().is_empty() || token.name.len() > 64 {
+        return Err(models::ApiTokenError::NameError);
+    }
+
+    if token.scopes.is_empty() {
+        return Err(models::ApiTokenError::NoScopes);
+    }
+
+    if token.scopes.iter().any(|scope| !scope.valid_for(token_type)) {
+        return Err(models::ApiTokenError::InvalidScope);
+    }
+
+    if let Some(expires_at) = token.expires_at {
+        if expires_at <= chrono::Utc::now() {
+            return Err(models::ApiTokenError::ExpiryInPast);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the scoped API tokens of a user
+#[get("/users/{id}/tokens")]
+async fn get_user_api_tokens(req: HttpRequest, id: web::Path<models::FetchBotPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(id.id) {
+        return err.error_response();
+    }
+
+    HttpResponse::Ok().json(data.database.get_api_tokens(id.id, models::TokenType::User).await)
+}
+
+/// Creates a scoped API token for a user
+#[post("/users/{id}/tokens")]
+async fn create_user_api_token(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    token: web::Json<models::CreateApiToken>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(id.id) {
+        return err.error_response();
+    }
+
+    if let Err(err) = check_api_token(models::TokenType::User, &token) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err));
+    }
+
+    match data.database.create_api_token(id.id, models::TokenType::User, &token).await {
+        Ok(new_token) => {
+            data.database
+                .audit_log(models::NewAuditLog {
+                    target_id: id.id,
+                    target_type: models::AuditTargetType::User,
+                    actor_id: id.id,
+                    action: models::AuditAction::CreateApiToken,
+                    context: Some(token.name.clone()),
+                })
+                .await;
+
+            HttpResponse::Ok().json(new_token)
+        }
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Revokes a scoped API token of a user
+#[delete("/users/{user_id}/tokens/{token_id}")]
+async fn delete_user_api_token(req: HttpRequest, info: web::Path<models::UserTokenPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(info.user_id) {
+        return err.error_response();
+    }
+
+    let token_id = match uuid::Uuid::parse_str(&info.token_id) {
+        Ok(token_id) => token_id,
+        Err(_) => return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::ApiTokenError::NotFound)),
+    };
+
+    match data.database.delete_api_token(info.user_id, models::TokenType::User, token_id).await {
+        Ok(()) => {
+            data.database
+                .audit_log(models::NewAuditLog {
+                    target_id: info.user_id,
+                    target_type: models::AuditTargetType::User,
+                    actor_id: info.user_id,
+                    action: models::AuditAction::DeleteApiToken,
+                    context: Some(token_id.to_string()),
+                })
+                .await;
+
+            HttpResponse::Ok().json(models::APIResponse::ok())
+        }
+        Err(models::ApiTokenError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::ApiTokenError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Lists the scoped API tokens of a bot
+#[get("/users/{user_id}/bots/{bot_id}/tokens")]
+async fn get_bot_api_tokens(req: HttpRequest, info: web::Path<models::GetUserBotPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(info.user_id) {
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    HttpResponse::Ok().json(data.database.get_api_tokens(info.bot_id, models::TokenType::Bot).await)
+}
+
+/// Creates a scoped API token for a bot
+#[post("/users/{user_id}/bots/{bot_id}/tokens")]
+async fn create_bot_api_token(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    token: web::Json<models::CreateApiToken>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(info.user_id) {
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    if let Err(err) = check_api_token(models::TokenType::Bot, &token) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err));
+    }
+
+    match data.database.create_api_token(info.bot_id, models::TokenType::Bot, &token).await {
+        Ok(new_token) => {
+            data.database
+                .audit_log(models::NewAuditLog {
+                    target_id: info.bot_id,
+                    target_type: models::AuditTargetType::Bot,
+                    actor_id: info.user_id,
+                    action: models::AuditAction::CreateApiToken,
+                    context: Some(token.name.clone()),
+                })
+                .await;
+
+            HttpResponse::Ok().json(new_token)
+        }
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Revokes a scoped API token of a bot
+#[delete("/users/{user_id}/bots/{bot_id}/tokens/{token_id}")]
+async fn delete_bot_api_token(req: HttpRequest, info: web::Path<models::UserBotTokenPath>, auth: auth::UserAuth) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure_full(info.user_id) {
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let token_id = match uuid::Uuid::parse_str(&info.token_id) {
+        Ok(token_id) => token_id,
+        Err(_) => return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::ApiTokenError::NotFound)),
+    };
+
+    match data.database.delete_api_token(info.bot_id, models::TokenType::Bot, token_id).await {
+        Ok(()) => {
+            data.database
+                .audit_log(models::NewAuditLog {
+                    target_id: info.bot_id,
+                    target_type: models::AuditTargetType::Bot,
+                    actor_id: info.user_id,
+                    action: models::AuditAction::DeleteApiToken,
+                    context: Some(token_id.to_string()),
+                })
+                .await;
+
+            HttpResponse::Ok().json(models::APIResponse::ok())
+        }
+        Err(models::ApiTokenError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::ApiTokenError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
 }
@@ -1,55 +1,27 @@
 /// Handles bot actions (view)
 
+use crate::auth;
 use crate::models;
 use std::sync::Arc;
 use uuid::Uuid;
-use actix_web::http::header::HeaderValue;
 use actix_web::{get, web, http, web::Json, HttpRequest, HttpResponse};
-use log::{error, debug};
+use log::debug;
 
 // Server route
 #[get("/servers/{id}")]
-async fn get_server(req: HttpRequest, id: web::Path<models::FetchBotPath>) -> HttpResponse {
+async fn get_server(req: HttpRequest, id: web::Path<models::FetchBotPath>, viewer: auth::FrostpawAuth) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
     let id = id.into_inner();
 
-    let mut event_user: Option<String> = None;
+    let event_user = viewer.0.map(|user_id| user_id.to_string());
 
     if req.headers().contains_key("Frostpaw") {
-        let auth_default = &HeaderValue::from_str("").unwrap();
-        let auth = req.headers().get("Frostpaw-Auth").unwrap_or(auth_default);
-        if !auth.clone().is_empty() {
-            let auth_bytes = auth.to_str();
-            match auth_bytes {
-                Ok(auth_str) => {
-                    let auth_split = auth_str.split('|');
-                    let auth_vec = auth_split.collect::<Vec<&str>>();
-
-                    let user_id = auth_vec.get(0).unwrap_or(&"");
-                    let token = auth_vec.get(1).unwrap_or(&"");
-
-                    let user_id_str = user_id.to_string();
-
-                    let user_id_i64 = user_id_str.parse::<i64>().unwrap_or(0);
-
-                    if data
-                        .database
-                        .authorize_user(user_id_i64, token.as_ref())
-                        .await
-                    {
-                        event_user = Some(user_id_str);
-                    }
-                }
-                Err(err) => {
-                    error!("{}", err);
-                }
-            }
-        }
         let event = models::Event {
             m: models::EventMeta {
                 e: models::EventName::ServerView,
                 eid: Uuid::new_v4().to_hyphenated().to_string(),
+                seq: 0,
             },
             ctx: models::EventContext {
                 target: id.id.to_string(),
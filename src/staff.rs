@@ -0,0 +1,243 @@
+// Staff endpoints to moderate the bot and server queue
+use crate::auth;
+use crate::models;
+use crate::perms;
+use actix_web::{get, patch, web, http, HttpRequest, HttpResponse, ResponseError};
+use log::error;
+use serenity::model::prelude::*;
+use uuid::Uuid;
+
+/// The capability needed for a staff action
+fn action_capability(action: models::UserBotAction) -> models::Capability {
+    match action {
+        models::UserBotAction::Claim | models::UserBotAction::Unclaim => models::Capability::QueueClaim,
+        models::UserBotAction::Ban => models::Capability::BotBan,
+        models::UserBotAction::Certify => models::Capability::BotCertify,
+        _ => models::Capability::QueueApprove,
+    }
+}
+
+/// The event and bot logs embed (title and color) of a staff action
+fn action_meta(action: models::UserBotAction) -> (models::EventName, &'static str, u32) {
+    match action {
+        models::UserBotAction::Claim => (models::EventName::BotClaim, "Claimed", 0xffff00),
+        models::UserBotAction::Unclaim => (models::EventName::BotUnclaim, "Unclaimed", 0xffa500),
+        models::UserBotAction::Approve => (models::EventName::BotApprove, "Approved", 0x00ff00),
+        models::UserBotAction::Deny => (models::EventName::BotDeny, "Denied", 0xff0000),
+        models::UserBotAction::Ban => (models::EventName::BotBan, "Banned", 0x8b0000),
+        models::UserBotAction::Certify => (models::EventName::BotCertify, "Certified", 0x00ffff),
+        _ => (models::EventName::BotRequeue, "Requeued", 0x0000ff),
+    }
+}
+
+/// Get Queue
+#[get("/users/{id}/staff/queue", wrap = "perms::Staff(models::Capability::QueueView)")]
+async fn get_queue(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    query: web::Query<models::QueueQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(id.id) {
+        error!("Staff Queue Auth error");
+        return err.error_response();
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_queue(
+            query.target_type.unwrap_or(models::TargetType::Bot),
+            query.state.unwrap_or(models::State::Pending),
+            cursor.as_ref(),
+            models::Cursor::limit(query.limit, 25),
+        )
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::Ok().json(models::QueueList {
+            queue: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Staff Action
+#[patch("/users/{user_id}/staff/queue/{target_id}", wrap = "perms::Staff(models::Capability::QueueView)")]
+async fn staff_action(
+    req: HttpRequest,
+    info: web::Path<models::StaffTargetPath>,
+    action: web::Json<models::StaffAction>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Staff Action Auth error");
+        return err.error_response();
+    }
+
+    let action = action.into_inner();
+
+    if let Err(err) = perms::require(data, info.user_id, action_capability(action.action)).await {
+        return err;
+    }
+
+    let res = data
+        .database
+        .staff_action(info.user_id, info.target_id, action.target_type, action.action, action.reason.as_deref())
+        .await;
+
+    if let Err(err) = res {
+        return match err {
+            models::StaffActionError::NotFound => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&err)),
+            models::StaffActionError::InvalidState(_) => HttpResponse::build(http::StatusCode::CONFLICT).json(models::APIResponse::err_small(&err)),
+            _ => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+        };
+    }
+
+    let (event_name, verb, color) = action_meta(action.action);
+
+    // Server events reuse the bot event names, ctx.target_type tells them apart
+    let event = models::Event {
+        m: models::EventMeta {
+            e: event_name,
+            eid: Uuid::new_v4().to_hyphenated().to_string(),
+            seq: 0,
+        },
+        ctx: models::EventContext {
+            target: info.target_id.to_string(),
+            target_type: action.target_type,
+            user: Some(info.user_id.to_string()),
+            ts: chrono::Utc::now().timestamp(),
+        },
+        props: models::ModActionProp {
+            action: action.action,
+            reason: action.reason.clone(),
+        },
+    };
+    data.database.ws_event(event).await;
+
+    let (target, path, kind) = match action.target_type {
+        models::TargetType::Bot => (data.database.get_user(info.target_id).await, "/bot/", "Bot"),
+        models::TargetType::Server => (data.database.get_server_user(info.target_id).await, "/server/", "Server"),
+    };
+
+    let result = data
+        .config
+        .discord
+        .channels
+        .bot_logs
+        .send_message(&data.config.discord_http, |m| {
+            m.embed(|e| {
+                e.url(data.config.discord.site_url.to_string() + path + &target.id);
+                e.title(format!("{} {}!", kind, verb));
+                e.color(color);
+                e.description(format!(
+                    "{user} has {verb} {target} ({target_id})!",
+                    user = UserId(info.user_id as u64).mention(),
+                    verb = verb.to_lowercase(),
+                    target = target.username,
+                    target_id = target.id
+                ));
+
+                if let Some(ref reason) = action.reason {
+                    e.field("Reason", reason, false);
+                }
+
+                e
+            });
+            m
+        })
+        .await;
+
+    if let Err(err) = result {
+        error!("Error sending message: {}", err);
+    }
+
+    HttpResponse::Ok().json(models::APIResponse::ok())
+}
+
+/// Get Review Queue
+#[get("/users/{id}/staff/reviews", wrap = "perms::Staff(models::Capability::ReviewModerate)")]
+async fn get_review_queue(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    query: web::Query<models::ReviewQueueQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(id.id) {
+        error!("Staff Review Queue Auth error");
+        return err.error_response();
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_review_queue(query.hidden, cursor.as_ref(), models::Cursor::limit(query.limit, 25))
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::Ok().json(models::ReviewQueueList {
+            reviews: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Moderate Review
+#[patch("/users/{user_id}/staff/reviews/{rid}", wrap = "perms::Staff(models::Capability::ReviewModerate)")]
+async fn moderate_review(
+    req: HttpRequest,
+    info: web::Path<models::StaffReviewPath>,
+    moderation: web::Json<models::ReviewModeration>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Moderate Review Auth error");
+        return err.error_response();
+    }
+
+    if moderation.action == models::ReviewModAction::Delete {
+        if let Err(err) = perms::require(data, info.user_id, models::Capability::ReviewDeleteAny).await {
+            return err;
+        }
+    }
+
+    let reason = moderation
+        .reason
+        .as_deref()
+        .map(|reason| reason.trim())
+        .filter(|reason| !reason.is_empty());
+
+    let res = data
+        .database
+        .moderate_review(info.user_id, info.rid, moderation.action, reason)
+        .await;
+
+    match res {
+        Ok(_) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(models::GenericError::NotFound) => {
+            HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound))
+        }
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
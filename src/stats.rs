@@ -6,9 +6,21 @@ use actix_web::{get, web, HttpRequest, HttpResponse};
 use log::error;
 
 #[get("/stats")]
-async fn get_bot_list_stats(req: HttpRequest) -> HttpResponse {
+async fn get_bot_list_stats(req: HttpRequest, query: web::Query<models::CursorQuery>) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::BadRequest().json(models::APIResponse::err_small(&err)),
+    };
+
+    // For backwards compatibility, all bots are returned unless a cursor or limit is given
+    let limit = if cursor.is_some() || query.limit.is_some() {
+        Some(models::Cursor::limit(query.limit, 100))
+    } else {
+        None
+    };
+
     let uptime = match uptime_lib::get() {
         Ok(up) => {
             up.as_secs_f64()
@@ -20,12 +32,16 @@ async fn get_bot_list_stats(req: HttpRequest) -> HttpResponse {
         }
     };
 
+    let bots = data.database.get_all_bots(cursor.as_ref(), limit).await;
+
     HttpResponse::Ok().json(models::ListStats {
         total_bots: data.database.get_bot_count().await,
         total_users: data.database.get_user_count().await,
         total_servers: data.database.get_server_count().await,
-        bots: data.database.get_all_bots().await,
+        bots: bots.items,
         servers: data.database.get_all_servers().await,
         uptime,
+        next_cursor: bots.next_cursor,
+        prev_cursor: bots.prev_cursor,
     })
 }
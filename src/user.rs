@@ -1,7 +1,7 @@
 // Endpoints to get and modify users
+use crate::auth;
 use crate::models;
-use actix_web::http::header::HeaderValue;
-use actix_web::{get, put, patch, web, http, HttpRequest, HttpResponse};
+use actix_web::{get, put, patch, web, http, HttpRequest, HttpResponse, ResponseError};
 use log::error;
 
 /// Gets a user given a id
@@ -11,25 +11,20 @@ pub async fn get_user_from_id(req: HttpRequest, info: web::Path<models::FetchBot
     HttpResponse::Ok().json(data.database.get_user(info.id).await)
 }
 
-/// Gets a users perms given a id
+/// Gets a users perms (and the capabilities they have) given a id
 #[get("/baypaw/perms/{id}")]
 pub async fn get_user_perms(req: HttpRequest, info: web::Path<models::FetchBotPath>) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
-    // This api may crash with 408 if baypaw is down, it merely proxies
-    let req = data.database.requests.get(
-    format!(
-        "http://127.0.0.1:1234/perms/{id}",
-        id = info.id
-    ))
-    .send()
-    .await
-    .unwrap();
-
-    HttpResponse::Ok().json(req.json::<bristlefrost::models::StaffRole>().await.unwrap())
+    match data.database.get_staff_perms(info.id).await {
+        Ok(perms) => HttpResponse::Ok().json(perms),
+        Err(err) => {
+            error!("Could not get perms of {} from baypaw: {}", info.id, err);
+            HttpResponse::build(http::StatusCode::REQUEST_TIMEOUT).json(models::APIResponse::err_small(&models::StaffPermError::BaypawError(err)))
+        }
+    }
 }
 
-
 #[get("/profiles/{id}")]
 async fn get_profile(req: HttpRequest, info: web::Path<models::FetchBotPath>) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
@@ -50,89 +45,79 @@ async fn update_profile(
     req: HttpRequest,
     info: web::Path<models::FetchBotPath>,
     body: web::Json<models::Profile>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(info.id, auth).await {
-        let profile = data.database.get_profile(info.id).await;
-        if profile.is_none() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
-        let profile = profile.unwrap();
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Update profile auth error");
+        return err.error_response();
+    }
 
-        if profile.state == models::UserState::ProfileEditBan {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("ProfileEditBan".to_string())));
-        }
+    let profile = data.database.get_profile(info.id).await;
+    if profile.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+    let profile = profile.unwrap();
 
-        if body.flags.contains(&(models::UserFlags::VotesPrivate as i32)) && !profile.user_experiments.contains(&models::UserExperiments::UserVotePrivacy) {
-                return models::UserExperiments::UserVotePrivacy.not_enabled();
-        }
+    if profile.state == models::UserState::ProfileEditBan {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("ProfileEditBan".to_string())));
+    }
 
-        let res = data
-            .database
-            .update_profile(info.id, body.into_inner())
-            .await;
+    if body.flags.contains(&(models::UserFlags::VotesPrivate as i32)) && !profile.user_experiments.contains(&models::UserExperiments::UserVotePrivacy) {
+        return models::UserExperiments::UserVotePrivacy.not_enabled();
+    }
 
-        if res.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&res.unwrap_err()));
-        }
-        return HttpResponse::Ok().json(models::APIResponse::ok());
+    let res = data
+        .database
+        .update_profile(info.id, body.into_inner())
+        .await;
+
+    if res.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&res.unwrap_err()));
     }
-    error!("Update profile auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+    HttpResponse::Ok().json(models::APIResponse::ok())
 }
 
 #[put("/profiles/{id}/old-roles")]
 async fn receive_profile_roles(
     req: HttpRequest,
     info: web::Path<models::FetchBotPath>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(info.id, auth).await {
-        let profile = data.database.get_profile(info.id).await;
-        if profile.is_none() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
-        let profile = profile.unwrap();
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Update profile auth error");
+        return err.error_response();
+    }
 
-        let rl = data.database.get_ratelimit(models::Ratelimit::RoleUpdate, info.id).await;
+    let profile = data.database.get_profile(info.id).await;
+    if profile.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+    let profile = profile.unwrap();
 
-        if rl.is_some() && rl.unwrap() > 0 {
-            return HttpResponse::BadRequest().json(models::APIResponse::rl(rl.unwrap()));
-        }    
+    let rl = data.database.get_ratelimit(models::Ratelimit::RoleUpdate, info.id).await;
 
-        if profile.state == models::UserState::ProfileEditBan {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("ProfileEditBan".to_string())));
-        }
+    if rl.is_some() && rl.unwrap() > 0 {
+        return HttpResponse::BadRequest().json(models::APIResponse::rl(rl.unwrap()));
+    }    
 
-        data.database.set_ratelimit(models::Ratelimit::RoleUpdate, info.id).await;
+    if profile.state == models::UserState::ProfileEditBan {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&models::GenericError::APIBan("ProfileEditBan".to_string())));
+    }
 
-        let update = data
-            .database
-            .update_user_bot_roles(info.id, &data.config.discord)
-            .await;
+    data.database.set_ratelimit(models::Ratelimit::RoleUpdate, info.id).await;
 
-        if update.is_err() {
-            return HttpResponse::BadRequest().json(models::APIResponse::err_small(&update.unwrap_err()));
-        }
+    let update = data
+        .database
+        .update_user_bot_roles(info.id, &data.config.discord)
+        .await;
 
-        return HttpResponse::Ok().json(update.unwrap());
+    if update.is_err() {
+        return HttpResponse::BadRequest().json(models::APIResponse::err_small(&update.unwrap_err()));
     }
-    error!("Update profile auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
+
+    HttpResponse::Ok().json(update.unwrap())
 }
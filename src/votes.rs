@@ -1,9 +1,21 @@
+use crate::auth;
 use crate::models;
+use crate::perms;
 use crate::converters;
-use actix_web::http::header::HeaderValue;
-use actix_web::{get, patch, web, http, HttpRequest, HttpResponse};
+use actix_web::{get, patch, web, http, HttpRequest, HttpResponse, ResponseError};
 use log::error;
 
+/// Held votes still succeed (and start the cooldown) but are only counted once staff confirm them
+fn vote_response(state: models::VoteRiskState) -> HttpResponse {
+    if state == models::VoteRiskState::Held {
+        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse {
+            done: true,
+            reason: None,
+            context: Some("Your vote is being reviewed by staff and will be counted once it is confirmed".to_string()),
+        });
+    }
+    HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok())
+}
 
 /// Create Bot Vote
 #[patch("/users/{user_id}/bots/{bot_id}/votes")]
@@ -11,36 +23,32 @@ async fn create_bot_vote(
     req: HttpRequest,
     info: web::Path<models::GetUserBotPath>,
     vote: web::Query<models::VoteBotQuery>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
     let bot_id = info.bot_id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        let bot = data.database.get_bot(bot_id).await;
-        if bot.is_none() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
-        let bot = bot.unwrap();
-        if converters::flags_check(&bot.flags, vec![models::Flags::System as i32]) {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::VoteBotError::System));
-        }
-        let vote = data.database.vote_bot(user_id, bot_id, vote.test).await;
-        if vote.is_err() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&vote.unwrap_err()));
-        }
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
+    if let Err(err) = auth.ensure_scope(user_id, models::TokenScope::VotesWrite) {
+        error!("Vote Bot Auth error");
+        return err.error_response();
+    }
+
+    let bot = data.database.get_bot(bot_id).await;
+    if bot.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+    let bot = bot.unwrap();
+    if converters::flags_check(&bot.flags, vec![models::Flags::System as i32]) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::VoteBotError::System));
+    }
+    let meta = converters::vote_meta(&req);
+    let vote = data.database.vote_bot(user_id, bot_id, vote.test, &meta, &data.config.vote_risk, &data.config.vote_rules).await;
+    match vote {
+        Ok(state) => vote_response(state),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
     }
-    error!("Vote Bot Auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
 }
 
 /// Create Server Vote
@@ -49,51 +57,54 @@ async fn create_server_vote(
     req: HttpRequest,
     info: web::Path<models::GetUserServerPath>,
     vote: web::Query<models::VoteBotQuery>,
+    auth: auth::UserAuth,
 ) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
     let user_id = info.user_id;
     let server_id = info.server_id;
 
     // Check auth
-    let auth_default = &HeaderValue::from_str("").unwrap();
-    let auth = req
-        .headers()
-        .get("Authorization")
-        .unwrap_or(auth_default)
-        .to_str()
-        .unwrap();
-    if data.database.authorize_user(user_id, auth).await {
-        let server = data.database.get_server(server_id).await;
-        if server.is_none() {
-            return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
-        }
-        let server = server.unwrap();
-        if converters::flags_check(&server.flags, vec![models::Flags::System as i32]) {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::VoteBotError::System));
-        }
-        let vote = data
-            .database
-            .vote_server(
-                &data.config.discord_http_server,
-                user_id,
-                server_id,
-                vote.test,
-            )
-            .await;
-        if vote.is_err() {
-            return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&vote.unwrap_err()));
-        }
-        return HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok());
+    if let Err(err) = auth.ensure_scope(user_id, models::TokenScope::VotesWrite) {
+        error!("Vote Server Auth error");
+        return err.error_response();
+    }
+
+    let server = data.database.get_server(server_id).await;
+    if server.is_none() {
+        return HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::GenericError::NotFound));
+    }
+    let server = server.unwrap();
+    if converters::flags_check(&server.flags, vec![models::Flags::System as i32]) {
+        return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&models::VoteBotError::System));
+    }
+    let vote = data
+        .database
+        .vote_server(
+            &data.config.discord_http_server,
+            user_id,
+            server_id,
+            vote.test,
+            &converters::vote_meta(&req),
+            &data.config.vote_risk,
+            &data.config.vote_rules,
+        )
+        .await;
+    match vote {
+        Ok(state) => vote_response(state),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
     }
-    error!("Vote Server Auth error");
-    HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden))
 }
 
 /// Bot: Has User Voted?
 #[get("/users/{user_id}/bots/{bot_id}/votes")]
-async fn get_bot_votes(req: HttpRequest, info: web::Path<models::GetUserBotPath>) -> HttpResponse {
+async fn get_bot_votes(req: HttpRequest, info: web::Path<models::GetUserBotPath>, query: web::Query<models::CursorQuery>) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
     let user_flags = data.database.get_user_flags(info.user_id).await;
 
     if user_flags.contains(&models::UserFlags::VotesPrivate) {
@@ -103,14 +114,19 @@ async fn get_bot_votes(req: HttpRequest, info: web::Path<models::GetUserBotPath>
         })
     }
 
-    let resp = data.database.get_user_bot_voted(info.bot_id, info.user_id).await;
+    let resp = data.database.get_user_bot_voted(info.bot_id, info.user_id, cursor.as_ref(), models::Cursor::limit(query.limit, 100), &data.config.vote_rules).await;
     HttpResponse::build(http::StatusCode::OK).json(resp)
 }
 
 /// Server: Has User Voted?
 #[get("/users/{user_id}/servers/{server_id}/votes")]
-async fn get_server_votes(req: HttpRequest, info: web::Path<models::GetUserServerPath>) -> HttpResponse {
+async fn get_server_votes(req: HttpRequest, info: web::Path<models::GetUserServerPath>, query: web::Query<models::CursorQuery>) -> HttpResponse {
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
     
     let user_flags = data.database.get_user_flags(info.user_id).await;
 
@@ -121,6 +137,73 @@ async fn get_server_votes(req: HttpRequest, info: web::Path<models::GetUserServe
         })
     }
     
-    let resp = data.database.get_user_server_voted(info.server_id, info.user_id).await;
+    let resp = data.database.get_user_server_voted(info.server_id, info.user_id, cursor.as_ref(), models::Cursor::limit(query.limit, 100), &data.config.vote_rules).await;
     HttpResponse::build(http::StatusCode::OK).json(resp)
-}
\ No newline at end of file
+}
+
+/// Get Flagged Votes
+#[get("/users/{id}/staff/votes", wrap = "perms::Staff(models::Capability::VoteReview)")]
+async fn get_flagged_votes(
+    req: HttpRequest,
+    id: web::Path<models::FetchBotPath>,
+    query: web::Query<models::FlaggedVoteQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(id.id) {
+        error!("Flagged Votes Auth error");
+        return err.error_response();
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_flagged_votes(
+            query.state.unwrap_or(models::VoteRiskState::Held),
+            query.target_id,
+            cursor.as_ref(),
+            models::Cursor::limit(query.limit, 100),
+        )
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::build(http::StatusCode::OK).json(models::FlaggedVoteList {
+            votes: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Review Flagged Vote
+#[patch("/users/{user_id}/staff/votes/{vote_id}", wrap = "perms::Staff(models::Capability::VoteReview)")]
+async fn review_flagged_vote(
+    req: HttpRequest,
+    info: web::Path<models::FlaggedVotePath>,
+    decision: web::Json<models::VoteDecision>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Flagged Votes Auth error");
+        return err.error_response();
+    }
+
+    let res = data
+        .database
+        .review_vote(&data.config.discord_http_server, info.vote_id, info.user_id, decision.confirm)
+        .await;
+
+    match res {
+        Ok(()) => HttpResponse::build(http::StatusCode::OK).json(models::APIResponse::ok()),
+        Err(models::VoteReviewError::NotFound) => HttpResponse::build(http::StatusCode::NOT_FOUND).json(models::APIResponse::err_small(&models::VoteReviewError::NotFound)),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
@@ -0,0 +1,484 @@
+// Durable webhook delivery (backed by the webhook_deliveries table) and endpoints to inspect it
+use crate::auth;
+use crate::converters;
+use crate::database;
+use crate::models;
+use actix_web::{delete, get, http, post, put, web, HttpRequest, HttpResponse, ResponseError};
+use log::{debug, error};
+use std::time::Duration;
+
+/// Deliveries are dead-lettered after this many failed attempts
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Background task that sends queued webhooks. Spawned once on startup
+pub async fn delivery_worker(database: database::Database) {
+    loop {
+        let deliveries = database.claim_webhook_deliveries(25).await;
+
+        if deliveries.is_empty() {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+
+        futures::future::join_all(
+            deliveries
+                .into_iter()
+                .map(|delivery| deliver(&database, delivery)),
+        )
+        .await;
+    }
+}
+
+/// Time to wait before retrying. This is 30 seconds doubling every attempt (capped at 6 hours)
+/// unless the receiver asked us to wait longer using ``Retry-After``
+fn backoff(attempts: i32, retry_after: Option<i64>) -> chrono::Duration {
+    let backoff = (30 * 2_i64.pow(attempts.clamp(0, 10) as u32)).min(6 * 60 * 60);
+    let retry_after = retry_after.unwrap_or(0).clamp(0, 24 * 60 * 60);
+
+    chrono::Duration::seconds(backoff.max(retry_after))
+}
+
+async fn deliver(database: &database::Database, delivery: models::PendingWebhookDelivery) {
+    let attempts = delivery.attempts + 1;
+
+    let secrets = match database.get_webhook_secret(delivery.target_id, delivery.target_type).await {
+        Ok(Some(secrets)) => secrets,
+        Ok(None) => {
+            // Bot/server no longer exists, retrying won't help
+            database.finish_webhook_delivery(
+                delivery.id,
+                models::WebhookDeliveryStatus::DeadLetter,
+                None,
+                Some("Target no longer exists".to_string()),
+                None,
+            ).await;
+            return;
+        }
+        Err(err) => {
+            // Most likely a database hiccup, so try again later instead of dropping the webhook
+            error!("Failed to get webhook secret for delivery {}: {:?}", delivery.id, err);
+            database.finish_webhook_delivery(
+                delivery.id,
+                models::WebhookDeliveryStatus::Pending,
+                None,
+                Some("Failed to get webhook secret".to_string()),
+                Some(chrono::Utc::now() + backoff(attempts, None)),
+            ).await;
+            return;
+        }
+    };
+
+    let body = serde_json::to_string(&delivery.payload).unwrap_or_default();
+
+    // Signed per attempt so retries aren't rejected as expired
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let mut sign_with = vec![secrets.secret.as_str()];
+    if let Some(secondary) = &secrets.secondary {
+        sign_with.push(secondary.as_str());
+    }
+
+    // The legacy signature (and Authorization header) can only hold one secret so the old one is used 
+    // until the rotation is finished by clearing it
+    let legacy_secret = secrets.secondary.as_deref().unwrap_or(&secrets.secret);
+
+    let mut req = database
+        .requests
+        .post(delivery.url.as_str())
+        .timeout(Duration::from_secs(10))
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Signature", converters::sign_webhook(legacy_secret, &body))
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Event-Id", delivery.eid.as_str())
+        .header("X-Webhook-Signature-V2", converters::sign_webhook_v2(&sign_with, timestamp, &delivery.eid, &body));
+
+    if !secrets.hmac_only {
+        req = req.header("Authorization", legacy_secret);
+    }
+
+    let (status_code, err, retry_after) = match req.body(body).send().await {
+        Ok(res) => {
+            let status = res.status();
+
+            if status.is_success() {
+                debug!("Sent webhook {} with status code: {}", delivery.id, status);
+                database.finish_webhook_delivery(
+                    delivery.id,
+                    models::WebhookDeliveryStatus::Delivered,
+                    Some(status.as_u16().into()),
+                    None,
+                    None,
+                ).await;
+                return;
+            }
+
+            // Only the delay-seconds form of Retry-After is supported
+            let retry_after = res
+                .headers()
+                .get("Retry-After")
+                .and_then(|header| header.to_str().ok())
+                .and_then(|header| header.trim().parse::<i64>().ok());
+
+            let text = res.text().await.unwrap_or_default();
+
+            (Some(status.as_u16().into()), Some(text.chars().take(512).collect::<String>()), retry_after)
+        }
+        Err(err) => (None, Some(err.to_string()), None),
+    };
+
+    if attempts >= MAX_ATTEMPTS {
+        error!("Dead-lettering webhook delivery {} after {} attempts", delivery.id, attempts);
+        database.finish_webhook_delivery(
+            delivery.id,
+            models::WebhookDeliveryStatus::DeadLetter,
+            status_code,
+            err,
+            None,
+        ).await;
+    } else {
+        database.finish_webhook_delivery(
+            delivery.id,
+            models::WebhookDeliveryStatus::Pending,
+            status_code,
+            err,
+            Some(chrono::Utc::now() + backoff(attempts, retry_after)),
+        ).await;
+    }
+}
+
+/// Lists recent webhook deliveries for a bot
+#[get("/users/{user_id}/bots/{bot_id}/webhooks")]
+async fn get_bot_webhook_deliveries(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    query: web::Query<models::CursorQuery>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Get Bot Webhook Deliveries Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_webhook_deliveries(info.bot_id, models::TargetType::Bot, cursor.as_ref(), models::Cursor::limit(query.limit, 25))
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::Ok().json(models::WebhookDeliveryList {
+            deliveries: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Queues a event to be sent again to a bots webhook
+#[post("/users/{user_id}/bots/{bot_id}/webhooks/{eid}/redeliver")]
+async fn redeliver_bot_webhook(
+    req: HttpRequest,
+    info: web::Path<models::RedeliverBotWebhookPath>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Redeliver Bot Webhook Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let res = data
+        .database
+        .redeliver_webhook(info.bot_id, models::TargetType::Bot, &info.eid)
+        .await;
+
+    match res {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Lists recent webhook deliveries for a server
+#[get("/servers/{id}/webhooks")]
+async fn get_server_webhook_deliveries(
+    req: HttpRequest,
+    info: web::Path<models::FetchBotPath>,
+    query: web::Query<models::CursorQuery>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Get Server Webhook Deliveries Auth error");
+        return err.error_response();
+    }
+
+    let cursor = match models::Cursor::from_query(&query.cursor) {
+        Ok(cursor) => cursor,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    let res = data
+        .database
+        .get_webhook_deliveries(info.id, models::TargetType::Server, cursor.as_ref(), models::Cursor::limit(query.limit, 25))
+        .await;
+
+    match res {
+        Ok(page) => HttpResponse::Ok().json(models::WebhookDeliveryList {
+            deliveries: page.items,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Queues a event to be sent again to a servers webhook
+#[post("/servers/{id}/webhooks/{eid}/redeliver")]
+async fn redeliver_server_webhook(
+    req: HttpRequest,
+    info: web::Path<models::RedeliverServerWebhookPath>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Redeliver Server Webhook Auth error");
+        return err.error_response();
+    }
+
+    let res = data
+        .database
+        .redeliver_webhook(info.id, models::TargetType::Server, &info.eid)
+        .await;
+
+    match res {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Returns the events a bots webhook is subscribed to
+#[get("/users/{user_id}/bots/{bot_id}/webhooks/events")]
+async fn get_bot_webhook_events(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Get Bot Webhook Events Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    match data.database.get_webhook_events(info.bot_id, models::TargetType::Bot).await {
+        Ok(mask) => HttpResponse::Ok().json(models::WebhookEvents {
+            events: models::EventName::from_mask(mask),
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Sets the events a bots webhook is subscribed to
+#[put("/users/{user_id}/bots/{bot_id}/webhooks/events")]
+async fn set_bot_webhook_events(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    events: web::Json<models::WebhookEvents>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Set Bot Webhook Events Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let mask = models::EventName::to_mask(&events.events);
+
+    match data.database.set_webhook_events(info.bot_id, models::TargetType::Bot, mask).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Returns the events a servers webhook is subscribed to
+#[get("/servers/{id}/webhooks/events")]
+async fn get_server_webhook_events(
+    req: HttpRequest,
+    info: web::Path<models::FetchBotPath>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Get Server Webhook Events Auth error");
+        return err.error_response();
+    }
+
+    match data.database.get_webhook_events(info.id, models::TargetType::Server).await {
+        Ok(mask) => HttpResponse::Ok().json(models::WebhookEvents {
+            events: models::EventName::from_mask(mask),
+        }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Sets the events a servers webhook is subscribed to
+#[put("/servers/{id}/webhooks/events")]
+async fn set_server_webhook_events(
+    req: HttpRequest,
+    info: web::Path<models::FetchBotPath>,
+    events: web::Json<models::WebhookEvents>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Set Server Webhook Events Auth error");
+        return err.error_response();
+    }
+
+    let mask = models::EventName::to_mask(&events.events);
+
+    match data.database.set_webhook_events(info.id, models::TargetType::Server, mask).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Checks a custom webhook secret (or creates a random one)
+fn new_webhook_secret(rotate: &models::RotateWebhookSecret) -> Result<String, models::WebhookError> {
+    match &rotate.secret {
+        Some(secret) if secret.len() < 16 || secret.len() > 512 || secret.contains(',') => {
+            Err(models::WebhookError::InvalidSecret)
+        }
+        Some(secret) => Ok(secret.clone()),
+        None => Ok(converters::create_token(64)),
+    }
+}
+
+/// Rotates a bots webhook secret, the old secret keeps being signed with until it is cleared
+#[post("/users/{user_id}/bots/{bot_id}/webhooks/secret/rotate")]
+async fn rotate_bot_webhook_secret(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    rotate: web::Json<models::RotateWebhookSecret>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Rotate Bot Webhook Secret Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    let secret = match new_webhook_secret(&rotate) {
+        Ok(secret) => secret,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    match data.database.rotate_webhook_secret(info.bot_id, models::TargetType::Bot, &secret).await {
+        Ok(()) => HttpResponse::Ok().json(models::WebhookSecret { secret }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Stops signing a bots webhooks with the old (secondary) secret
+#[delete("/users/{user_id}/bots/{bot_id}/webhooks/secret/secondary")]
+async fn clear_bot_secondary_webhook_secret(
+    req: HttpRequest,
+    info: web::Path<models::GetUserBotPath>,
+    auth: auth::UserAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.user_id) {
+        error!("Clear Bot Secondary Webhook Secret Auth error");
+        return err.error_response();
+    }
+
+    if !data.database.is_bot_owner(info.bot_id, info.user_id).await {
+        return HttpResponse::build(http::StatusCode::FORBIDDEN).json(models::APIResponse::err_small(&models::GenericError::Forbidden));
+    }
+
+    match data.database.clear_secondary_webhook_secret(info.bot_id, models::TargetType::Bot).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Rotates a servers webhook secret, the old secret keeps being signed with until it is cleared
+#[post("/servers/{id}/webhooks/secret/rotate")]
+async fn rotate_server_webhook_secret(
+    req: HttpRequest,
+    info: web::Path<models::FetchBotPath>,
+    rotate: web::Json<models::RotateWebhookSecret>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Rotate Server Webhook Secret Auth error");
+        return err.error_response();
+    }
+
+    let secret = match new_webhook_secret(&rotate) {
+        Ok(secret) => secret,
+        Err(err) => return HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    };
+
+    match data.database.rotate_webhook_secret(info.id, models::TargetType::Server, &secret).await {
+        Ok(()) => HttpResponse::Ok().json(models::WebhookSecret { secret }),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
+
+/// Stops signing a servers webhooks with the old (secondary) secret
+#[delete("/servers/{id}/webhooks/secret/secondary")]
+async fn clear_server_secondary_webhook_secret(
+    req: HttpRequest,
+    info: web::Path<models::FetchBotPath>,
+    auth: auth::ServerAuth,
+) -> HttpResponse {
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    if let Err(err) = auth.ensure(info.id) {
+        error!("Clear Server Secondary Webhook Secret Auth error");
+        return err.error_response();
+    }
+
+    match data.database.clear_secondary_webhook_secret(info.id, models::TargetType::Server).await {
+        Ok(()) => HttpResponse::Ok().json(models::APIResponse::ok()),
+        Err(err) => HttpResponse::build(http::StatusCode::BAD_REQUEST).json(models::APIResponse::err_small(&err)),
+    }
+}
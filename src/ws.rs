@@ -1,12 +1,19 @@
 use actix_web::{get, web, Error, HttpRequest, HttpResponse};
-use std::time::{Instant};
+use std::time::{Duration, Instant};
 
 use crate::converters;
+use crate::database;
 use crate::models;
+use actix_rt::time;
 use actix_ws::Message;
+use futures::future;
 use futures::StreamExt;
+use indexmap::IndexMap;
 use log::{error};
+use serde::Serialize;
+use serde_json::json;
 use sqlx::postgres::PgPool;
+use std::collections::HashSet;
 
 #[get("/ws/_preview")]
 pub async fn preview_description(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, Error> {
@@ -79,35 +86,363 @@ pub async fn preview_description(req: HttpRequest, body: web::Payload) -> Result
     Ok(response)
 }
 
-async fn bot_gateway_task_sub(mode: models::TargetType, id: i64, session: actix_ws::Session) {
-    let client = redis::Client::open("redis://127.0.0.1:1001/1").unwrap();
-
-    let mut pubsub_conn = client.get_async_connection().await.unwrap().into_pubsub();
-    
+/// The redis channel events of a bot/server are published to
+fn target_channel(mode: models::TargetType, id: &str) -> String {
     let mode = match mode {
         models::TargetType::Bot => "bot",
         models::TargetType::Server => "server",
     };
 
+    mode.to_string() + "-" + id
+}
+
+/// Opens a new redis pubsub connection, logging why if it can't be
+async fn pubsub_connection() -> Option<redis::aio::PubSub> {
+    let client = match redis::Client::open("redis://127.0.0.1:1001/1") {
+        Ok(client) => client,
+        Err(err) => {
+            error!("Failed to open redis client: {}", err);
+            return None;
+        }
+    };
+
+    match client.get_async_connection().await {
+        Ok(conn) => Some(conn.into_pubsub()),
+        Err(err) => {
+            error!("Failed to connect to redis: {}", err);
+            None
+        }
+    }
+}
+
+/// Subscribes to the redis channel events of a bot/server are published to
+async fn gateway_pubsub(mode: models::TargetType, id: i64) -> Option<redis::aio::PubSub> {
+    let mut pubsub_conn = pubsub_connection().await?;
+
     let res = pubsub_conn
-        .subscribe(mode.to_string() + "-" + &id.to_string())
+        .subscribe(target_channel(mode, &id.to_string()))
         .await;
 
     if res.is_err() {
         error!("{}", res.err().unwrap());
+        return None;
+    }
+
+    Some(pubsub_conn)
+}
+
+/// Gets the sequence number of a published event (``{eid: event}``)
+fn event_seq(msg: &str) -> Option<i64> {
+    let event: IndexMap<String, models::Event<serde_json::Value>> = serde_json::from_str(msg).ok()?;
+
+    event.values().next().map(|event| event.m.seq)
+}
+
+/// Converts a published event (``{eid: event}``) to what is sent to the client, ``None`` if it 
+/// should not be sent (as it doesn't match ``filter``). The legacy protocol gets it as is while 
+/// the JSON gateway gets a Dispatch frame
+fn gateway_message(msg: String, legacy: bool, filter: Option<&models::GatewayFilter>) -> Option<String> {
+    if legacy && filter.is_none() {
+        return Some(msg);
+    }
+
+    let event: IndexMap<String, models::Event<serde_json::Value>> = serde_json::from_str(&msg).ok()?;
+    let (_, event) = event.into_iter().next()?;
+
+    if let Some(filter) = filter {
+        if !filter.matches(&event) {
+            return None;
+        }
+    }
+
+    if legacy {
+        return Some(msg);
+    }
+
+    serde_json::to_string(&models::GatewayFrame {
+        op: models::GatewayOp::Dispatch,
+        s: event.m.e.sequenced().then(|| event.m.seq),
+        t: Some(event.m.e),
+        d: json!(event),
+    })
+    .ok()
+}
+
+fn gateway_frame<T: Serialize>(op: models::GatewayOp, d: T) -> String {
+    serde_json::to_string(&models::GatewayFrame {
+        op,
+        d: json!(d),
+        s: None,
+        t: None,
+    })
+    .unwrap()
+}
+
+async fn bot_gateway_task_sub(
+    mode: models::TargetType,
+    id: i64,
+    legacy: bool,
+    filter: Option<models::GatewayFilter>,
+    session: actix_ws::Session,
+) {
+    let mut pubsub_conn = match gateway_pubsub(mode, id).await {
+        Some(pubsub_conn) => pubsub_conn,
+        None => return invalid_session(session, 4000, "Failed to subscribe to events").await,
+    };
+
+    let mut session = session.clone();
+
+    let ready = if legacy {
+        "GWTASK LISTEN".to_string()
+    } else {
+        gateway_frame(models::GatewayOp::Ready, models::GatewayReady { resumed: false, targets: None })
+    };
+
+    if session.text(ready).await.is_err() {
         return;
     }
 
+    while let Some(msg) = pubsub_conn.on_message().next().await {
+        let msg: Result<String, _> = msg.get_payload();
+        if msg.is_err() {
+            continue;
+        }
+        let msg = match gateway_message(msg.unwrap(), legacy, filter.as_ref()) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        if session.text(msg).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Replays all events after ``seq`` and then switches to the live subscription
+///
+/// We subscribe *before* reading ``ws_events`` so nothing published in between is lost, live events
+/// that were already replayed are then skipped. Events are persisted before being published so an 
+/// event is always either replayed, received live or both
+async fn bot_gateway_task_resume(
+    pool: PgPool,
+    mode: models::TargetType,
+    id: i64,
+    seq: i64,
+    legacy: bool,
+    filter: Option<models::GatewayFilter>,
+    session: actix_ws::Session,
+) {
+    let mut pubsub_conn = match gateway_pubsub(mode, id).await {
+        Some(pubsub_conn) => pubsub_conn,
+        None => return invalid_session(session, 4000, "Failed to subscribe to events").await,
+    };
+
     let mut session = session.clone();
 
-    session.text("GWTASK LISTEN").await.unwrap();
+    if legacy && session.text("GWTASK RESUME").await.is_err() {
+        return;
+    }
+
+    let mode = match mode {
+        models::TargetType::Bot => "bot",
+        models::TargetType::Server => "server",
+    };
+
+    let rows = sqlx::query!(
+        "SELECT event, seq AS \"seq!\" FROM ws_events WHERE id = $1 AND type = $2 AND seq > $3 ORDER BY seq",
+        id,
+        mode,
+        seq
+    )
+    .fetch_all(&pool)
+    .await;
+
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("{} {}", id.to_string(), err);
+            return;
+        }
+    };
+
+    let mut replayed = HashSet::new();
+
+    for row in rows {
+        replayed.insert(row.seq);
+
+        let event = serde_json::to_string(&row.event);
+        if event.is_err() {
+            error!("{} {}", id.to_string(), event.err().unwrap());
+            continue;
+        }
+        let event = match gateway_message(event.unwrap(), legacy, filter.as_ref()) {
+            Some(event) => event,
+            None => continue,
+        };
+        if session.text(event).await.is_err() {
+            return;
+        }
+    }
+
+    let ready = if legacy {
+        "GWTASKACK RESUME".to_string()
+    } else {
+        gateway_frame(models::GatewayOp::Ready, models::GatewayReady { resumed: true, targets: None })
+    };
+
+    if session.text(ready).await.is_err() {
+        return;
+    }
 
     while let Some(msg) = pubsub_conn.on_message().next().await {
         let msg: Result<String, _> = msg.get_payload();
         if msg.is_err() {
             continue;
         }
-        if session.text(msg.unwrap()).await.is_err() {
+        let msg = msg.unwrap();
+
+        if let Some(seq) = event_seq(&msg) {
+            if replayed.contains(&seq) {
+                continue;
+            }
+        }
+
+        let msg = match gateway_message(msg, legacy, filter.as_ref()) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        if session.text(msg).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// The bots/servers a user gateway session should receive events for
+async fn user_gateway_targets(
+    database: &database::Database,
+    user_id: i64,
+    only: &Option<Vec<models::GatewayTarget>>,
+) -> Vec<models::GatewayTarget> {
+    let mut targets = database.get_user_targets(user_id).await;
+
+    if let Some(only) = only {
+        targets.retain(|target| only.contains(target));
+    }
+
+    targets
+}
+
+/// Multiplexes the events of every bot/server a user owns (or ``only`` these) over one session
+///
+/// Ownership changes are published to ``user-{id}`` (see ``Database::notify_owner_change``), 
+/// on which the targets are fetched again and the subscriptions updated
+async fn user_gateway_task(
+    database: database::Database,
+    user_id: i64,
+    only: Option<Vec<models::GatewayTarget>>,
+    filter: Option<models::GatewayFilter>,
+    session: actix_ws::Session,
+) {
+    let mut pubsub_conn = match pubsub_connection().await {
+        Some(pubsub_conn) => pubsub_conn,
+        None => return invalid_session(session, 4000, "Failed to subscribe to events").await,
+    };
+
+    let user_channel = "user-".to_string() + &user_id.to_string();
+
+    if let Err(err) = pubsub_conn.subscribe(&user_channel).await {
+        error!("{}", err);
+        return invalid_session(session, 4000, "Failed to subscribe to events").await;
+    }
+
+    let targets = user_gateway_targets(&database, user_id, &only).await;
+
+    let mut channels = HashSet::new();
+
+    for target in &targets {
+        let channel = target_channel(target.target_type, &target.id);
+        if let Err(err) = pubsub_conn.subscribe(&channel).await {
+            error!("{}", err);
+            return invalid_session(session, 4000, "Failed to subscribe to events").await;
+        }
+        channels.insert(channel);
+    }
+
+    let mut session = session.clone();
+
+    let ready = gateway_frame(
+        models::GatewayOp::Ready,
+        models::GatewayReady {
+            resumed: false,
+            targets: Some(targets),
+        },
+    );
+
+    if session.text(ready).await.is_err() {
+        return;
+    }
+
+    loop {
+        let msg = match pubsub_conn.on_message().next().await {
+            Some(msg) => msg,
+            None => return,
+        };
+
+        let channel = msg.get_channel_name().to_string();
+
+        if channel == user_channel {
+            // Owned bots/servers changed, update subscriptions
+            let targets = user_gateway_targets(&database, user_id, &only).await;
+
+            let new_channels: HashSet<String> = targets
+                .iter()
+                .map(|target| target_channel(target.target_type, &target.id))
+                .collect();
+
+            for channel in new_channels.difference(&channels) {
+                if let Err(err) = pubsub_conn.subscribe(channel).await {
+                    error!("{}", err);
+                    return;
+                }
+            }
+
+            for channel in channels.difference(&new_channels) {
+                if let Err(err) = pubsub_conn.unsubscribe(channel).await {
+                    error!("{}", err);
+                    return;
+                }
+            }
+
+            channels = new_channels;
+
+            let ready = gateway_frame(
+                models::GatewayOp::Ready,
+                models::GatewayReady {
+                    resumed: false,
+                    targets: Some(targets),
+                },
+            );
+
+            if session.text(ready).await.is_err() {
+                return;
+            }
+
+            continue;
+        }
+
+        // Could have been sent before unsubscribing
+        if !channels.contains(&channel) {
+            continue;
+        }
+
+        let msg: Result<String, _> = msg.get_payload();
+        if msg.is_err() {
+            continue;
+        }
+        let msg = match gateway_message(msg.unwrap(), false, filter.as_ref()) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        if session.text(msg).await.is_err() {
             return;
         }
     }
@@ -149,6 +484,226 @@ async fn bot_gateway_task_archive(pool: PgPool, mode: models::TargetType, id: i6
     }
 }
 
+const GATEWAY_VERSION: i32 = 1;
+
+/// How often clients must send a Heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(45);
+
+/// Clients that don't send a Heartbeat in this time are disconnected, this gives
+/// some leeway over ``HEARTBEAT_INTERVAL`` for slow networks
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Sends a InvalidSession frame and closes the connection
+async fn invalid_session(mut session: actix_ws::Session, code: u16, reason: &str) {
+    let _ = session
+        .text(gateway_frame(
+            models::GatewayOp::InvalidSession,
+            models::GatewayInvalidSession {
+                reason: reason.to_string(),
+            },
+        ))
+        .await;
+
+    let _ = session
+        .close(Some(actix_ws::CloseReason {
+            code: actix_ws::CloseCode::Other(code),
+            description: Some(reason.to_string()),
+        }))
+        .await;
+}
+
+/// What a JSON gateway session receives events for
+#[derive(Clone, Copy)]
+enum GatewayScope {
+    /// A single bot/server, authenticated using its token
+    Target(models::TargetType, i64),
+    /// Every bot/server a user owns, authenticated using a user token
+    User(i64),
+}
+
+/// Checks a gateway token. Scoped API tokens need the ``votes:read`` scope
+async fn gateway_authorize(database: &database::Database, scope: GatewayScope, token: &str) -> bool {
+    let (resolved, principal) = match scope {
+        GatewayScope::Target(models::TargetType::Bot, id) => (database.resolve_bot_token(token).await, models::Principal::Bot(id)),
+        GatewayScope::Target(models::TargetType::Server, id) => (database.resolve_server_token(token).await, models::Principal::Server(id)),
+        GatewayScope::User(user_id) => (database.resolve_user_token(token).await, models::Principal::User(user_id)),
+    };
+
+    match resolved {
+        Some(resolved) => {
+            let principal_matches = match resolved.principal {
+                models::Principal::FrostpawClient { user_id, .. } => principal == models::Principal::User(user_id),
+                ref got => *got == principal,
+            };
+            principal_matches && resolved.allows(models::TokenScope::VotesRead)
+        }
+        None => false,
+    }
+}
+
+/// The JSON gateway. A Hello is sent on connect, the client must then Identify (or Resume)
+/// and send a Heartbeat every ``heartbeat_interval`` milliseconds
+async fn json_gateway(
+    database: database::Database,
+    mut session: actix_ws::Session,
+    mut msg_stream: actix_ws::MessageStream,
+    scope: GatewayScope,
+    version: i32,
+) {
+    if version != GATEWAY_VERSION {
+        invalid_session(session, 4012, "Unsupported gateway version").await;
+        return;
+    }
+
+    let hello = gateway_frame(
+        models::GatewayOp::Hello,
+        models::GatewayHello {
+            v: GATEWAY_VERSION,
+            heartbeat_interval: HEARTBEAT_INTERVAL.as_millis() as u64,
+        },
+    );
+
+    if session.text(hello).await.is_err() {
+        return;
+    }
+
+    let mut gw_task: Option<actix_rt::task::JoinHandle<()>> = None;
+    let mut last_heartbeat = time::Instant::now();
+
+    // Reason to send a InvalidSession and close with, if any
+    let mut invalid: Option<(u16, &str)> = None;
+
+    loop {
+        let timeout = Box::pin(time::sleep_until(last_heartbeat + HEARTBEAT_TIMEOUT));
+
+        let msg = match future::select(msg_stream.next(), timeout).await {
+            future::Either::Left((Some(Ok(msg)), _)) => msg,
+            future::Either::Left(_) => break,
+            future::Either::Right(_) => {
+                invalid = Some((4009, "Heartbeat timed out"));
+                break;
+            }
+        };
+
+        let text = match msg {
+            Message::Ping(bytes) => {
+                if session.pong(&bytes).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Message::Pong(_) => continue,
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => {
+                invalid = Some((4003, "Only text frames are supported"));
+                break;
+            }
+        };
+
+        let frame: models::GatewayFrame = match serde_json::from_str(&text) {
+            Ok(frame) => frame,
+            Err(_) => {
+                invalid = Some((4003, "Invalid frame"));
+                break;
+            }
+        };
+
+        match frame.op {
+            models::GatewayOp::Heartbeat => {
+                last_heartbeat = time::Instant::now();
+                if session.text(gateway_frame(models::GatewayOp::HeartbeatAck, json!(null))).await.is_err() {
+                    break;
+                }
+            }
+            models::GatewayOp::Identify | models::GatewayOp::Resume => {
+                if gw_task.is_some() {
+                    invalid = Some((4005, "Already identified"));
+                    break;
+                }
+
+                if frame.op == models::GatewayOp::Identify {
+                    let identify: models::GatewayIdentify = match serde_json::from_value(frame.d) {
+                        Ok(identify) => identify,
+                        Err(_) => {
+                            invalid = Some((4003, "Invalid identify payload"));
+                            break;
+                        }
+                    };
+
+                    if !gateway_authorize(&database, scope, &identify.token).await {
+                        invalid = Some((4004, "Authentication failed"));
+                        break;
+                    }
+
+                    gw_task = Some(match scope {
+                        GatewayScope::Target(mode, id) => actix_rt::spawn(bot_gateway_task_sub(
+                            mode,
+                            id,
+                            false,
+                            identify.filter,
+                            session.clone(),
+                        )),
+                        GatewayScope::User(user_id) => actix_rt::spawn(user_gateway_task(
+                            database.clone(),
+                            user_id,
+                            identify.targets,
+                            identify.filter,
+                            session.clone(),
+                        )),
+                    });
+                } else {
+                    let (mode, id) = match scope {
+                        GatewayScope::Target(mode, id) => (mode, id),
+                        GatewayScope::User(_) => {
+                            invalid = Some((4003, "Resume is not supported on the user gateway"));
+                            break;
+                        }
+                    };
+
+                    let resume: models::GatewayResume = match serde_json::from_value(frame.d) {
+                        Ok(resume) if resume.seq >= 0 => resume,
+                        _ => {
+                            invalid = Some((4003, "Invalid resume payload"));
+                            break;
+                        }
+                    };
+
+                    if !gateway_authorize(&database, scope, &resume.token).await {
+                        invalid = Some((4004, "Authentication failed"));
+                        break;
+                    }
+
+                    gw_task = Some(actix_rt::spawn(bot_gateway_task_resume(
+                        database.get_postgres(),
+                        mode,
+                        id,
+                        resume.seq,
+                        false,
+                        resume.filter,
+                        session.clone(),
+                    )));
+                }
+            }
+            _ => {
+                invalid = Some((4003, "Unknown opcode"));
+                break;
+            }
+        }
+    }
+
+    if let Some(task) = gw_task {
+        task.abort();
+    }
+
+    match invalid {
+        Some((code, reason)) => invalid_session(session, code, reason).await,
+        None => {
+            let _ = session.close(None).await;
+        }
+    }
+}
+
 #[get("/ws/{id}")]
 pub async fn bot_ws(
     req: HttpRequest,
@@ -160,6 +715,22 @@ pub async fn bot_ws(
 
     let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
 
+    let id = id.into_inner();
+    let query = mode.into_inner();
+    let mode = query.mode;
+
+    if !query.legacy.unwrap_or(false) {
+        actix_rt::spawn(json_gateway(
+            data.database.clone(),
+            session,
+            msg_stream,
+            GatewayScope::Target(mode, id),
+            query.v.unwrap_or(GATEWAY_VERSION),
+        ));
+
+        return Ok(response);
+    }
+
     let database = std::rc::Rc::new(data.database.clone());
 
     //let pool = data.database.get_postgres();
@@ -169,8 +740,6 @@ pub async fn bot_ws(
     let mut auth = false;
 
     actix_rt::spawn(async move {
-        let id = id.into_inner();
-        let mode = mode.into_inner().mode;
         let mut hb = Instant::now();
 
         while let Some(Ok(msg)) = msg_stream.next().await {
@@ -186,19 +755,9 @@ pub async fn bot_ws(
                 Message::Text(text) => {
                     if text.starts_with("AUTH") {
                         let token = text.split(' ').nth(1).unwrap_or("unknown");
-                        match mode {
-                            models::TargetType::Bot => {
-                                if database.authorize_bot(id, token).await {
-                                    auth = true;
-                                    continue;        
-                                }
-                            },
-                            models::TargetType::Server => {
-                                if database.authorize_server(id, token).await {
-                                    auth = true;
-                                    continue;
-                                }
-                            }
+                        if gateway_authorize(&database, GatewayScope::Target(mode, id), token).await {
+                            auth = true;
+                            continue;
                         }
 
                         close_reason = Some(actix_ws::CloseReason {
@@ -216,7 +775,7 @@ pub async fn bot_ws(
                             .await
                             .is_err() {
                         break;
-                    } else if text == "SUB" {
+                    } else if text == "SUB" || text.starts_with("SUB ") {
                         if gw_task.is_some() {
                             // Error out, you can only have one gateway task per session
                             close_reason = Some(actix_ws::CloseReason {
@@ -225,10 +784,28 @@ pub async fn bot_ws(
                             });
                             break;
                         }
+
+                        // SUB {"events": [0], "user": "...", "exclude_test": true}
+                        let filter = match text.split_once(' ') {
+                            Some((_, filter)) => match serde_json::from_str::<models::GatewayFilter>(filter) {
+                                Ok(filter) => Some(filter),
+                                Err(_) => {
+                                    close_reason = Some(actix_ws::CloseReason {
+                                        code: actix_ws::CloseCode::Other(4002),
+                                        description: Some("Invalid filter".to_string())
+                                    });
+                                    break;
+                                }
+                            },
+                            None => None,
+                        };
+
                         // Subscribe to messages sent to the bots websocket channel
                         gw_task = Some(actix_rt::spawn(bot_gateway_task_sub(
                             mode,
                             id,
+                            true,
+                            filter,
                             session.clone(),
                         )));
                     } else if text == "ARCHIVE" {
@@ -247,6 +824,60 @@ pub async fn bot_ws(
                             id,
                             session.clone()
                         )));
+                    } else if text.starts_with("RESUME") {
+                        if gw_task.is_some() {
+                            // Error out, you can only have one gateway task per session
+                            close_reason = Some(actix_ws::CloseReason {
+                                code: actix_ws::CloseCode::Other(4001),
+                                description: Some("You can only have one gateway task per session at any given time".to_string())
+                            });
+                            break;
+                        }
+
+                        if !auth {
+                            close_reason = Some(actix_ws::CloseReason {
+                                code: actix_ws::CloseCode::Other(4002),
+                                description: Some("You must authenticate before resuming".to_string())
+                            });
+                            break;
+                        }
+
+                        let seq = match text.split(' ').nth(1).map(|seq| seq.parse::<i64>()) {
+                            Some(Ok(seq)) if seq >= 0 => seq,
+                            _ => {
+                                close_reason = Some(actix_ws::CloseReason {
+                                    code: actix_ws::CloseCode::Other(4002),
+                                    description: Some("Invalid sequence number".to_string())
+                                });
+                                break;
+                            }
+                        };
+
+                        // RESUME <seq> {"events": [0], "user": "...", "exclude_test": true}
+                        let filter = match text.splitn(3, ' ').nth(2) {
+                            Some(filter) => match serde_json::from_str::<models::GatewayFilter>(filter) {
+                                Ok(filter) => Some(filter),
+                                Err(_) => {
+                                    close_reason = Some(actix_ws::CloseReason {
+                                        code: actix_ws::CloseCode::Other(4002),
+                                        description: Some("Invalid filter".to_string())
+                                    });
+                                    break;
+                                }
+                            },
+                            None => None,
+                        };
+
+                        // Replay missed events and then subscribe
+                        gw_task = Some(actix_rt::spawn(bot_gateway_task_resume(
+                            database.get_postgres(),
+                            mode,
+                            id,
+                            seq,
+                            true,
+                            filter,
+                            session.clone()
+                        )));
                     } else if text == "ENDGWTASK" {
                         if gw_task.is_none() {
                             // Error out, cannot UNSUB if you are not subscribed
@@ -292,3 +923,26 @@ pub async fn bot_ws(
 
     Ok(response)
 }
+
+/// A JSON gateway session for every bot/server a user owns
+#[get("/ws/users/{id}")]
+pub async fn user_ws(
+    req: HttpRequest,
+    id: web::Path<i64>,
+    query: web::Query<models::UserWsQuery>,
+    body: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+
+    let data: &models::AppState = req.app_data::<web::Data<models::AppState>>().unwrap();
+
+    actix_rt::spawn(json_gateway(
+        data.database.clone(),
+        session,
+        msg_stream,
+        GatewayScope::User(id.into_inner()),
+        query.v.unwrap_or(GATEWAY_VERSION),
+    ));
+
+    Ok(response)
+}